@@ -0,0 +1,54 @@
+use crate::man_db::{BUNDLE_INDEX, BUNDLE_PAGES, ManDb, bundle_page_path};
+use anyhow::{Result, anyhow};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+
+/// Renders every indexed man page into dir with an index `--bundle` can load.
+/// Returns how many pages were written
+pub async fn create(man_db: Arc<ManDb>, dir: &Path) -> Result<usize> {
+    let pages = dir.join(BUNDLE_PAGES);
+    std::fs::create_dir_all(&pages)
+        .map_err(|e| anyhow!("Failed to create {}: {e}", pages.display()))?;
+
+    let mut tasks = JoinSet::new();
+    for command in man_db.get_commands().clone() {
+        // Names become file names, so skip any that could leave the pages directory
+        if command.contains(['/', '\\']) || command.starts_with('.') {
+            continue;
+        }
+        let Some(section) = man_db.get_section(&command).map(String::from) else {
+            continue;
+        };
+        let man_db = man_db.clone();
+        tasks.spawn(async move {
+            // ManDb caps how many `man` processes run at once, and each page is
+            // dropped once written instead of staying in the memory cache
+            let page = man_db.scan_man_page(&command).await;
+            (command, section, page)
+        });
+    }
+
+    let mut index = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        let Ok((command, section, page)) = result else {
+            continue;
+        };
+        if page.failed {
+            log::warn!("Left {command}({section}) out of the bundle, it failed to load");
+            continue;
+        }
+        let path = bundle_page_path(dir, &command, &section);
+        std::fs::write(&path, page.lines.join("\n"))
+            .map_err(|e| anyhow!("Failed to write {}: {e}", path.display()))?;
+        let description = man_db.get_description(&command).unwrap_or_default();
+        index.push(format!("{command} ({section}) - {description}"));
+    }
+
+    index.sort_unstable();
+    let path = dir.join(BUNDLE_INDEX);
+    let mut text = index.join("\n");
+    text.push('\n');
+    std::fs::write(&path, text).map_err(|e| anyhow!("Failed to write {}: {e}", path.display()))?;
+    Ok(index.len())
+}
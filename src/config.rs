@@ -0,0 +1,565 @@
+use crate::fuzzy::{CaseMode, MatchMode};
+use crate::man_db::FormFeeds;
+use crate::tui::{DescriptionSource, JumpTo, OnEnter, PreviewPosition};
+use anyhow::{Result, anyhow};
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Config file name inside the config directory
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Value parsed from the config file
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+    List(Vec<Value>),
+}
+
+/// User settings loaded from `config.toml`
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// Shell command producing `name (section) - description` lines
+    pub index_command: Option<String>,
+    /// Columns between tab stops in page content
+    pub tab_width: Option<usize>,
+    /// Skip loading pages while the filter matches more commands than this
+    pub max_preview_matches: Option<usize>,
+    /// Directory of `<command>.md` tldr pages
+    pub tldr_dir: Option<PathBuf>,
+    /// Days before local tldr pages count as stale, 0 to never check
+    pub tldr_max_age_days: Option<u64>,
+    /// Display limit for a content line in chars, 0 for unlimited
+    pub max_line_width: Option<usize>,
+    /// Widest text column for content, 0 for the full pane
+    pub max_width: Option<usize>,
+    /// Blank columns on each side of the content inside its border
+    pub content_padding: Option<usize>,
+    /// Algorithm used to filter the command list
+    pub match_mode: Option<MatchMode>,
+    /// How letter case affects filtering and content search
+    pub case_mode: Option<CaseMode>,
+    /// Rows kept between the selection or match and the pane edges
+    pub scroll_off: Option<usize>,
+    /// Most external commands run at once (default: number of CPUs)
+    pub max_concurrency: Option<usize>,
+    /// Most commands kept in the index, 0 for unlimited
+    pub max_index_size: Option<usize>,
+    /// Treat names differing only in letter case as one command
+    pub fold_case: Option<bool>,
+    /// Sections left out of the index
+    pub exclude_sections: Vec<String>,
+    /// Glob patterns of command names left out of the index
+    pub exclude_commands: Vec<String>,
+    /// Glob patterns of command names allowed into the index, empty for all
+    pub include_commands: Vec<String>,
+    /// Wrap long lines of man and info pages
+    pub wrap_man: Option<bool>,
+    /// Wrap long lines of tldr pages
+    pub wrap_tldr: Option<bool>,
+    /// Mark rows continuing a wrapped line
+    pub wrap_markers: Option<bool>,
+    /// Highlight the characters of each command the filter matched
+    pub highlight_matches: Option<bool>,
+    /// Collapse runs of blank lines in page content
+    pub compact_blank_lines: Option<bool>,
+    /// How far down the pane search matches are revealed, in percent
+    pub match_position: Option<usize>,
+    /// Scroll to the first match while typing a search
+    pub jump_while_searching: Option<bool>,
+    /// Where the content pane sits relative to the command list
+    pub preview: Option<PreviewPosition>,
+    /// Where a newly opened man page starts
+    pub jump_to: Option<JumpTo>,
+    /// What Enter does in the command list
+    pub on_enter: Option<OnEnter>,
+    /// Command line opening a terminal, `{cmd}` replaced by the command
+    pub launcher: Option<String>,
+    /// How page breaks in man output are shown
+    pub form_feeds: Option<FormFeeds>,
+    /// Text shown in the description box
+    pub description_source: Option<DescriptionSource>,
+    /// Color scheme file loaded over the default theme
+    pub theme_file: Option<PathBuf>,
+}
+
+impl Config {
+    /// Loads config from `path`, returning defaults if it doesn't exist
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).map_err(|e| anyhow!("{}: {e}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(anyhow!("Failed to read {}: {e}", path.display())),
+        }
+    }
+
+    /// Parses config text
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut config = Self::default();
+
+        for (key, value) in parse_table(text)? {
+            match key.as_str() {
+                "index_command" => config.index_command = Some(expect_str(&key, value)?),
+                "launcher" => config.launcher = Some(expect_str(&key, value)?),
+                "tab_width" => config.tab_width = Some(expect_usize(&key, value)?),
+                "tldr_dir" => config.tldr_dir = Some(PathBuf::from(expect_str(&key, value)?)),
+                "theme_file" => config.theme_file = Some(PathBuf::from(expect_str(&key, value)?)),
+                "match_mode" => {
+                    let name = expect_str(&key, value)?;
+                    let mode = MatchMode::from_str(&name, true)
+                        .map_err(|_| anyhow!("unknown match mode `{name}`"))?;
+                    config.match_mode = Some(mode);
+                }
+                "case" => {
+                    let name = expect_str(&key, value)?;
+                    let mode = CaseMode::from_str(&name, true)
+                        .map_err(|_| anyhow!("unknown case mode `{name}`"))?;
+                    config.case_mode = Some(mode);
+                }
+                "jump_to" => {
+                    let name = expect_str(&key, value)?;
+                    let target = JumpTo::from_str(&name, true)
+                        .map_err(|_| anyhow!("unknown jump target `{name}`"))?;
+                    config.jump_to = Some(target);
+                }
+                "on_enter" => {
+                    let name = expect_str(&key, value)?;
+                    let action = OnEnter::from_str(&name, true)
+                        .map_err(|_| anyhow!("unknown Enter action `{name}`"))?;
+                    config.on_enter = Some(action);
+                }
+                "preview" => {
+                    let name = expect_str(&key, value)?;
+                    let position = PreviewPosition::from_str(&name, true)
+                        .map_err(|_| anyhow!("unknown preview position `{name}`"))?;
+                    config.preview = Some(position);
+                }
+                "form_feeds" => {
+                    let name = expect_str(&key, value)?;
+                    let mode = FormFeeds::from_str(&name, true)
+                        .map_err(|_| anyhow!("unknown form_feeds mode `{name}`"))?;
+                    config.form_feeds = Some(mode);
+                }
+                "description_source" => {
+                    let name = expect_str(&key, value)?;
+                    let source = DescriptionSource::from_str(&name, true)
+                        .map_err(|_| anyhow!("unknown description source `{name}`"))?;
+                    config.description_source = Some(source);
+                }
+                "max_width" => config.max_width = Some(expect_usize(&key, value)?),
+                "content_padding" => config.content_padding = Some(expect_usize(&key, value)?),
+                "max_line_width" => config.max_line_width = Some(expect_usize(&key, value)?),
+                "max_preview_matches" => {
+                    config.max_preview_matches = Some(expect_usize(&key, value)?)
+                }
+                "exclude_sections" => config.exclude_sections = expect_str_list(&key, value)?,
+                "exclude_commands" => config.exclude_commands = expect_str_list(&key, value)?,
+                "include_commands" => config.include_commands = expect_str_list(&key, value)?,
+                "tldr_max_age_days" => {
+                    config.tldr_max_age_days = Some(expect_usize(&key, value)? as u64)
+                }
+                "max_concurrency" => config.max_concurrency = Some(expect_usize(&key, value)?),
+                "max_index_size" => config.max_index_size = Some(expect_usize(&key, value)?),
+                "fold_case" => config.fold_case = Some(expect_bool(&key, value)?),
+                "scroll_off" => config.scroll_off = Some(expect_usize(&key, value)?),
+                "wrap_man" => config.wrap_man = Some(expect_bool(&key, value)?),
+                "wrap_tldr" => config.wrap_tldr = Some(expect_bool(&key, value)?),
+                "match_position" => {
+                    config.match_position = Some(match value {
+                        Value::Str(name) if name == "top" => 0,
+                        Value::Str(name) if name == "center" => 50,
+                        Value::Int(n @ 0..=100) => n as usize,
+                        other => {
+                            return Err(anyhow!(
+                                "`{key}` must be \"top\", \"center\" or a percentage, got {other:?}"
+                            ));
+                        }
+                    })
+                }
+                "wrap_markers" => config.wrap_markers = Some(expect_bool(&key, value)?),
+                "compact_blank_lines" => {
+                    config.compact_blank_lines = Some(expect_bool(&key, value)?)
+                }
+                "highlight_matches" => config.highlight_matches = Some(expect_bool(&key, value)?),
+                "jump_while_searching" => {
+                    config.jump_while_searching = Some(expect_bool(&key, value)?)
+                }
+                _ => return Err(anyhow!("unknown key `{key}`")),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+/// Sets top-level `key` in the config at `path`, keeping other lines intact
+pub fn store(path: &Path, key: &str, value: &Value) -> Result<()> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(anyhow!("Failed to read {}: {e}", path.display())),
+    };
+
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+    let entry = format!("{key} = {}", format_value(value));
+    // Top-level keys must come before the first `[table]`
+    let top_end = lines
+        .iter()
+        .position(|line| strip_comment(line).trim_start().starts_with('['))
+        .unwrap_or(lines.len());
+    let existing = lines[..top_end].iter().position(|line| {
+        strip_comment(line)
+            .split_once('=')
+            .is_some_and(|(k, _)| k.trim() == key)
+    });
+    match existing {
+        Some(idx) => lines[idx] = entry,
+        None => {
+            let after_last = lines[..top_end]
+                .iter()
+                .rposition(|line| !line.trim().is_empty())
+                .map_or(0, |idx| idx + 1);
+            lines.insert(after_last, entry);
+        }
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| anyhow!("Failed to create {}: {e}", dir.display()))?;
+    }
+    std::fs::write(path, lines.join("\n") + "\n")
+        .map_err(|e| anyhow!("Failed to write {}: {e}", path.display()))
+}
+
+/// Formats value as it would appear in the config file
+pub fn format_value(value: &Value) -> String {
+    match value {
+        Value::Str(s) => format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
+        Value::Int(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::List(items) => {
+            let items: Vec<String> = items.iter().map(format_value).collect();
+            format!("[{}]", items.join(", "))
+        }
+    }
+}
+
+/// Expects a list of strings, accepting numbers so sections can be bare
+fn expect_str_list(key: &str, value: Value) -> Result<Vec<String>> {
+    let Value::List(items) = value else {
+        return Err(anyhow!("`{key}` must be a list, got {value:?}"));
+    };
+
+    items
+        .into_iter()
+        .map(|item| match item {
+            Value::Str(s) => Ok(s),
+            Value::Int(n) => Ok(n.to_string()),
+            other => Err(anyhow!("`{key}` entries must be strings, got {other:?}")),
+        })
+        .collect()
+}
+
+fn expect_bool(key: &str, value: Value) -> Result<bool> {
+    match value {
+        Value::Bool(b) => Ok(b),
+        other => Err(anyhow!("`{key}` must be true or false, got {other:?}")),
+    }
+}
+
+fn expect_str(key: &str, value: Value) -> Result<String> {
+    match value {
+        Value::Str(s) => Ok(s),
+        other => Err(anyhow!("`{key}` must be a string, got {other:?}")),
+    }
+}
+
+fn expect_usize(key: &str, value: Value) -> Result<usize> {
+    match value {
+        Value::Int(n) if n >= 0 => Ok(n as usize),
+        other => Err(anyhow!(
+            "`{key}` must be a non-negative integer, got {other:?}"
+        )),
+    }
+}
+
+/// Parses `key = value` lines, prefixing keys with the current `[table]`
+pub fn parse_table(text: &str) -> Result<BTreeMap<String, Value>> {
+    let mut entries = BTreeMap::new();
+    let mut table = String::new();
+
+    for (idx, raw) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = strip_comment(raw).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            table = name.trim().to_string();
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| anyhow!("line {line_no}: expected `key = value`"))?;
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(anyhow!("line {line_no}: missing key"));
+        }
+
+        let mut chars = value.trim().chars().peekable();
+        let value = parse_value(&mut chars).map_err(|e| anyhow!("line {line_no}: {e}"))?;
+        if chars.any(|c| !c.is_whitespace()) {
+            return Err(anyhow!("line {line_no}: trailing characters after value"));
+        }
+
+        let full_key = if table.is_empty() {
+            key.to_string()
+        } else {
+            format!("{table}.{key}")
+        };
+        entries.insert(full_key, value);
+    }
+
+    Ok(entries)
+}
+
+/// Removes a trailing `#` comment that isn't inside a string
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (idx, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '#') => return &line[..idx],
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_value(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Value> {
+    skip_whitespace(chars);
+
+    match chars.peek() {
+        Some('"') => {
+            chars.next();
+            let mut out = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => return Ok(Value::Str(out)),
+                    Some('\\') => match chars.next() {
+                        Some('n') => out.push('\n'),
+                        Some('t') => out.push('\t'),
+                        Some(c @ ('"' | '\\')) => out.push(c),
+                        other => return Err(anyhow!("invalid escape {other:?}")),
+                    },
+                    Some(c) => out.push(c),
+                    None => return Err(anyhow!("unterminated string")),
+                }
+            }
+        }
+        Some('\'') => {
+            chars.next();
+            let out: String = chars.by_ref().take_while(|&c| c != '\'').collect();
+            Ok(Value::Str(out))
+        }
+        Some('[') => {
+            chars.next();
+            let mut items = Vec::new();
+            loop {
+                skip_whitespace(chars);
+                if chars.peek() == Some(&']') {
+                    chars.next();
+                    return Ok(Value::List(items));
+                }
+                items.push(parse_value(chars)?);
+                skip_whitespace(chars);
+                match chars.next() {
+                    Some(',') => {}
+                    Some(']') => return Ok(Value::List(items)),
+                    _ => return Err(anyhow!("expected `,` or `]` in list")),
+                }
+            }
+        }
+        Some(_) => {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c == ',' || c == ']' || c.is_whitespace() {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            match word.as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                _ => word
+                    .parse()
+                    .map(Value::Int)
+                    .map_err(|_| anyhow!("invalid value `{word}`")),
+            }
+        }
+        None => Err(anyhow!("missing value")),
+    }
+}
+
+fn skip_whitespace(chars: &mut std::iter::Peekable<std::str::Chars>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_values() {
+        let table = parse_table(
+            r#"
+            # comment
+            name = "a # not a comment"
+            count = 42
+            flag = true
+            list = ["x", 'y', 3]
+
+            [tui]
+            debounce = 150 # trailing
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(table["name"], Value::Str("a # not a comment".to_string()));
+        assert_eq!(table["count"], Value::Int(42));
+        assert_eq!(table["flag"], Value::Bool(true));
+        assert_eq!(
+            table["list"],
+            Value::List(vec![
+                Value::Str("x".to_string()),
+                Value::Str("y".to_string()),
+                Value::Int(3),
+            ])
+        );
+        assert_eq!(table["tui.debounce"], Value::Int(150));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_table("novalue").is_err());
+        assert!(parse_table("x = \"open").is_err());
+        assert!(parse_table("x = [1, 2").is_err());
+        assert!(Config::parse("unknown = 1").is_err());
+        assert!(Config::parse("index_command = 1").is_err());
+    }
+
+    #[test]
+    fn test_config_match_mode() {
+        let config = Config::parse("match_mode = \"smartcase-fuzzy\"").unwrap();
+        assert_eq!(config.match_mode, Some(MatchMode::SmartcaseFuzzy));
+        assert!(Config::parse("match_mode = \"regex\"").is_err());
+    }
+
+    #[test]
+    fn test_config_case() {
+        let config = Config::parse("case = \"sensitive\"").unwrap();
+        assert_eq!(config.case_mode, Some(CaseMode::Sensitive));
+        assert!(Config::parse("case = \"upper\"").is_err());
+    }
+
+    #[test]
+    fn test_config_match_position() {
+        let config = Config::parse("match_position = \"top\"").unwrap();
+        assert_eq!(config.match_position, Some(0));
+        let config = Config::parse("match_position = 25").unwrap();
+        assert_eq!(config.match_position, Some(25));
+        assert!(Config::parse("match_position = 150").is_err());
+        assert!(Config::parse("match_position = \"bottom\"").is_err());
+    }
+
+    #[test]
+    fn test_config_jump_to() {
+        let config = Config::parse("jump_to = \"options\"").unwrap();
+        assert_eq!(config.jump_to, Some(JumpTo::Options));
+        assert!(Config::parse("jump_to = \"examples\"").is_err());
+
+        let config = Config::parse("on_enter = \"print\"").unwrap();
+        assert_eq!(config.on_enter, Some(OnEnter::Print));
+        assert!(Config::parse("on_enter = \"open\"").is_err());
+    }
+
+    #[test]
+    fn test_config_preview() {
+        let config = Config::parse("preview = \"bottom\"").unwrap();
+        assert_eq!(config.preview, Some(PreviewPosition::Bottom));
+        assert!(Config::parse("preview = \"left\"").is_err());
+    }
+
+    #[test]
+    fn test_config_form_feeds() {
+        let config = Config::parse("form_feeds = \"rule\"").unwrap();
+        assert_eq!(config.form_feeds, Some(FormFeeds::Rule));
+        assert!(Config::parse("form_feeds = \"keep\"").is_err());
+    }
+
+    #[test]
+    fn test_config_index_filter() {
+        let config =
+            Config::parse("exclude_sections = [3, \"1ssl\"]\ninclude_commands = [\"git*\"]")
+                .unwrap();
+        assert_eq!(config.exclude_sections, vec!["3", "1ssl"]);
+        assert_eq!(config.include_commands, vec!["git*"]);
+        assert!(config.exclude_commands.is_empty());
+        assert!(Config::parse("exclude_commands = \"x\"").is_err());
+        assert_eq!(
+            Config::parse("fold_case = true").unwrap().fold_case,
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_config_tab_width() {
+        assert_eq!(Config::parse("tab_width = 4").unwrap().tab_width, Some(4));
+        assert!(Config::parse("tab_width = -1").is_err());
+    }
+
+    #[test]
+    fn test_config_index_command() {
+        let config = Config::parse("index_command = \"apropos -s {section} .\"").unwrap();
+        assert_eq!(
+            config.index_command.as_deref(),
+            Some("apropos -s {section} .")
+        );
+    }
+
+    #[test]
+    fn test_store_updates_top_level_key() {
+        let dir = std::env::temp_dir().join(format!("rtfm-config-{}", std::process::id()));
+        let path = dir.join(CONFIG_FILE);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&path, "# settings\nwrap_man = false\n\n[tui]\nx = 1\n").unwrap();
+
+        store(&path, "wrap_man", &Value::Bool(true)).unwrap();
+        store(&path, "wrap_tldr", &Value::Bool(false)).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            text,
+            "# settings\nwrap_man = true\nwrap_tldr = false\n\n[tui]\nx = 1\n"
+        );
+        let table = parse_table(&text).unwrap();
+        assert_eq!(table["wrap_tldr"], Value::Bool(false));
+        assert_eq!(table["tui.x"], Value::Int(1));
+    }
+
+    #[test]
+    fn test_format_value_round_trips() {
+        let value = Value::List(vec![Value::Str("a \"b\" \\c".to_string()), Value::Int(-2)]);
+        let text = format!("v = {}", format_value(&value));
+        assert_eq!(parse_table(&text).unwrap()["v"], value);
+    }
+}
@@ -0,0 +1,97 @@
+use std::ops::Range;
+
+/// Indentation past the surrounding text that sets an example apart
+const MIN_EXTRA_INDENT: usize = 4;
+
+/// Finds runs of lines set off by blank lines and indented past the text before
+/// them, like the commands in a man page's EXAMPLES section.
+///
+/// Deeper paragraphs following an option line continue its description rather
+/// than starting an example, so they're left out.
+pub fn detect(lines: &[String]) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    // Indentation of the last prose paragraph and whether it described an option
+    let mut base: Option<(usize, bool)> = None;
+    let mut start = 0;
+
+    while start < lines.len() {
+        if is_blank(&lines[start]) {
+            start += 1;
+            continue;
+        }
+        let end = start + lines[start..].iter().take_while(|l| !is_blank(l)).count();
+        let paragraph = &lines[start..end];
+        // A heading sits directly above its first paragraph
+        let body = match paragraph {
+            [heading, rest @ ..] if indent(heading) == 0 => rest,
+            _ => paragraph,
+        };
+
+        match (body.iter().map(|line| indent(line)).min(), base) {
+            (None, _) => base = None,
+            (Some(depth), Some((prose, false)))
+                if body.len() == paragraph.len() && depth >= prose + MIN_EXTRA_INDENT =>
+            {
+                blocks.push(start..end);
+            }
+            (Some(depth), Some((prose, true))) if depth > prose => {}
+            (Some(depth), _) => {
+                let option = body[0].trim_start().starts_with('-');
+                base = Some((depth, option));
+            }
+        }
+        start = end;
+    }
+
+    blocks
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+fn indent(line: &str) -> usize {
+    line.chars().take_while(|&c| c == ' ').count()
+}
+
+#[cfg(test)]
+mod examples_tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_detect_examples() {
+        let content = lines(
+            "EXAMPLES\n\
+             \x20      Show the changes:\n\
+             \n\
+             \x20          $ git diff\n\
+             \x20          $ git diff --cached\n\
+             \n\
+             \x20      Then commit them.\n\
+             \n\
+             \x20          $ git commit",
+        );
+        assert_eq!(detect(&content), vec![3..5, 8..9]);
+    }
+
+    #[test]
+    fn test_detect_skips_prose_and_option_paragraphs() {
+        let content = lines(
+            "DESCRIPTION\n\
+             \x20      List files.\n\
+             \n\
+             \x20      Sort entries alphabetically.\n\
+             \n\
+             \x20      -a, --all\n\
+             \x20             do not ignore entries starting with .\n\
+             \n\
+             \x20             This includes . and ..",
+        );
+        assert!(detect(&content).is_empty());
+        assert!(detect(&lines("           indented first")).is_empty());
+    }
+}
@@ -0,0 +1,476 @@
+/// Bonus for a match directly following the previous one
+const CONSECUTIVE_BONUS: i64 = 15;
+/// Bonus for a match at the start of the candidate or after a separator
+const WORD_START_BONUS: i64 = 10;
+/// Penalty per skipped character between matches
+const GAP_PENALTY: i64 = 1;
+/// Penalty for matching only after swapping two adjacent query characters
+const TRANSPOSITION_PENALTY: i64 = 20;
+
+/// Algorithm used to filter the command list
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum MatchMode {
+    /// Substring, results alphabetical
+    #[default]
+    Substring,
+    /// Subsequence, ranked by fuzzy score: consecutive and
+    /// word-start matches score higher, gaps and long names lower
+    Subsequence,
+    /// Like subsequence, but case-sensitive once the query has an uppercase letter
+    SmartcaseFuzzy,
+}
+
+/// How letter case affects filtering and content search
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum CaseMode {
+    /// Case-sensitive only once the query has an uppercase letter
+    #[default]
+    Smart,
+    /// Always case-insensitive
+    Ignore,
+    /// Always case-sensitive
+    Sensitive,
+}
+
+impl CaseMode {
+    /// Whether query is matched case-sensitively
+    pub fn is_sensitive(self, query: &str) -> bool {
+        match self {
+            CaseMode::Smart => query.chars().any(char::is_uppercase),
+            CaseMode::Ignore => false,
+            CaseMode::Sensitive => true,
+        }
+    }
+}
+
+/// fzf-style query operator overriding the match mode
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operator {
+    /// `^term`: names starting with term
+    Prefix,
+    /// `term$`: names ending with term
+    Suffix,
+    /// `^term$`: the name term itself
+    Whole,
+    /// `'term`: names containing term
+    Exact,
+    /// No operator, the match mode decides
+    Plain,
+}
+
+impl Operator {
+    /// Whether name passes the operator for term, both already case-folded
+    fn matches(self, name: &str, term: &str) -> bool {
+        match self {
+            Operator::Prefix => name.starts_with(term),
+            Operator::Suffix => name.ends_with(term),
+            Operator::Whole => name == term,
+            Operator::Exact | Operator::Plain => name.contains(term),
+        }
+    }
+}
+
+/// Splits query into its operator and the term it applies to
+fn parse_operator(query: &str) -> (Operator, &str) {
+    if let Some(term) = query.strip_prefix('\'') {
+        return (Operator::Exact, term);
+    }
+    match (query.strip_prefix('^'), query.strip_suffix('$')) {
+        (Some(_), Some(_)) if query.len() >= 2 => (Operator::Whole, &query[1..query.len() - 1]),
+        (Some(term), _) => (Operator::Prefix, term),
+        (None, Some(term)) => (Operator::Suffix, term),
+        (None, None) => (Operator::Plain, query),
+    }
+}
+
+/// Case mode the match mode uses: `SmartcaseFuzzy` always uses smart case
+fn effective_case(mode: MatchMode, case: CaseMode) -> CaseMode {
+    if mode == MatchMode::SmartcaseFuzzy {
+        CaseMode::Smart
+    } else {
+        case
+    }
+}
+
+/// Lowercases every command once, for repeated substring filtering
+pub fn lowercase_all(commands: &[String]) -> Vec<String> {
+    commands.iter().map(|cmd| cmd.to_lowercase()).collect()
+}
+
+/// Filters commands by query using mode, best match first.
+///
+/// `lowered` holds `lowercase_all(commands)`, so a keystroke only folds the query.
+/// `SmartcaseFuzzy` always uses smart case; the other modes follow `case`.
+/// A query of `^prefix`, `suffix$`, `^name$` or `'exact` overrides mode and keeps
+/// the names it matches in order.
+pub fn filter(
+    mode: MatchMode,
+    case: CaseMode,
+    query: &str,
+    commands: &[String],
+    lowered: &[String],
+) -> Vec<String> {
+    debug_assert_eq!(commands.len(), lowered.len());
+    let (operator, term) = parse_operator(query);
+    if operator != Operator::Plain {
+        let sensitive = effective_case(mode, case).is_sensitive(term);
+        let folded = if sensitive {
+            term.to_string()
+        } else {
+            term.to_lowercase()
+        };
+        return commands
+            .iter()
+            .zip(lowered)
+            .filter(|(cmd, lower)| operator.matches(if sensitive { cmd } else { lower }, &folded))
+            .map(|(cmd, _)| cmd.clone())
+            .collect();
+    }
+
+    match mode {
+        MatchMode::Substring if case.is_sensitive(query) => commands
+            .iter()
+            .filter(|cmd| cmd.contains(query))
+            .cloned()
+            .collect(),
+        MatchMode::Substring => {
+            let query = query.to_lowercase();
+            commands
+                .iter()
+                .zip(lowered)
+                .filter(|(_, lower)| lower.contains(&query))
+                .map(|(cmd, _)| cmd.clone())
+                .collect()
+        }
+        MatchMode::Subsequence => rank_with(query, commands, case.is_sensitive(query)),
+        MatchMode::SmartcaseFuzzy => {
+            rank_with(query, commands, CaseMode::Smart.is_sensitive(query))
+        }
+    }
+}
+
+/// Whether `filter` orders the names query matches by score rather than
+/// keeping them alphabetical
+pub fn ranks(mode: MatchMode, query: &str) -> bool {
+    mode != MatchMode::Substring && parse_operator(query).0 == Operator::Plain
+}
+
+/// Char positions of candidate matched by query under mode, for highlighting.
+///
+/// Substring marks the first occurrence; the subsequence modes mark the greedy
+/// left-to-right match `fuzzy_score` scores. Empty when nothing matches.
+pub fn match_positions(
+    mode: MatchMode,
+    case: CaseMode,
+    query: &str,
+    candidate: &str,
+) -> Vec<usize> {
+    let (operator, query) = parse_operator(query);
+    let sensitive = effective_case(mode, case).is_sensitive(query);
+    let fold = |c: char| {
+        if sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let query: Vec<char> = query.chars().map(fold).collect();
+    let chars: Vec<char> = candidate.chars().map(fold).collect();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let len = query.len();
+    match operator {
+        Operator::Prefix | Operator::Whole if chars.starts_with(&query) => {
+            return (0..len).collect();
+        }
+        Operator::Suffix if chars.ends_with(&query) => {
+            return (chars.len() - len..chars.len()).collect();
+        }
+        Operator::Prefix | Operator::Whole | Operator::Suffix => return Vec::new(),
+        _ => {}
+    }
+    if mode == MatchMode::Substring || operator == Operator::Exact {
+        return chars
+            .windows(query.len())
+            .position(|window| window == query.as_slice())
+            .map(|start| (start..start + query.len()).collect())
+            .unwrap_or_default();
+    }
+
+    let mut positions = Vec::with_capacity(query.len());
+    for (idx, c) in chars.iter().enumerate() {
+        if positions.len() < query.len() && *c == query[positions.len()] {
+            positions.push(idx);
+        }
+    }
+    if positions.len() < query.len() {
+        positions.clear();
+    }
+    positions
+}
+
+/// Scores `candidate` against `query` as a subsequence match.
+///
+/// Returns `None` when not every query character appears in order.
+/// Higher scores mean better matches; shorter candidates win ties.
+pub fn fuzzy_score(query: &str, candidate: &str, case_sensitive: bool) -> Option<i64> {
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let fold = |s: &str| {
+        if case_sensitive {
+            s.to_string()
+        } else {
+            s.to_lowercase()
+        }
+    };
+    let query: Vec<char> = fold(query).chars().collect();
+    let mut score = 0;
+    let mut query_idx = 0;
+    let mut last_match: Option<usize> = None;
+    let mut prev_char: Option<char> = None;
+
+    for (idx, c) in fold(candidate).chars().enumerate() {
+        if query_idx < query.len() && c == query[query_idx] {
+            score += 1;
+            match last_match {
+                Some(last) if last + 1 == idx => score += CONSECUTIVE_BONUS,
+                Some(last) => score -= (idx - last - 1) as i64 * GAP_PENALTY,
+                None => score -= idx as i64 * GAP_PENALTY,
+            }
+            if prev_char.is_none_or(|p| !p.is_alphanumeric()) {
+                score += WORD_START_BONUS;
+            }
+            last_match = Some(idx);
+            query_idx += 1;
+        }
+        prev_char = Some(c);
+    }
+
+    if query_idx < query.len() {
+        return None;
+    }
+
+    Some(score - candidate.chars().count() as i64 * GAP_PENALTY)
+}
+
+/// Ranks candidates by fuzzy score, best first; a query with two adjacent
+/// characters swapped, like `gti`, still finds `git`, ranked below exact matches
+pub fn rank<'a, I>(query: &str, candidates: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let chars: Vec<char> = query.chars().collect();
+    let swapped: Vec<String> = (1..chars.len())
+        .filter(|&idx| chars[idx - 1] != chars[idx])
+        .map(|idx| {
+            let mut chars = chars.clone();
+            chars.swap(idx - 1, idx);
+            chars.into_iter().collect()
+        })
+        .collect();
+
+    let mut scored: Vec<(i64, &String)> = candidates
+        .into_iter()
+        .filter_map(|cmd| {
+            let transposed = swapped
+                .iter()
+                .filter_map(|q| fuzzy_score(q, cmd, false))
+                .map(|score| score - TRANSPOSITION_PENALTY)
+                .max();
+            fuzzy_score(query, cmd, false)
+                .max(transposed)
+                .map(|score| (score, cmd))
+        })
+        .collect();
+
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+    scored.into_iter().map(|(_, cmd)| cmd.clone()).collect()
+}
+
+/// Ranks candidates by fuzzy score, optionally respecting case
+fn rank_with<'a, I>(query: &str, candidates: I, case_sensitive: bool) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let mut scored: Vec<(i64, &String)> = candidates
+        .into_iter()
+        .filter_map(|cmd| fuzzy_score(query, cmd, case_sensitive).map(|score| (score, cmd)))
+        .collect();
+
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+    scored.into_iter().map(|(_, cmd)| cmd.clone()).collect()
+}
+
+#[cfg(test)]
+mod fuzzy_tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score_subsequence() {
+        assert!(fuzzy_score("tg", "git", false).is_none());
+        assert!(fuzzy_score("gt", "git", false).is_some());
+        assert!(fuzzy_score("xyz", "git", false).is_none());
+        assert_eq!(fuzzy_score("", "git", false), Some(0));
+    }
+
+    #[test]
+    fn test_match_positions() {
+        let smart = CaseMode::Smart;
+        assert_eq!(
+            match_positions(MatchMode::Substring, smart, "it", "gitit"),
+            vec![1, 2]
+        );
+        assert_eq!(
+            match_positions(MatchMode::Subsequence, smart, "gk", "gitk"),
+            vec![0, 3]
+        );
+        assert_eq!(
+            match_positions(MatchMode::Substring, CaseMode::Ignore, "GIT", "git"),
+            vec![0, 1, 2]
+        );
+        assert!(match_positions(MatchMode::Subsequence, smart, "kg", "gitk").is_empty());
+        assert!(match_positions(MatchMode::Substring, smart, "", "git").is_empty());
+    }
+
+    #[test]
+    fn test_ranks() {
+        assert!(!ranks(MatchMode::Substring, "git"));
+        assert!(ranks(MatchMode::Subsequence, "git"));
+        assert!(ranks(MatchMode::SmartcaseFuzzy, "git"));
+        for query in ["^git", "git$", "^git$", "'git"] {
+            assert!(!ranks(MatchMode::Subsequence, query));
+        }
+    }
+
+    #[test]
+    fn test_query_operators() {
+        let commands: Vec<String> = ["git", "gitk", "digit", "tig"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let lowered = lowercase_all(&commands);
+        let filter = |query| {
+            filter(
+                MatchMode::Subsequence,
+                CaseMode::Smart,
+                query,
+                &commands,
+                &lowered,
+            )
+        };
+
+        assert_eq!(filter("^git"), vec!["git", "gitk"]);
+        assert_eq!(filter("git$"), vec!["git", "digit"]);
+        assert_eq!(filter("^git$"), vec!["git"]);
+        assert_eq!(filter("'it"), vec!["git", "gitk", "digit"]);
+        assert_eq!(filter("^GIT"), Vec::<String>::new());
+        assert_eq!(filter("^").len(), 4);
+
+        let smart = CaseMode::Smart;
+        assert_eq!(
+            match_positions(MatchMode::Subsequence, smart, "git$", "digit"),
+            vec![2, 3, 4]
+        );
+        assert_eq!(
+            match_positions(MatchMode::Subsequence, smart, "'it", "digit"),
+            vec![3, 4]
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_score_prefers_consecutive() {
+        let exact = fuzzy_score("git", "git", false).unwrap();
+        let spread = fuzzy_score("git", "gpg-init", false).unwrap();
+        assert!(exact > spread);
+    }
+
+    #[test]
+    fn test_filter_modes() {
+        let commands = vec![
+            "Mail".to_string(),
+            "gzip".to_string(),
+            "mailx".to_string(),
+            "make".to_string(),
+        ];
+        let lowered = lowercase_all(&commands);
+
+        assert_eq!(
+            filter(
+                MatchMode::Substring,
+                CaseMode::Ignore,
+                "ma",
+                &commands,
+                &lowered
+            ),
+            vec!["Mail", "mailx", "make"]
+        );
+        assert_eq!(
+            filter(
+                MatchMode::Subsequence,
+                CaseMode::Ignore,
+                "mx",
+                &commands,
+                &lowered
+            ),
+            vec!["mailx"]
+        );
+        assert_eq!(
+            filter(
+                MatchMode::SmartcaseFuzzy,
+                CaseMode::Ignore,
+                "Ma",
+                &commands,
+                &lowered
+            ),
+            vec!["Mail"]
+        );
+        assert_eq!(
+            filter(
+                MatchMode::SmartcaseFuzzy,
+                CaseMode::Ignore,
+                "ma",
+                &commands,
+                &lowered
+            )
+            .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_case_modes() {
+        let commands = vec!["Mail".to_string(), "mailx".to_string()];
+        let lowered = lowercase_all(&commands);
+        let substring =
+            |case, query| filter(MatchMode::Substring, case, query, &commands, &lowered);
+
+        assert_eq!(substring(CaseMode::Smart, "Ma"), vec!["Mail"]);
+        assert_eq!(substring(CaseMode::Smart, "ma").len(), 2);
+        assert_eq!(substring(CaseMode::Ignore, "MA").len(), 2);
+        assert_eq!(substring(CaseMode::Sensitive, "ma"), vec!["mailx"]);
+    }
+
+    #[test]
+    fn test_rank_orders_best_first() {
+        let commands = vec![
+            "gitk".to_string(),
+            "git".to_string(),
+            "grep".to_string(),
+            "logit".to_string(),
+        ];
+
+        let results = rank("git", &commands);
+        assert_eq!(results, vec!["git", "gitk", "logit"]);
+    }
+
+    #[test]
+    fn test_rank_allows_transpositions() {
+        let commands = vec!["gitk".to_string(), "git".to_string(), "gti".to_string()];
+        assert_eq!(rank("gti", &commands), vec!["gti", "git", "gitk"]);
+        assert!(rank("xgti", &commands).is_empty());
+    }
+}
@@ -0,0 +1,133 @@
+use crate::man_db::ManDb;
+use regex::Regex;
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+const PROGRESS_WIDTH: usize = 30;
+const PROGRESS_INTERVAL_MS: u64 = 100;
+
+/// Line of a man page matching the grep pattern
+pub struct GrepMatch {
+    pub command: String,
+    pub line: String,
+}
+
+/// Outcome of a full-text scan
+pub enum GrepOutcome {
+    Finished(Vec<GrepMatch>),
+    Cancelled,
+}
+
+/// Scans every indexed man page for pattern, reporting progress on stderr
+pub async fn run(man_db: Arc<ManDb>, pattern: Regex) -> GrepOutcome {
+    let commands = man_db.get_commands().clone();
+    let total = commands.len();
+    let done = Arc::new(AtomicUsize::new(0));
+    let pattern = Arc::new(pattern);
+
+    let mut tasks = JoinSet::new();
+    for command in commands {
+        let man_db = man_db.clone();
+        let pattern = pattern.clone();
+        let done = done.clone();
+        tasks.spawn(async move {
+            // ManDb caps how many `man` processes run at once, and each page is
+            // dropped once searched so only its matching lines are kept
+            let page = man_db.scan_man_page(&command).await;
+            done.fetch_add(1, Ordering::Relaxed);
+            // A failed load's notice isn't page text to search
+            let lines = if page.failed {
+                &[][..]
+            } else {
+                &page.lines[..]
+            };
+            lines
+                .iter()
+                .filter(|line| pattern.is_match(line))
+                .map(|line| GrepMatch {
+                    command: command.clone(),
+                    line: line.trim().to_string(),
+                })
+                .collect::<Vec<_>>()
+        });
+    }
+
+    let progress = {
+        let done = done.clone();
+        tokio::spawn(async move {
+            loop {
+                eprint!("\r{}", progress_bar(done.load(Ordering::Relaxed), total));
+                let _ = std::io::stderr().flush();
+                tokio::time::sleep(Duration::from_millis(PROGRESS_INTERVAL_MS)).await;
+            }
+        })
+    };
+
+    let collect = async {
+        let mut matches = Vec::new();
+        while let Some(result) = tasks.join_next().await {
+            if let Ok(found) = result {
+                matches.extend(found);
+            }
+        }
+        matches
+    };
+
+    let outcome = tokio::select! {
+        matches = collect => GrepOutcome::Finished(matches),
+        _ = tokio::signal::ctrl_c() => GrepOutcome::Cancelled,
+    };
+
+    progress.abort();
+    eprintln!("\r{}", progress_bar(done.load(Ordering::Relaxed), total));
+
+    if let GrepOutcome::Finished(mut matches) = outcome {
+        matches.sort_by(|a, b| a.command.cmp(&b.command));
+        return GrepOutcome::Finished(matches);
+    }
+    outcome
+}
+
+/// Formats a textual progress bar like `[====>    ] 45% (1200/2700)`
+fn progress_bar(done: usize, total: usize) -> String {
+    let ratio = if total == 0 {
+        1.0
+    } else {
+        done as f64 / total as f64
+    };
+    let filled = (ratio * PROGRESS_WIDTH as f64) as usize;
+
+    let bar: String = (0..PROGRESS_WIDTH)
+        .map(|i| match i.cmp(&filled) {
+            std::cmp::Ordering::Less => '=',
+            std::cmp::Ordering::Equal => '>',
+            std::cmp::Ordering::Greater => ' ',
+        })
+        .collect();
+
+    format!("[{bar}] {:>3}% ({done}/{total})", (ratio * 100.0) as usize)
+}
+
+#[cfg(test)]
+mod grep_tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_bar() {
+        assert_eq!(
+            progress_bar(0, 10),
+            format!("[>{}]   0% (0/10)", " ".repeat(PROGRESS_WIDTH - 1))
+        );
+        assert_eq!(
+            progress_bar(5, 10),
+            format!("[{}>{}]  50% (5/10)", "=".repeat(15), " ".repeat(14))
+        );
+        assert_eq!(
+            progress_bar(10, 10),
+            format!("[{}] 100% (10/10)", "=".repeat(PROGRESS_WIDTH))
+        );
+    }
+}
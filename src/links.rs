@@ -0,0 +1,88 @@
+use anyhow::{Result, anyhow};
+use regex::Regex;
+use std::process::{Command, Stdio};
+use std::sync::LazyLock;
+
+/// Matches http(s) URLs, stopping before common closing punctuation
+static URL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"https?://[^\s<>"'()\[\]]*[^\s<>"'()\[\].,;:!?]"#).unwrap());
+
+/// Matches man page references like `grep(1)` or `pthread_create(3p)`
+static MAN_REF_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"([A-Za-z0-9_][\w.+-]*)\((\d+[a-z]*|n)\)").unwrap());
+
+/// Finds all URLs in a line
+pub fn find_urls(line: &str) -> Vec<&str> {
+    URL_RE.find_iter(line).map(|m| m.as_str()).collect()
+}
+
+/// Checks whether a word contains a URL
+pub fn is_url(word: &str) -> bool {
+    URL_RE.is_match(word)
+}
+
+/// Finds `(name, section)` man page references in a line
+pub fn find_man_refs(line: &str) -> Vec<(&str, &str)> {
+    MAN_REF_RE
+        .captures_iter(line)
+        .filter_map(|caps| Some((caps.get(1)?.as_str(), caps.get(2)?.as_str())))
+        .collect()
+}
+
+/// Opens URL in `$BROWSER` or the platform default opener
+pub fn open_url(url: &str) -> Result<()> {
+    let opener = std::env::var("BROWSER").ok().unwrap_or_else(|| {
+        if cfg!(target_os = "macos") {
+            "open".to_string()
+        } else {
+            "xdg-open".to_string()
+        }
+    });
+
+    let mut child = Command::new(&opener)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run {opener}: {e}"))?;
+    // Reap the opener when it exits so it doesn't linger as a zombie
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod links_tests {
+    use super::*;
+
+    #[test]
+    fn test_find_urls() {
+        let line = "See https://example.com/docs, or (http://foo.org/a?b=1).";
+        assert_eq!(
+            find_urls(line),
+            vec!["https://example.com/docs", "http://foo.org/a?b=1"]
+        );
+        assert!(find_urls("no links here").is_empty());
+    }
+
+    #[test]
+    fn test_find_man_refs() {
+        let line = "       grep(1), pthread_create(3p), git-log(1), intro(n).";
+        assert_eq!(
+            find_man_refs(line),
+            vec![
+                ("grep", "1"),
+                ("pthread_create", "3p"),
+                ("git-log", "1"),
+                ("intro", "n")
+            ]
+        );
+        assert!(find_man_refs("call f(x) or (1)").is_empty());
+    }
+
+    #[test]
+    fn test_is_url() {
+        assert!(is_url("<https://tldr.sh>"));
+        assert!(!is_url("--help"));
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::{Result, anyhow};
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Log file name inside the cache directory
+pub const LOG_FILE: &str = "rtfm.log";
+
+/// Appends records to a file, since the TUI owns the terminal
+struct FileLogger {
+    file: Mutex<File>,
+    level: LevelFilter,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level && metadata.target().starts_with("rtfm")
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut file = self.file.lock().unwrap();
+        // A failed write has nowhere better to be reported
+        let _ = writeln!(
+            file,
+            "{}.{:03} {:<5} {}: {}",
+            now.as_secs(),
+            now.subsec_millis(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+    }
+
+    fn flush(&self) {
+        let _ = self.file.lock().unwrap().flush();
+    }
+}
+
+/// Level to log at: `RUST_LOG` if set, else debug with `--verbose`, else nothing
+///
+/// `RUST_LOG` takes a bare level or `rtfm=<level>` among comma-separated directives.
+pub fn level(verbose: bool, rust_log: Option<&str>) -> Option<LevelFilter> {
+    let from_env = rust_log.and_then(|spec| {
+        spec.split(',').find_map(|directive| {
+            let level = match directive.trim().split_once('=') {
+                Some(("rtfm", level)) => level,
+                Some(_) => return None,
+                None => directive.trim(),
+            };
+            LevelFilter::from_str(level).ok()
+        })
+    });
+
+    match from_env {
+        Some(LevelFilter::Off) => None,
+        Some(level) => Some(level),
+        None => verbose.then_some(LevelFilter::Debug),
+    }
+}
+
+/// Starts appending log records at level or above to path
+pub fn init(path: &Path, level: LevelFilter) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| anyhow!("Failed to create {}: {e}", dir.display()))?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| anyhow!("Failed to open {}: {e}", path.display()))?;
+
+    // The logger lives for the rest of the process
+    let logger = Box::leak(Box::new(FileLogger {
+        file: Mutex::new(file),
+        level,
+    }));
+    log::set_logger(logger).map_err(|e| anyhow!("{e}"))?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+#[cfg(test)]
+mod logging_tests {
+    use super::*;
+
+    #[test]
+    fn test_level() {
+        assert_eq!(level(false, None), None);
+        assert_eq!(level(true, None), Some(LevelFilter::Debug));
+        assert_eq!(level(false, Some("trace")), Some(LevelFilter::Trace));
+        assert_eq!(
+            level(true, Some("tokio=warn,rtfm=info")),
+            Some(LevelFilter::Info)
+        );
+        assert_eq!(level(true, Some("tokio=warn")), Some(LevelFilter::Debug));
+        assert_eq!(level(true, Some("off")), None);
+    }
+}
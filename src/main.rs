@@ -1,10 +1,46 @@
+mod bundle;
+mod config;
+mod examples;
+mod fuzzy;
+mod grep;
+mod links;
+mod logging;
 mod man_db;
+mod manpage;
+mod notes;
+mod paths;
+mod provider;
+mod roles;
+mod search;
+mod tables;
+mod theme;
 mod trie;
 mod tui;
+mod warm;
 
-use crate::man_db::ManDb;
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+use crate::config::{CONFIG_FILE, Config, Value};
+use crate::fuzzy::MatchMode;
+use crate::grep::GrepOutcome;
+use crate::logging::LOG_FILE;
+use crate::man_db::{
+    DEFAULT_MAX_INDEX_SIZE, DEFAULT_TAB_WIDTH, EMPTY_INDEX_WARNING, IndexFilter, LoadOptions,
+    ManDb, PAGE_CACHE_DIR, default_concurrency,
+};
+use crate::paths::Paths;
+use crate::theme::Theme;
+use crate::tui::{
+    DEFAULT_COMPACT_BLANK_LINES, DEFAULT_CONTENT_PADDING, DEFAULT_HIGHLIGHT_MATCHES,
+    DEFAULT_JUMP_WHILE_SEARCHING, DEFAULT_MATCH_POSITION, DEFAULT_MAX_LINE_WIDTH,
+    DEFAULT_SCROLL_OFF, DEFAULT_TLDR_MAX_AGE_DAYS, DEFAULT_WRAP_MAN, DEFAULT_WRAP_MARKERS,
+    DEFAULT_WRAP_TLDR, JumpTo, OnEnter, PreviewPosition, StartFocus, TuiOptions,
+};
+use anyhow::{Result, anyhow};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use regex::RegexBuilder;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// CLI for browsing man pages and tldr cheatsheets
 #[derive(Parser)]
@@ -13,47 +49,705 @@ struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
 
-    /// Manual section to use (default: 1)
-    #[arg(short, long, default_value_t = 1)]
-    section: u8,
+    /// Start the browser with the command list filtered by query; put `--` before
+    /// a query named like a subcommand, as in `rtfm -- man`
+    query: Option<String>,
+
+    /// Manual section to use, e.g. 3 or 3p; a number includes its subsections
+    #[arg(short, long, default_value = "1")]
+    section: String,
+
+    /// Shell command used instead of `man -k .` to build the index
+    #[arg(long, global = true)]
+    index_command: Option<String>,
+
+    /// Columns between tab stops in page content (default: 8)
+    #[arg(long)]
+    tab_width: Option<usize>,
+
+    /// Directory of `<command>.md` tldr pages to prefer over the tldr client
+    #[arg(long)]
+    tldr_dir: Option<PathBuf>,
+
+    /// Read the command list from stdin, one per line, instead of `man -k`
+    #[arg(long)]
+    stdin: bool,
+
+    /// Index names differing only in letter case, like Mail and mail, once
+    #[arg(long, global = true)]
+    fold_case: bool,
+
+    /// Read the index and man pages of a remote machine over ssh
+    #[arg(long, global = true, value_name = "USER@HOST")]
+    host: Option<String>,
+
+    /// Serve the index and pages from a directory written by `rtfm bundle-create`
+    #[arg(long, global = true, value_name = "DIR")]
+    bundle: Option<PathBuf>,
+
+    /// Load pages immediately when navigating the list (typing stays debounced)
+    #[arg(long)]
+    instant_preview: bool,
+
+    /// Don't auto-load pages while the filter matches more than N commands
+    #[arg(long, value_name = "N")]
+    max_preview_matches: Option<usize>,
+
+    /// Clip content lines longer than N chars for display, 0 for unlimited
+    #[arg(long, value_name = "N")]
+    max_line_width: Option<usize>,
+
+    /// Rows kept between the selection or search match and the pane edges (default: 3)
+    #[arg(long, value_name = "N")]
+    scroll_off: Option<usize>,
+
+    /// Render content in a centered column at most N chars wide, 0 for the full pane
+    #[arg(long, value_name = "N")]
+    max_width: Option<usize>,
+
+    /// Blank columns between the content and its border on each side (default: 1)
+    #[arg(long, value_name = "N")]
+    content_padding: Option<usize>,
+
+    /// Algorithm used to filter the command list
+    #[arg(long, value_enum)]
+    match_mode: Option<MatchMode>,
+
+    /// Pane to start in; content opens the first match of QUERY right away
+    #[arg(long, value_enum, default_value = "list")]
+    focus: StartFocus,
+
+    /// Where a newly opened man page starts; options lands on its OPTIONS or FLAGS heading
+    #[arg(long, value_enum)]
+    jump_to: Option<JumpTo>,
+
+    /// What Enter does in the command list; print quits and prints the name
+    #[arg(long, value_enum)]
+    on_enter: Option<OnEnter>,
+
+    /// Command opening a terminal for Ctrl+T, {cmd} standing for the command
+    /// (default: $TERMINAL -e {cmd})
+    #[arg(long, value_name = "TEMPLATE")]
+    launcher: Option<String>,
+
+    /// Leave mouse events to the terminal so text can be selected (toggle: Ctrl+O)
+    #[arg(long)]
+    no_mouse: bool,
+
+    /// Return to the starting state after this many seconds without a keypress
+    #[arg(long, value_name = "SECONDS")]
+    idle_reset: Option<u64>,
+
+    /// Where to show page content; the choice is saved for later runs
+    #[arg(long, value_enum)]
+    preview: Option<PreviewPosition>,
+
+    /// TOML file mapping UI elements to color names or #rrggbb values
+    #[arg(long, value_name = "PATH")]
+    theme_file: Option<PathBuf>,
+
+    /// Draw borders and markers with plain ASCII characters
+    #[arg(long)]
+    ascii: bool,
+
+    /// Directory for persistent caches (default: $XDG_CACHE_HOME/rtfm)
+    #[arg(long, global = true)]
+    cache_dir: Option<PathBuf>,
+
+    /// Log external commands and cache activity to rtfm.log in the cache directory
+    /// (RUST_LOG=<level> picks the level)
+    #[arg(short, long, global = true)]
+    verbose: bool,
 }
 
 /// Available subcommands
 #[derive(Subcommand)]
 enum Commands {
-    /// List commands starting with prefix
-    Getmans { prefix: String },
-    /// Show man page for command
-    Getman { command: String },
+    /// List commands starting with prefix, shortest first
+    Getmans {
+        prefix: String,
+        /// Most commands to print, 0 for all
+        #[arg(long, default_value = "50")]
+        limit: usize,
+        /// Matches to skip before printing
+        #[arg(long, default_value = "0")]
+        offset: usize,
+    },
+    /// Print every indexed command, one per line
+    List {
+        /// Follow each name with a tab and its description
+        #[arg(long)]
+        with_descriptions: bool,
+    },
+    /// Show man page for command, or render a roff file like ./foo.1.gz
+    Getman {
+        command: String,
+        /// When man finds no page, offer the sections that have one
+        #[arg(long)]
+        all_sections_fallback: bool,
+    },
+    /// Print the man page file(s) command resolves to and whether it has a tldr page
+    Which { command: String },
+    /// Search the text of every indexed man page
+    Grep {
+        pattern: String,
+        /// Match case-insensitively
+        #[arg(short, long)]
+        ignore_case: bool,
+    },
+    /// Show index statistics
+    Index {
+        /// Print every raw `man -k` line with its parse result
+        #[arg(long)]
+        debug: bool,
+    },
+    /// Render every indexed man page into DIR for later use with --bundle
+    BundleCreate { dir: PathBuf },
+    /// Render and cache the pages of every command, or those starting with prefix
+    Warm { prefix: Option<String> },
+    /// Print the config file path, or with --show the settings in effect
+    Config {
+        /// Print every setting after merging flags, config file and defaults
+        #[arg(long)]
+        show: bool,
+    },
+    /// Print a roff man page for rtfm
+    Man,
+    /// Print the built-in keybindings, which can't be changed, as tab-separated
+    /// context, keys and action
+    Keymap,
+    /// List commands whose name or description contains keyword, like apropos
+    Search {
+        keyword: String,
+        /// Match keyword only as a whole word
+        #[arg(long)]
+        exact: bool,
+        /// Treat keyword as a regular expression
+        #[arg(long)]
+        regex: bool,
+    },
+    /// Print the command best matching a fuzzy query
+    Resolve {
+        query: String,
+        /// Print every match, best first
+        #[arg(long)]
+        all: bool,
+    },
 }
 
 fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let man_db = ManDb::load(cli.section)?;
+    let mut cli = Cli::parse();
+    let command = cli.command.take();
+    if let Some(Commands::Man) = command {
+        print!(
+            "{}",
+            manpage::render(&Cli::command(), tui::KEYBINDINGS, tui::FILTER_SYNTAX)
+        );
+        return Ok(());
+    }
+    if let Some(Commands::Keymap) = command {
+        print!("{}", manpage::render_keymap(tui::KEYBINDINGS));
+        return Ok(());
+    }
+
+    let paths = Paths::resolve(cli.cache_dir.clone())?;
+    let rust_log = std::env::var("RUST_LOG").ok();
+    if let Some(level) = logging::level(cli.verbose, rust_log.as_deref()) {
+        let log_path = paths.cache_dir.join(LOG_FILE);
+        if let Err(e) = logging::init(&log_path, level) {
+            eprintln!("Logging disabled: {e}");
+        }
+    }
+    let config_path = paths.config_dir.join(CONFIG_FILE);
+    let config = Config::load(&config_path)?;
+    let load_options = load_options(&cli, &config, &paths)?;
+
+    if let Some(Commands::Config { show }) = command {
+        if show {
+            let options = tui_options(&cli, &config, &load_options)?;
+            print!(
+                "{}",
+                effective_config(&cli, &config, &load_options, &options, &paths)
+            );
+        } else {
+            println!("{}", config_path.display());
+        }
+        return Ok(());
+    }
+
+    let Some(command) = command else {
+        let options = tui_options(&cli, &config, &load_options)?;
+        if let Some(preview) = cli.preview.and_then(|p| p.to_possible_value()) {
+            let value = Value::Str(preview.get_name().to_string());
+            if let Err(e) = config::store(&config_path, "preview", &value) {
+                eprintln!("Failed to save preview position: {e}");
+            }
+        }
+        // The TUI builds the index itself so a slow `man -k` can be cancelled
+        let rt = tokio::runtime::Runtime::new()?;
+        let result = rt.block_on(tui::run_tui(load_options, paths, options));
+        // Don't wait on an index build the user cancelled
+        rt.shutdown_background();
+        return result;
+    };
 
-    match cli.command {
-        Some(Commands::Getmans { prefix }) => {
-            for word in man_db.commands_starting_with(&prefix) {
+    let man_db = ManDb::load(&load_options)?;
+    for warning in man_db.warnings() {
+        eprintln!("{warning}");
+    }
+    if man_db.get_commands().is_empty() && !load_options.from_stdin {
+        eprintln!("Warning: {EMPTY_INDEX_WARNING}");
+    }
+
+    match command {
+        Commands::Getmans {
+            prefix,
+            limit,
+            offset,
+        } => {
+            let words = man_db.commands_starting_with(&prefix);
+            let (shown, remaining) = paginate(words.len(), limit, offset);
+            for word in &words[shown] {
                 println!("{word}");
             }
+            // On stderr so piped output stays one command per line
+            if remaining > 0 {
+                eprintln!("… and {remaining} more (use --offset or --limit 0)");
+            }
+        }
+        Commands::List { with_descriptions } => {
+            for cmd in man_db.get_commands() {
+                if with_descriptions {
+                    let description = man_db.get_description(cmd).unwrap_or_default();
+                    println!("{cmd}\t{description}");
+                } else {
+                    println!("{cmd}");
+                }
+            }
         }
-        Some(Commands::Getman { command }) => {
-            man_db.display_man_page(&command)?;
+        Commands::Getman {
+            command,
+            all_sections_fallback,
+        } => {
+            if !man_db.display_man_page(&command)? && all_sections_fallback {
+                pick_section_fallback(&command, man_db.host())?;
+            }
+        }
+        Commands::Which { command } => {
+            let section = man_db.get_section(&command).unwrap_or(&cli.section);
+            let paths = if man_db.tldr_only() {
+                println!("man: not installed");
+                Vec::new()
+            } else {
+                let paths = man_db.page_paths(&command, Some(section))?;
+                if paths.is_empty() {
+                    println!("man: no page in section {section}");
+                }
+                paths
+            };
+            for path in &paths {
+                println!("man: {}", path.display());
+            }
+            let has_tldr = match man_db.local_tldr_path(&command) {
+                Some(path) => {
+                    println!("tldr: {}", path.display());
+                    true
+                }
+                None if ManDb::tldr_client_has(&command) => {
+                    println!("tldr: from the tldr client");
+                    true
+                }
+                None => {
+                    println!("tldr: no page");
+                    false
+                }
+            };
+            if paths.is_empty() && !has_tldr {
+                std::process::exit(1);
+            }
         }
-        None => {
+        Commands::Grep {
+            pattern,
+            ignore_case,
+        } => {
+            let pattern = RegexBuilder::new(&pattern)
+                .case_insensitive(ignore_case)
+                .build()?;
             let rt = tokio::runtime::Runtime::new()?;
-            rt.block_on(tui::run_tui(man_db))?;
+            match rt.block_on(grep::run(Arc::new(man_db), pattern)) {
+                GrepOutcome::Finished(matches) => {
+                    if matches.is_empty() {
+                        std::process::exit(1);
+                    }
+                    for found in matches {
+                        println!("{}: {}", found.command, found.line);
+                    }
+                }
+                GrepOutcome::Cancelled => {
+                    eprintln!("Cancelled");
+                    std::process::exit(130);
+                }
+            }
+        }
+        Commands::BundleCreate { dir } => {
+            let rt = tokio::runtime::Runtime::new()?;
+            let written = rt.block_on(bundle::create(Arc::new(man_db), &dir))?;
+            println!("Wrote {written} pages to {}", dir.display());
+        }
+        Commands::Warm { prefix } => {
+            if load_options.bundle.is_some() {
+                return Err(anyhow!("Bundle pages are already on disk, nothing to warm"));
+            }
+            let rt = tokio::runtime::Runtime::new()?;
+            let prefix = prefix.unwrap_or_default();
+            let rendered = rt.block_on(warm::warm(Arc::new(man_db), &prefix))?;
+            println!(
+                "Rendered {rendered} pages into {}",
+                paths.cache_dir.display()
+            );
+        }
+        Commands::Index { debug } => {
+            if debug {
+                for line in ManDb::debug_index(&load_options)? {
+                    let status = match (&line.entry, line.kept) {
+                        (Some(_), true) => "kept",
+                        (Some(_), false) => "filtered",
+                        (None, _) => "unparsed",
+                    };
+                    println!("{status:<9}{}", line.raw);
+                    if let Some(entry) = line.entry {
+                        println!(
+                            "         -> ({}, {}, {:?})",
+                            entry.name, entry.section, entry.description
+                        );
+                    }
+                }
+            }
+            let duplicates = man_db.duplicate_sources();
+            if debug && !duplicates.is_empty() {
+                println!("Names indexed from several lines:");
+                for (name, count) in &duplicates {
+                    println!("  {name:<24} {count} lines");
+                }
+            }
+            println!(
+                "{} commands indexed for section {}",
+                man_db.get_commands().len(),
+                cli.section
+            );
+            if !duplicates.is_empty() {
+                println!(
+                    "{} names came from more than one index line",
+                    duplicates.len()
+                );
+            }
+        }
+        Commands::Search {
+            keyword,
+            exact,
+            regex,
+        } => {
+            let mut pattern = if regex {
+                keyword
+            } else {
+                regex::escape(&keyword)
+            };
+            if exact {
+                pattern = format!(r"\b(?:{pattern})\b");
+            }
+            let pattern = RegexBuilder::new(&pattern).case_insensitive(true).build()?;
+
+            let matches = man_db.commands_matching_regex(&pattern);
+            if matches.is_empty() {
+                std::process::exit(1);
+            }
+            for cmd in matches {
+                let section = man_db.get_section(cmd).unwrap_or("?");
+                let description = man_db.get_description(cmd).unwrap_or_default();
+                println!("{cmd} ({section}) - {description}");
+            }
+        }
+        Commands::Resolve { query, all } => {
+            let matches = man_db.commands_matching_fuzzy(&query);
+            if matches.is_empty() {
+                std::process::exit(1);
+            }
+
+            let count = if all { matches.len() } else { 1 };
+            for word in matches.iter().take(count) {
+                println!("{word}");
+            }
+        }
+        Commands::Man | Commands::Keymap | Commands::Config { .. } => {
+            unreachable!("handled before loading the index")
         }
     }
 
     Ok(())
 }
 
+/// Asks which section to read command from when man's default search missed it
+fn pick_section_fallback(command: &str, host: Option<&str>) -> Result<()> {
+    let sections = ManDb::sections_for(command, host)?;
+    let section = match sections.as_slice() {
+        [] => return Err(anyhow!("No man page for {command} in any section")),
+        [only] => only.clone(),
+        _ => {
+            eprintln!("{command} has pages in these sections:");
+            for (i, section) in sections.iter().enumerate() {
+                eprintln!("  {}) {command}({section})", i + 1);
+            }
+            eprint!("Pick one [1-{}]: ", sections.len());
+            let mut answer = String::new();
+            std::io::stdin().read_line(&mut answer)?;
+            let answer = answer.trim();
+            answer
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| sections.get(n.wrapping_sub(1)))
+                .or_else(|| sections.iter().find(|s| *s == answer))
+                .cloned()
+                .ok_or_else(|| anyhow!("No section `{answer}` for {command}"))?
+        }
+    };
+    ManDb::display_man_page_in(command, Some(&section), host)?;
+    Ok(())
+}
+
+/// Resolves index settings from flags, then the config file, then defaults
+fn load_options(cli: &Cli, config: &Config, paths: &Paths) -> Result<LoadOptions> {
+    Ok(LoadOptions {
+        section: cli.section.clone(),
+        index_command: cli
+            .index_command
+            .clone()
+            .or_else(|| config.index_command.clone()),
+        tab_width: cli
+            .tab_width
+            .or(config.tab_width)
+            .unwrap_or(DEFAULT_TAB_WIDTH),
+        tldr_dir: cli.tldr_dir.clone().or_else(|| config.tldr_dir.clone()),
+        from_stdin: cli.stdin,
+        filter: IndexFilter::new(
+            config.exclude_sections.clone(),
+            &config.exclude_commands,
+            &config.include_commands,
+        )?,
+        fold_case: cli.fold_case || config.fold_case.unwrap_or(false),
+        max_concurrency: config.max_concurrency.unwrap_or_else(default_concurrency),
+        max_index_size: config.max_index_size.unwrap_or(DEFAULT_MAX_INDEX_SIZE),
+        form_feeds: config.form_feeds.unwrap_or_default(),
+        bundle: cli.bundle.clone(),
+        page_cache: Some(paths.cache_dir.join(PAGE_CACHE_DIR)),
+        host: cli.host.clone(),
+        progress: None,
+    })
+}
+
+/// Range of items to print and how many follow it, for limit and offset
+fn paginate(total: usize, limit: usize, offset: usize) -> (Range<usize>, usize) {
+    let start = offset.min(total);
+    let end = match limit {
+        0 => total,
+        limit => start.saturating_add(limit).min(total),
+    };
+    (start..end, total - end)
+}
+
+/// Resolves TUI settings from flags, then the config file, then defaults
+fn tui_options(cli: &Cli, config: &Config, load_options: &LoadOptions) -> Result<TuiOptions> {
+    let theme = match theme_file(cli, config) {
+        Some(path) => Theme::load(&path)?,
+        None => Theme::default(),
+    };
+
+    Ok(TuiOptions {
+        instant_preview: cli.instant_preview,
+        max_preview_matches: cli.max_preview_matches.or(config.max_preview_matches),
+        max_line_width: cli
+            .max_line_width
+            .or(config.max_line_width)
+            .unwrap_or(DEFAULT_MAX_LINE_WIDTH),
+        max_width: cli.max_width.or(config.max_width).unwrap_or(0),
+        content_padding: cli
+            .content_padding
+            .or(config.content_padding)
+            .unwrap_or(DEFAULT_CONTENT_PADDING),
+        match_mode: cli.match_mode.or(config.match_mode).unwrap_or_default(),
+        case_mode: config.case_mode.unwrap_or_default(),
+        initial_query: cli.query.clone(),
+        wrap_man: config.wrap_man.unwrap_or(DEFAULT_WRAP_MAN),
+        wrap_tldr: config.wrap_tldr.unwrap_or(DEFAULT_WRAP_TLDR),
+        wrap_markers: config.wrap_markers.unwrap_or(DEFAULT_WRAP_MARKERS),
+        match_position: config.match_position.unwrap_or(DEFAULT_MATCH_POSITION),
+        compact_blank_lines: config
+            .compact_blank_lines
+            .unwrap_or(DEFAULT_COMPACT_BLANK_LINES),
+        highlight_matches: config
+            .highlight_matches
+            .unwrap_or(DEFAULT_HIGHLIGHT_MATCHES),
+        jump_while_searching: config
+            .jump_while_searching
+            .unwrap_or(DEFAULT_JUMP_WHILE_SEARCHING),
+        ascii: cli.ascii,
+        mouse: !cli.no_mouse,
+        idle_reset: cli
+            .idle_reset
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs),
+        scroll_off: cli
+            .scroll_off
+            .or(config.scroll_off)
+            .unwrap_or(DEFAULT_SCROLL_OFF),
+        tldr_dir: load_options.tldr_dir.clone(),
+        tldr_max_age_days: config
+            .tldr_max_age_days
+            .unwrap_or(DEFAULT_TLDR_MAX_AGE_DAYS),
+        preview: cli.preview.or(config.preview).unwrap_or_default(),
+        jump_to: cli.jump_to.or(config.jump_to).unwrap_or_default(),
+        on_enter: cli.on_enter.or(config.on_enter).unwrap_or_default(),
+        launcher: cli.launcher.clone().or_else(|| config.launcher.clone()),
+        focus: cli.focus,
+        description_source: config.description_source.unwrap_or_default(),
+        theme,
+    })
+}
+
+fn theme_file(cli: &Cli, config: &Config) -> Option<PathBuf> {
+    cli.theme_file.clone().or_else(|| config.theme_file.clone())
+}
+
+/// Renders the settings in effect as config file lines
+fn effective_config(
+    cli: &Cli,
+    config: &Config,
+    load: &LoadOptions,
+    tui: &TuiOptions,
+    paths: &Paths,
+) -> String {
+    let str_value = |s: &str| Some(Value::Str(s.to_string()));
+    let path_value = |p: &Option<PathBuf>| p.as_ref().map(|p| Value::Str(p.display().to_string()));
+    let int_value = |n: usize| Some(Value::Int(n as i64));
+    let list_value =
+        |items: &[String]| Some(Value::List(items.iter().cloned().map(Value::Str).collect()));
+    let enum_value = |value: Option<clap::builder::PossibleValue>| {
+        value.map(|v| Value::Str(v.get_name().to_string()))
+    };
+
+    let settings = [
+        (
+            "index_command",
+            load.index_command.as_deref().and_then(str_value),
+        ),
+        ("tab_width", int_value(load.tab_width)),
+        ("tldr_dir", path_value(&load.tldr_dir)),
+        (
+            "tldr_max_age_days",
+            int_value(tui.tldr_max_age_days as usize),
+        ),
+        ("max_concurrency", int_value(load.max_concurrency)),
+        ("max_index_size", int_value(load.max_index_size)),
+        (
+            "form_feeds",
+            enum_value(load.form_feeds.to_possible_value()),
+        ),
+        ("exclude_sections", list_value(&config.exclude_sections)),
+        ("exclude_commands", list_value(&config.exclude_commands)),
+        ("include_commands", list_value(&config.include_commands)),
+        ("fold_case", Some(Value::Bool(load.fold_case))),
+        (
+            "max_preview_matches",
+            tui.max_preview_matches.and_then(int_value),
+        ),
+        ("max_line_width", int_value(tui.max_line_width)),
+        ("max_width", int_value(tui.max_width)),
+        ("content_padding", int_value(tui.content_padding)),
+        ("match_mode", enum_value(tui.match_mode.to_possible_value())),
+        ("case", enum_value(tui.case_mode.to_possible_value())),
+        ("scroll_off", int_value(tui.scroll_off)),
+        ("wrap_man", Some(Value::Bool(tui.wrap_man))),
+        ("wrap_tldr", Some(Value::Bool(tui.wrap_tldr))),
+        ("wrap_markers", Some(Value::Bool(tui.wrap_markers))),
+        ("match_position", int_value(tui.match_position)),
+        (
+            "compact_blank_lines",
+            Some(Value::Bool(tui.compact_blank_lines)),
+        ),
+        (
+            "highlight_matches",
+            Some(Value::Bool(tui.highlight_matches)),
+        ),
+        (
+            "jump_while_searching",
+            Some(Value::Bool(tui.jump_while_searching)),
+        ),
+        ("preview", enum_value(tui.preview.to_possible_value())),
+        ("jump_to", enum_value(tui.jump_to.to_possible_value())),
+        ("on_enter", enum_value(tui.on_enter.to_possible_value())),
+        ("launcher", tui.launcher.as_deref().and_then(str_value)),
+        (
+            "description_source",
+            enum_value(tui.description_source.to_possible_value()),
+        ),
+        ("theme_file", path_value(&theme_file(cli, config))),
+    ];
+
+    // Only settable by flag, so kept out of the parsed keys
+    let mut out = format!(
+        "# config_dir: {}\n# cache_dir: {}\n# section: {}\n",
+        paths.config_dir.display(),
+        paths.cache_dir.display(),
+        load.section
+    );
+    for (key, value) in settings {
+        out += &match value {
+            Some(value) => format!("{key} = {}\n", config::format_value(&value)),
+            None => format!("# {key} is unset\n"),
+        };
+    }
+    out += "\n# Keybindings (built in)\n";
+    for (context, keys, action) in tui::KEYBINDINGS {
+        out += &format!("# {context}: {keys} = {action}\n");
+    }
+    out
+}
+
 #[cfg(test)]
 mod cli_tests {
-    
+
+    use super::*;
     use std::process::Command;
 
+    #[test]
+    fn test_query_named_like_subcommand() {
+        let cli = Cli::parse_from(["rtfm", "man"]);
+        assert!(matches!(cli.command, Some(Commands::Man)));
+        assert!(cli.query.is_none());
+
+        let cli = Cli::parse_from(["rtfm", "--", "man"]);
+        assert!(cli.command.is_none());
+        assert_eq!(cli.query.as_deref(), Some("man"));
+    }
+
+    #[test]
+    fn test_effective_config_parses_back() {
+        let cli = Cli::parse_from(["rtfm", "-s", "3"]);
+        let config = Config::default();
+        let paths = Paths {
+            config_dir: PathBuf::from("/tmp/rtfm-config"),
+            cache_dir: PathBuf::from("/tmp/rtfm-cache"),
+        };
+        let load = load_options(&cli, &config, &paths).unwrap();
+        let tui = tui_options(&cli, &config, &load).unwrap();
+
+        let text = effective_config(&cli, &config, &load, &tui, &paths);
+        assert!(text.contains("# section: 3\n"));
+        assert!(text.contains("# Global: q, Ctrl+C = Quit\n"));
+        let parsed = Config::parse(&text).unwrap();
+        assert_eq!(parsed.tab_width, Some(load.tab_width));
+        assert_eq!(parsed.wrap_tldr, Some(tui.wrap_tldr));
+    }
+
     #[test]
     fn test_cli_commands() {
         let output = Command::new("./target/release/rtfm")
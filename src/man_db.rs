@@ -1,26 +1,402 @@
+use crate::fuzzy;
 use crate::trie::Trie;
 use anyhow::{Result, anyhow};
 use regex::Regex;
-use std::collections::HashMap;
-use std::process::{Command, Stdio};
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{Mutex, Semaphore};
 use tokio::task;
 
+/// Matches the section in `name (N)`, including subsections like `3p`
+static SECTION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\((\d+[a-z]*|n)\)").unwrap());
+
+/// Extensions of compressed roff pages, which `man -l` decompresses itself
+const COMPRESSED_EXTENSIONS: [&str; 6] = ["gz", "bz2", "xz", "lzma", "Z", "zst"];
+
+/// Whether a `getman` argument names a page file rather than an indexed command
+fn is_page_file(arg: &str) -> bool {
+    arg.contains('/')
+        || Path::new(arg)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| COMPRESSED_EXTENSIONS.contains(&ext))
+}
+
+/// Entry parsed from a `man -k` line
+#[derive(Debug, PartialEq)]
+pub struct IndexEntry {
+    pub name: String,
+    pub section: String,
+    pub description: String,
+}
+
+/// Raw `man -k` line with its parse result
+pub struct IndexLine {
+    pub raw: String,
+    pub entry: Option<IndexEntry>,
+    pub kept: bool,
+}
+
+/// Index file of a page bundle, in `man -k` format
+pub const BUNDLE_INDEX: &str = "index.txt";
+
+/// Directory of a page bundle holding `<command>.<section>` rendered pages
+pub const BUNDLE_PAGES: &str = "pages";
+
+/// Subdirectory of the cache directory keeping rendered pages between runs
+pub const PAGE_CACHE_DIR: &str = "pages";
+
+/// How long a page on disk is served before it is rendered again
+const PAGE_CACHE_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Exit status `ssh` reports when it couldn't connect or authenticate
+const SSH_FAILURE: i32 = 255;
+
+/// Default cap on indexed commands, far above a typical system's section 1
+pub const DEFAULT_MAX_INDEX_SIZE: usize = 100_000;
+
+/// Default columns between tab stops
+pub const DEFAULT_TAB_WIDTH: usize = 8;
+
+/// Start of the notice shown in place of a page that failed to load
+pub const LOAD_ERROR_PREFIX: &str = "Failed to load ";
+
+/// Lines of a page, or of the notice shown in its place when loading failed
+#[derive(Clone, Debug, Default)]
+pub struct LoadedPage {
+    pub lines: Arc<Vec<String>>,
+    /// The lines explain a failed load rather than show the page
+    pub failed: bool,
+}
+
+impl LoadedPage {
+    /// Content of a page that loaded
+    pub fn new(lines: Vec<String>) -> Self {
+        Self {
+            lines: Arc::new(lines),
+            failed: false,
+        }
+    }
+
+    /// Notice standing in for a page that failed to load
+    fn failed(lines: Vec<String>) -> Self {
+        Self {
+            lines: Arc::new(lines),
+            failed: true,
+        }
+    }
+}
+
+/// Shown when the index command succeeds but yields no commands
+pub const EMPTY_INDEX_WARNING: &str = "Index built but empty — is your man database populated?";
+
+/// Content line standing for a form feed kept as a page break
+pub const PAGE_BREAK: &str = "\x0c";
+
+/// How form feeds (`\f`) in rendered man pages are shown
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum FormFeeds {
+    /// Drop them
+    #[default]
+    Strip,
+    /// Put each on a line of its own, drawn as a horizontal rule
+    Rule,
+}
+
+/// Options controlling how the man index is built
+#[derive(Clone, Debug)]
+pub struct LoadOptions {
+    /// Manual section to index; a bare number also matches its subsections
+    pub section: String,
+    /// Shell command template replacing `man -k .`; `{section}` is substituted
+    pub index_command: Option<String>,
+    /// Columns between tab stops when expanding tabs in page content
+    pub tab_width: usize,
+    /// Directory of `<command>.md` tldr pages checked before the `tldr` binary
+    pub tldr_dir: Option<PathBuf>,
+    /// Read the command list from stdin, one per line, instead of the index
+    pub from_stdin: bool,
+    /// Entries left out of the index
+    pub filter: IndexFilter,
+    /// Keep one spelling of names differing only in letter case, like `Mail` and `mail`
+    pub fold_case: bool,
+    /// Most `man`/`tldr`/`info` processes run at once
+    pub max_concurrency: usize,
+    /// Most commands kept in the index, 0 for unlimited
+    pub max_index_size: usize,
+    /// How page breaks in man output are shown
+    pub form_feeds: FormFeeds,
+    /// Directory of pre-rendered pages and their index, used instead of `man`
+    pub bundle: Option<PathBuf>,
+    /// Directory rendered man and tldr pages are kept in between runs
+    pub page_cache: Option<PathBuf>,
+    /// `user@host` whose man pages are read over `ssh` instead of the local ones
+    pub host: Option<String>,
+    /// Bumped for each line the index command prints, for a live count while it runs
+    pub progress: Option<Arc<AtomicUsize>>,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            section: "1".to_string(),
+            index_command: None,
+            tab_width: DEFAULT_TAB_WIDTH,
+            tldr_dir: None,
+            from_stdin: false,
+            filter: IndexFilter::default(),
+            fold_case: false,
+            max_concurrency: default_concurrency(),
+            max_index_size: DEFAULT_MAX_INDEX_SIZE,
+            form_feeds: FormFeeds::default(),
+            bundle: None,
+            page_cache: None,
+            host: None,
+            progress: None,
+        }
+    }
+}
+
+/// Whether the page cached at path was written recently enough to serve
+pub fn is_fresh(path: &Path) -> bool {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age < PAGE_CACHE_MAX_AGE)
+}
+
+/// Reads a page from the disk cache unless it is missing or stale
+fn read_cached_page(path: &Path) -> Option<Vec<String>> {
+    if !is_fresh(path) {
+        return None;
+    }
+    let text = std::fs::read_to_string(path).ok()?;
+    log::trace!("disk cache hit for {}", path.display());
+    Some(text.lines().map(String::from).collect())
+}
+
+/// Writes a loaded page to the disk cache; failed loads are left out so they are retried
+fn write_cached_page(path: &Path, page: &LoadedPage) {
+    if page.failed {
+        return;
+    }
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(path, page.lines.join("\n")));
+    if let Err(e) = written {
+        log::debug!("Failed to cache {}: {e}", path.display());
+    }
+}
+
+/// What a manual section holds, going by its leading digit, like `3p`
+pub fn section_name(section: &str) -> Option<&'static str> {
+    Some(match section.chars().next()? {
+        '1' => "user commands",
+        '2' => "system calls",
+        '3' => "library functions",
+        '4' => "special files",
+        '5' => "file formats",
+        '6' => "games",
+        '7' => "miscellanea",
+        '8' => "admin commands",
+        '9' => "kernel routines",
+        'n' => "Tcl/Tk commands",
+        _ => return None,
+    })
+}
+
+/// Number of CPUs, the default limit on concurrent page loads
+pub fn default_concurrency() -> usize {
+    std::thread::available_parallelism().map_or(4, |n| n.get())
+}
+
+/// Sections and command-name patterns excluded from, or allowed into, the index
+#[derive(Clone, Debug, Default)]
+pub struct IndexFilter {
+    exclude_sections: Vec<String>,
+    exclude: Vec<Regex>,
+    include: Vec<Regex>,
+}
+
+impl IndexFilter {
+    /// Builds a filter from `*`/`?` glob patterns; an empty include list allows all
+    pub fn new(
+        exclude_sections: Vec<String>,
+        exclude_commands: &[String],
+        include_commands: &[String],
+    ) -> Result<Self> {
+        let compile = |patterns: &[String]| -> Result<Vec<Regex>> {
+            patterns.iter().map(|p| glob_regex(p)).collect()
+        };
+
+        Ok(Self {
+            exclude_sections,
+            exclude: compile(exclude_commands)?,
+            include: compile(include_commands)?,
+        })
+    }
+
+    /// Checks whether entry belongs in the index
+    fn keeps(&self, entry: &IndexEntry) -> bool {
+        !self
+            .exclude_sections
+            .iter()
+            .any(|s| section_matches(s, &entry.section))
+            && !self.exclude.iter().any(|re| re.is_match(&entry.name))
+            && (self.include.is_empty() || self.include.iter().any(|re| re.is_match(&entry.name)))
+    }
+}
+
+/// Compiles a glob where `*` matches any run and `?` any single char
+fn glob_regex(pattern: &str) -> Result<Regex> {
+    let mut re = String::from("^");
+    for c in pattern.chars() {
+        match c {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    Regex::new(&re).map_err(|e| anyhow!("invalid pattern `{pattern}`: {e}"))
+}
+
+/// Commands parsed from the index with per-command metadata
+#[derive(Default)]
+struct ManIndex {
+    commands: Vec<String>,
+    man_map: HashMap<String, String>,
+    sections: HashMap<String, String>,
+    available_sections: HashMap<String, Vec<String>>,
+    /// Index lines each command name was indexed from
+    source_counts: HashMap<String, usize>,
+    /// Problems building the index that didn't stop it
+    warnings: Vec<String>,
+    /// Built from tldr pages because `man` is missing
+    tldr_only: bool,
+    /// Cut short at `max_index_size` commands
+    truncated: bool,
+}
+
+impl ManIndex {
+    /// Keeps only the first max commands, with a warning saying how to narrow
+    /// the index; 0 keeps all
+    fn cap(&mut self, max: usize) {
+        let total = self.commands.len();
+        if max == 0 || total <= max {
+            return;
+        }
+
+        self.commands.truncate(max);
+        let kept: HashSet<&str> = self.commands.iter().map(String::as_str).collect();
+        self.man_map.retain(|name, _| kept.contains(name.as_str()));
+        self.sections.retain(|name, _| kept.contains(name.as_str()));
+        self.available_sections
+            .retain(|name, _| kept.contains(name.as_str()));
+        self.source_counts
+            .retain(|name, _| kept.contains(name.as_str()));
+        self.warn_truncated(&total.to_string(), max);
+    }
+
+    /// Notes that only max of found commands were kept, saying how to narrow the index
+    fn warn_truncated(&mut self, found: &str, max: usize) {
+        self.truncated = true;
+        self.warnings.push(format!(
+            "Index has {found} commands, keeping the first {max}; narrow it with --section \
+             or exclude_commands, or raise max_index_size"
+        ));
+    }
+
+    /// Drops names equal to another but for letter case, keeping the lowercase
+    /// spelling, else the one indexed from the most lines
+    fn fold_case(&mut self) {
+        let mut best: HashMap<String, &str> = HashMap::new();
+        for name in &self.commands {
+            let count = |name: &str| self.source_counts.get(name).copied().unwrap_or(0);
+            let lower = name.to_lowercase();
+            let keep = match best.get(&lower) {
+                None => true,
+                Some(&kept) => kept != lower && (*name == lower || count(name) > count(kept)),
+            };
+            if keep {
+                best.insert(lower, name);
+            }
+        }
+
+        let kept: HashSet<String> = best.into_values().map(String::from).collect();
+        self.commands.retain(|name| kept.contains(name));
+        self.man_map.retain(|name, _| kept.contains(name));
+        self.sections.retain(|name, _| kept.contains(name));
+        self.available_sections
+            .retain(|name, _| kept.contains(name));
+        self.source_counts.retain(|name, _| kept.contains(name));
+    }
+}
+
 /// Man page database with caching
 #[derive(Clone)]
 pub struct ManDb {
     commands: Vec<String>,
     man_map: HashMap<String, String>,
-    man_cache: Arc<Mutex<HashMap<String, Arc<Vec<String>>>>>,
-    tldr_cache: Arc<Mutex<HashMap<String, Arc<Vec<String>>>>>, // New tldr cache
+    sections: HashMap<String, String>,
+    available_sections: HashMap<String, Vec<String>>,
+    source_counts: HashMap<String, usize>,
+    man_cache: Arc<Mutex<HashMap<String, LoadedPage>>>,
+    tldr_cache: Arc<Mutex<HashMap<String, LoadedPage>>>, // New tldr cache
+    info_cache: Arc<Mutex<HashMap<String, LoadedPage>>>,
+    name_cache: Arc<Mutex<HashMap<String, Option<String>>>>,
     trie: Arc<Trie>,
+    /// Shared by every task running an external command
+    limiter: Arc<Semaphore>,
+    tab_width: usize,
+    form_feeds: FormFeeds,
+    tldr_dir: Option<PathBuf>,
+    bundle: Option<PathBuf>,
+    page_cache: Option<PathBuf>,
+    host: Option<String>,
+    warnings: Vec<String>,
+    tldr_only: bool,
+    truncated: bool,
 }
 
 impl ManDb {
-    /// Loads man database for specified section
-    pub fn load(section: u8) -> Result<Self> {
-        let (commands, man_map) = Self::load_man_k(section)?;
+    /// Loads man database using the given options
+    pub fn load(options: &LoadOptions) -> Result<Self> {
+        let mut index = if options.from_stdin {
+            Self::read_command_list(std::io::stdin().lock())?
+        } else if let Some(dir) = &options.bundle {
+            Self::load_bundle_index(dir, options)?
+        } else if options.index_command.is_none() && !man_available(options.host.as_deref()) {
+            Self::load_tldr_index(options)?
+        } else {
+            Self::load_man_k(options)?
+        };
+        index.cap(options.max_index_size);
+        let ManIndex {
+            commands,
+            man_map,
+            sections,
+            available_sections,
+            source_counts,
+            warnings,
+            tldr_only,
+            truncated,
+        } = index;
+        log::info!(
+            "Indexed {} commands for section {}",
+            commands.len(),
+            options.section
+        );
+        for warning in &warnings {
+            log::warn!("{warning}");
+        }
         let mut trie = Trie::new();
 
         for cmd in &commands {
@@ -30,183 +406,1620 @@ impl ManDb {
         Ok(Self {
             commands,
             man_map,
+            sections,
+            available_sections,
+            source_counts,
             man_cache: Arc::new(Mutex::new(HashMap::new())),
             tldr_cache: Arc::new(Mutex::new(HashMap::new())), // Initialize tldr cache
+            info_cache: Arc::new(Mutex::new(HashMap::new())),
+            name_cache: Arc::new(Mutex::new(HashMap::new())),
             trie: Arc::new(trie),
+            limiter: Arc::new(Semaphore::new(options.max_concurrency.max(1))),
+            tab_width: options.tab_width,
+            form_feeds: options.form_feeds,
+            tldr_dir: options.tldr_dir.clone(),
+            bundle: options.bundle.clone(),
+            // Bundle pages are already on disk; each host keeps its own pages
+            page_cache: match (&options.page_cache, &options.host) {
+                _ if options.bundle.is_some() => None,
+                (Some(dir), Some(host)) => Some(dir.join(host)),
+                (dir, None) => dir.clone(),
+                (None, _) => None,
+            },
+            host: options.host.clone(),
+            truncated,
+            warnings,
+            tldr_only,
         })
     }
 
+    /// Host whose man pages are read over `ssh`, if not the local ones
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+
+    /// Problems building the index, for the caller to report once a terminal is available
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Whether only tldr pages can be shown, because `man` is missing
+    pub fn tldr_only(&self) -> bool {
+        self.tldr_only
+    }
+
+    /// Whether the index was cut short at `max_index_size` commands
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Names indexed from more than one line, with their line counts, most first
+    pub fn duplicate_sources(&self) -> Vec<(&str, usize)> {
+        let mut duplicates: Vec<(&str, usize)> = self
+            .source_counts
+            .iter()
+            .filter(|&(_, &count)| count > 1)
+            .map(|(name, &count)| (name.as_str(), count))
+            .collect();
+        duplicates.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        duplicates
+    }
+
     /// Gets all commands
     pub fn get_commands(&self) -> &Vec<String> {
         &self.commands
     }
 
-    /// Gets commands starting with prefix
+    /// Gets commands starting with prefix, shortest first
     pub fn commands_starting_with(&self, prefix: &str) -> Vec<String> {
-        self.trie.words_starting_with(prefix)
+        self.trie.ranked_words_starting_with(prefix)
     }
 
-    /// Displays man page in terminal
-    pub fn display_man_page(&self, command: &str) -> Result<()> {
-        Command::new("man")
-            .arg(command)
-            .stdout(Stdio::inherit())
-            .status()?;
-        Ok(())
+    /// Extends prefix by the part every command starting with it shares
+    pub fn complete_prefix(&self, prefix: &str) -> String {
+        self.trie.longest_common_prefix(prefix)
+    }
+
+    /// Gets commands whose name or description matches pattern, like apropos
+    pub fn commands_matching_regex(&self, pattern: &Regex) -> Vec<&str> {
+        self.commands
+            .iter()
+            .filter(|cmd| {
+                pattern.is_match(cmd)
+                    || self
+                        .man_map
+                        .get(*cmd)
+                        .is_some_and(|description| pattern.is_match(description))
+            })
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Gets commands fuzzy-matching query, best match first
+    pub fn commands_matching_fuzzy(&self, query: &str) -> Vec<String> {
+        fuzzy::rank(query, &self.commands)
+    }
+
+    /// Displays man page in terminal; a path to a roff file, compressed or not, is
+    /// rendered directly. Returns whether `man` found the page
+    pub fn display_man_page(&self, command: &str) -> Result<bool> {
+        if is_page_file(command) && !Path::new(command).is_file() {
+            return Err(anyhow!("No such page file: {command}"));
+        }
+        Self::display_man_page_in(command, self.get_section(command), self.host())
+    }
+
+    /// Displays the man page from section, or man's default choice when `None`.
+    /// A host's page opens in its own pager over `ssh -t`
+    pub fn display_man_page_in(
+        command: &str,
+        section: Option<&str>,
+        host: Option<&str>,
+    ) -> Result<bool> {
+        let pager = std::env::var("MANPAGER")
+            .or_else(|_| std::env::var("PAGER"))
+            .ok();
+        let status = Self::pager_command(command, section, host, pager.as_deref()).status()?;
+        if let Some(host) = host
+            && status.code() == Some(SSH_FAILURE)
+        {
+            return Err(anyhow!("ssh to {host} failed"));
+        }
+        Ok(status.success())
+    }
+
+    /// Finds every section with a page for command via `man -f`, in manual order
+    pub fn sections_for(command: &str, host: Option<&str>) -> Result<Vec<String>> {
+        let output = run_man(host, &mut man_command(host, ["-f", command]))?;
+        Ok(Self::parse_whatis_sections(
+            command,
+            &String::from_utf8_lossy(&output.stdout),
+        ))
     }
 
-    /// Gets man page content (cached)
-    pub async fn get_man_page(&self, command: &str) -> Arc<Vec<String>> {
+    /// Files `man -w` would render for command from section; a bundle page when
+    /// serving from a bundle
+    pub fn page_paths(&self, command: &str, section: Option<&str>) -> Result<Vec<PathBuf>> {
+        if let Some(dir) = &self.bundle {
+            let section = section.ok_or_else(|| anyhow!("{command} is not in the bundle"))?;
+            let path = bundle_page_path(dir, command, section);
+            return Ok(path.is_file().then_some(path).into_iter().collect());
+        }
+
+        let args = ["-w"].into_iter().chain(section).chain([command]);
+        let output = run_man(self.host(), &mut man_command(self.host(), args))?;
+        if !output.status.success() {
+            return Ok(Vec::new());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(PathBuf::from)
+            .collect())
+    }
+
+    /// Local tldr page for command, if the tldr directory has one
+    pub fn local_tldr_path(&self, command: &str) -> Option<PathBuf> {
+        let path = self.tldr_dir.as_ref()?.join(format!("{command}.md"));
+        path.is_file().then_some(path)
+    }
+
+    /// Whether the `tldr` client has a page for command
+    pub fn tldr_client_has(command: &str) -> bool {
+        Self::load_tldr_page(command, DEFAULT_TAB_WIDTH).is_ok()
+    }
+
+    /// Sections of the `man -f` lines naming command exactly
+    fn parse_whatis_sections(command: &str, output: &str) -> Vec<String> {
+        let mut sections: Vec<String> = output
+            .lines()
+            .filter_map(Self::parse_man_k_line)
+            .filter(|entry| entry.name == command)
+            .map(|entry| entry.section)
+            .collect();
+        sections.sort_by(|a, b| section_order(a).cmp(&section_order(b)));
+        sections.dedup();
+        sections
+    }
+
+    /// Builds the `man` invocation for reading a page in the user's pager. A
+    /// local `man` inherits MANPAGER and PAGER, but a remote one only sees the
+    /// host's environment, so a local pager is handed over as MANPAGER
+    fn pager_command(
+        command: &str,
+        section: Option<&str>,
+        host: Option<&str>,
+        pager: Option<&str>,
+    ) -> Command {
+        if let Some(host) = host {
+            let mut ssh = Command::new("ssh");
+            let env = pager.map(|pager| ("MANPAGER", pager));
+            ssh.args(["-t", host, "--"])
+                .arg(remote_man_line(env, section.into_iter().chain([command])));
+            return ssh;
+        }
+
+        let mut man = Command::new("man");
+        if is_page_file(command) {
+            man.arg("-l");
+        } else {
+            man.args(section);
+        }
+        man.arg(command).stdout(Stdio::inherit());
+        man
+    }
+
+    /// Gets man page content from the indexed section (cached)
+    pub async fn get_man_page(&self, command: &str) -> LoadedPage {
+        let section = self.sections.get(command).cloned();
+        self.load_cached_man_page(command.to_string(), command, section)
+            .await
+    }
+
+    /// Gets man page content from a specific section (cached)
+    pub async fn get_man_page_in(&self, command: &str, section: &str) -> LoadedPage {
+        let cache_key = format!("{command}({section})");
+        self.load_cached_man_page(cache_key, command, Some(section.to_string()))
+            .await
+    }
+
+    /// Gets man page content from the indexed section without adding it to the
+    /// memory cache, so scanning every page doesn't keep them all loaded
+    pub async fn scan_man_page(&self, command: &str) -> LoadedPage {
+        if let Some(content) = self.man_cache.lock().await.get(command) {
+            return content.clone();
+        }
+        let section = self.sections.get(command).cloned();
+        self.load_man_page_uncached(command, section).await
+    }
+
+    async fn load_cached_man_page(
+        &self,
+        cache_key: String,
+        command: &str,
+        section: Option<String>,
+    ) -> LoadedPage {
         // Check cache
         {
             let cache = self.man_cache.lock().await;
-            if let Some(content) = cache.get(command) {
+            if let Some(content) = cache.get(&cache_key) {
+                log::trace!("man cache hit for {cache_key}");
                 return content.clone();
             }
         }
+        log::debug!("man cache miss for {cache_key}");
+
+        let page = self.load_man_page_uncached(command, section).await;
+
+        // Update cache
+        let mut cache = self.man_cache.lock().await;
+        cache.insert(cache_key, page.clone());
 
-        // Load man page
+        page
+    }
+
+    /// Loads a man page from the disk cache or `man`, skipping the memory cache
+    async fn load_man_page_uncached(&self, command: &str, section: Option<String>) -> LoadedPage {
         let command_str = command.to_string();
-        let content = task::spawn_blocking(move || {
-            Self::load_man_page(&command_str)
-                .unwrap_or_else(|_| vec![format!("Failed to load man page: {}", command_str)])
+        let (tab_width, form_feeds) = (self.tab_width, self.form_feeds);
+        let host = self.host.clone();
+        let bundle = self.bundle.clone();
+        let disk_path = self.man_cache_path(command, section.as_deref());
+        let _permit = self.limiter.acquire().await.unwrap();
+        task::spawn_blocking(move || {
+            if let Some(lines) = disk_path.as_deref().and_then(read_cached_page) {
+                return LoadedPage::new(lines);
+            }
+            let page = match &bundle {
+                Some(dir) => Self::load_bundle_page(dir, &command_str, section.as_deref())
+                    .map(|lines| apply_form_feeds(lines, form_feeds)),
+                None => Self::load_man_page(
+                    &command_str,
+                    section.as_deref(),
+                    host.as_deref(),
+                    tab_width,
+                    form_feeds,
+                ),
+            }
+            .map(LoadedPage::new)
+            .unwrap_or_else(|e| {
+                log::warn!("man page for {command_str} failed to load: {e}");
+                LoadedPage::failed(vec![format!("{LOAD_ERROR_PREFIX}man page: {command_str}")])
+            });
+            if let Some(path) = &disk_path {
+                write_cached_page(path, &page);
+            }
+            page
         })
         .await
-        .unwrap();
+        .unwrap()
+    }
 
-        let content_arc = Arc::new(content);
+    /// Gets tldr page content (cached)
+    pub async fn get_tldr_page(&self, command: &str) -> LoadedPage {
+        let cache_key = self.tldr_cache_key(command);
+
+        // Check cache
+        {
+            let cache = self.tldr_cache.lock().await;
+            if let Some(content) = cache.get(&cache_key) {
+                log::trace!("tldr cache hit for {cache_key}");
+                return content.clone();
+            }
+        }
+        log::debug!("tldr cache miss for {cache_key}");
+
+        let page = self.load_tldr_page_uncached(command).await;
 
         // Update cache
-        let mut cache = self.man_cache.lock().await;
-        cache.insert(command.to_string(), content_arc.clone());
+        let mut cache = self.tldr_cache.lock().await;
+        cache.insert(cache_key, page.clone());
 
-        content_arc
+        page
     }
 
-    /// Gets tldr page content (cached)
-    pub async fn get_tldr_page(&self, command: &str) -> Arc<Vec<String>> {
+    /// Gets tldr page content without adding it to the memory cache, so going
+    /// through every page doesn't keep them all loaded
+    pub async fn scan_tldr_page(&self, command: &str) -> LoadedPage {
+        if let Some(content) = self
+            .tldr_cache
+            .lock()
+            .await
+            .get(&self.tldr_cache_key(command))
+        {
+            return content.clone();
+        }
+        self.load_tldr_page_uncached(command).await
+    }
+
+    /// Key of command's tldr page in the memory cache
+    fn tldr_cache_key(&self, command: &str) -> String {
+        // Key includes the local directory so switching it never serves stale pages
+        match &self.tldr_dir {
+            Some(dir) => format!("{}:{command}", dir.display()),
+            None => command.to_string(),
+        }
+    }
+
+    /// Loads a tldr page from the disk cache, the local directory or the `tldr`
+    /// client, skipping the memory cache
+    async fn load_tldr_page_uncached(&self, command: &str) -> LoadedPage {
+        let command_str = command.to_string();
+        let tab_width = self.tab_width;
+        let tldr_dir = self.tldr_dir.clone();
+        let disk_path = self.tldr_cache_path(command);
+        let _permit = self.limiter.acquire().await.unwrap();
+        task::spawn_blocking(move || {
+            if let Some(lines) = disk_path.as_deref().and_then(read_cached_page) {
+                return LoadedPage::new(lines);
+            }
+            let page = tldr_dir
+                .and_then(|dir| Self::load_local_tldr_page(&dir, &command_str, tab_width))
+                .map(Ok)
+                .unwrap_or_else(|| Self::load_tldr_page(&command_str, tab_width))
+                .map(LoadedPage::new)
+                .unwrap_or_else(|e| {
+                    log::warn!("tldr page for {command_str} failed to load: {e}");
+                    LoadedPage::failed(vec![format!("{LOAD_ERROR_PREFIX}tldr page: {command_str}")])
+                });
+            if let Some(path) = &disk_path {
+                write_cached_page(path, &page);
+            }
+            page
+        })
+        .await
+        .unwrap()
+    }
+
+    /// Gets info page content (cached), falling back to the man page
+    pub async fn get_info_page(&self, command: &str) -> LoadedPage {
         // Check cache
         {
-            let cache = self.tldr_cache.lock().await;
+            let cache = self.info_cache.lock().await;
             if let Some(content) = cache.get(command) {
+                log::trace!("info cache hit for {command}");
                 return content.clone();
             }
         }
+        log::debug!("info cache miss for {command}");
 
-        // Load tldr page
+        // Load info page
         let command_str = command.to_string();
-        let content = task::spawn_blocking(move || {
-            Self::load_tldr_page(&command_str)
-                .unwrap_or_else(|_| vec![format!("Failed to load tldr page: {}", command_str)])
+        let section = self.sections.get(command).cloned();
+        let (tab_width, form_feeds) = (self.tab_width, self.form_feeds);
+        let host = self.host.clone();
+        let _permit = self.limiter.acquire().await.unwrap();
+        let page = task::spawn_blocking(move || {
+            let e = match Self::load_info_page(&command_str, tab_width) {
+                Ok(lines) => return LoadedPage::new(lines),
+                Err(e) => e,
+            };
+            log::debug!("info page for {command_str} unavailable ({e}), using man");
+            let mut fallback = vec![
+                format!("No info page for {command_str}, showing man page instead"),
+                String::new(),
+            ];
+            match Self::load_man_page(
+                &command_str,
+                section.as_deref(),
+                host.as_deref(),
+                tab_width,
+                form_feeds,
+            ) {
+                Ok(lines) => {
+                    fallback.extend(lines);
+                    LoadedPage::new(fallback)
+                }
+                // The notice stays above the man page error
+                Err(_) => {
+                    fallback.push(format!("{LOAD_ERROR_PREFIX}man page: {command_str}"));
+                    LoadedPage::failed(fallback)
+                }
+            }
         })
         .await
         .unwrap();
 
-        let content_arc = Arc::new(content);
-
         // Update cache
-        let mut cache = self.tldr_cache.lock().await;
-        cache.insert(command.to_string(), content_arc.clone());
+        let mut cache = self.info_cache.lock().await;
+        cache.insert(command.to_string(), page.clone());
 
-        content_arc
+        page
     }
 
     /// Loads man page index
-    fn load_man_k(section: u8) -> Result<(Vec<String>, HashMap<String, String>)> {
-        let output = Command::new("man").arg("-k").arg(".").output()?;
+    fn load_man_k(options: &LoadOptions) -> Result<ManIndex> {
+        let mut index = ManIndex::default();
+        let output_str = Self::run_index_command(options, &mut index.warnings)?;
+        Self::index_lines(options, &output_str, index)
+    }
+
+    /// Loads the index of a bundle written by `rtfm bundle-create`
+    fn load_bundle_index(dir: &Path, options: &LoadOptions) -> Result<ManIndex> {
+        let path = dir.join(BUNDLE_INDEX);
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Failed to read bundle index {}: {e}", path.display()))?;
+        Self::index_lines(options, &text, ManIndex::default())
+    }
+
+    /// Adds every `man -k` line of output that passes the filters to index
+    fn index_lines(
+        options: &LoadOptions,
+        output_str: &str,
+        mut index: ManIndex,
+    ) -> Result<ManIndex> {
+        let section = &options.section;
+        let max = options.max_index_size;
+        for line in output_str.lines() {
+            let Some(entry) = Self::parse_man_k_line(line) else {
+                continue;
+            };
+            if !options.filter.keeps(&entry) {
+                continue;
+            }
+            let in_section = section_matches(section, &entry.section);
+            // Once full, the first name not indexed yet ends parsing
+            if in_section
+                && max > 0
+                && index.source_counts.len() >= max
+                && !index.source_counts.contains_key(&entry.name)
+            {
+                index.warn_truncated(&format!("over {max}"), max);
+                break;
+            }
+
+            let available = index
+                .available_sections
+                .entry(entry.name.clone())
+                .or_default();
+            if !available.contains(&entry.section) {
+                available.push(entry.section.clone());
+            }
+
+            if in_section {
+                // An exact section wins over a subsection documenting the same name
+                if entry.section == *section || !index.sections.contains_key(&entry.name) {
+                    index.man_map.insert(entry.name.clone(), entry.description);
+                    index.sections.insert(entry.name.clone(), entry.section);
+                }
+                *index.source_counts.entry(entry.name.clone()).or_default() += 1;
+                index.commands.push(entry.name);
+            }
+        }
+        index.commands.sort_unstable();
+        index.commands.dedup();
+        if options.fold_case {
+            index.fold_case();
+        }
+        for available in index.available_sections.values_mut() {
+            available.sort_by(|a, b| section_order(a).cmp(&section_order(b)));
+        }
+        Ok(index)
+    }
+
+    /// Indexes tldr pages in place of `man -k`, from the local directory or `tldr --list`
+    fn load_tldr_index(options: &LoadOptions) -> Result<ManIndex> {
+        let entries = match options.tldr_dir.as_deref().and_then(list_local_tldr_pages) {
+            Some(entries) => entries,
+            None => Self::run_tldr_list()
+                .map_err(|e| anyhow!("`man` not found and no tldr pages available: {e}"))?,
+        };
+
+        let mut index = ManIndex {
+            tldr_only: true,
+            ..ManIndex::default()
+        };
+        index
+            .warnings
+            .push("`man` not found, showing tldr pages only".to_string());
+        for entry in entries {
+            if !options.filter.keeps(&entry) {
+                continue;
+            }
+            if !entry.description.is_empty() {
+                index.man_map.insert(entry.name.clone(), entry.description);
+            }
+            index.commands.push(entry.name);
+        }
+        index.commands.sort_unstable();
+        index.commands.dedup();
+        Ok(index)
+    }
+
+    /// Lists the pages the `tldr` client knows, without descriptions
+    fn run_tldr_list() -> Result<Vec<IndexEntry>> {
+        let output = run_logged(Command::new("tldr").arg("--list"))?;
 
         if !output.status.success() {
-            return Err(anyhow!("Command failed"));
+            return Err(anyhow!("tldr --list failed"));
         }
 
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        let mut man_map = HashMap::new();
-        let mut commands = Vec::new();
-        let re = Regex::new(r"\((\d)\)")?;
+        // Clients print one name per line or a comma-separated list
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|name| !name.is_empty())
+            .map(|name| IndexEntry {
+                name: name.to_string(),
+                section: String::new(),
+                description: String::new(),
+            })
+            .collect())
+    }
 
-        for line in output_str.lines() {
-            if let Some((name, desc)) = line.split_once(" - ") {
-                let name_part = name.trim();
-
-                // Extract section number
-                let mut section_match = None;
-                if let Some(caps) = re.captures(name_part) {
-                    if let Some(sec) = caps.get(1) {
-                        if sec.as_str().parse::<u8>().unwrap_or(0) == section {
-                            section_match = Some(sec.as_str());
-                        }
-                    }
-                }
+    /// Reads one command name per line, without descriptions or sections
+    fn read_command_list(reader: impl BufRead) -> Result<ManIndex> {
+        let mut index = ManIndex::default();
 
-                // Apply section filter
-                if section_match.is_some() {
-                    let cleaned_name = name_part
-                        .split_whitespace()
-                        .next()
-                        .unwrap_or("")
-                        .trim()
-                        .to_string();
-
-                    if !cleaned_name.is_empty() {
-                        man_map.insert(cleaned_name.clone(), desc.trim().to_string());
-                        commands.push(cleaned_name);
-                    }
-                }
+        for line in reader.lines() {
+            let line = line.map_err(|e| anyhow!("Failed to read command list: {e}"))?;
+            let name = line.trim();
+            if !name.is_empty() {
+                index.commands.push(name.to_string());
             }
         }
-        commands.sort_unstable();
-        commands.dedup();
-        Ok((commands, man_map))
+        index.commands.sort_unstable();
+        index.commands.dedup();
+        Ok(index)
+    }
+
+    /// Runs the configured index command, falling back to `man -k .`
+    fn run_index_command(options: &LoadOptions, warnings: &mut Vec<String>) -> Result<String> {
+        let host = options.host.as_deref();
+        let progress = options.progress.as_deref();
+        let Some(template) = &options.index_command else {
+            return Self::run_man_k(host, progress);
+        };
+
+        // The fallback's lines are counted from zero, not on from the failed command's
+        let fall_back = || {
+            if let Some(progress) = progress {
+                progress.store(0, Ordering::Relaxed);
+            }
+            Self::run_man_k(host, progress)
+        };
+        match Self::run_custom_index(template, &options.section, progress) {
+            Ok(output) if output.lines().any(|l| Self::parse_man_k_line(l).is_some()) => Ok(output),
+            Ok(_) => {
+                warnings.push(format!(
+                    "Index command `{template}` produced no parseable lines, using `man -k .`"
+                ));
+                fall_back()
+            }
+            Err(e) => {
+                warnings.push(format!(
+                    "Index command `{template}` failed ({e}), using `man -k .`"
+                ));
+                fall_back()
+            }
+        }
+    }
+
+    /// Runs a user-supplied index command through the shell
+    fn run_custom_index(
+        template: &str,
+        section: &str,
+        progress: Option<&AtomicUsize>,
+    ) -> Result<String> {
+        let command = template.replace("{section}", section);
+        let output = if cfg!(windows) {
+            run_logged_counting(Command::new("cmd").arg("/C").arg(&command), progress)?
+        } else {
+            run_logged_counting(Command::new("sh").arg("-c").arg(&command), progress)?
+        };
+
+        if !output.status.success() {
+            return Err(anyhow!("exited with {}", output.status));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Runs `man -k .` and returns its output
+    fn run_man_k(host: Option<&str>, progress: Option<&AtomicUsize>) -> Result<String> {
+        let output = run_man_counting(host, &mut man_command(host, ["-k", "."]), progress)?;
+
+        if !output.status.success() {
+            return Err(anyhow!("Command failed"));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Parses a single `man -k` line into an index entry
+    fn parse_man_k_line(line: &str) -> Option<IndexEntry> {
+        let (name, desc) = line.split_once(" - ")?;
+        let name_part = name.trim();
+
+        let section = SECTION_RE.captures(name_part)?.get(1)?.as_str().to_string();
+
+        let cleaned_name = name_part.split_whitespace().next()?.trim();
+        if cleaned_name.is_empty() {
+            return None;
+        }
+
+        Some(IndexEntry {
+            name: cleaned_name.to_string(),
+            section,
+            description: desc.trim().to_string(),
+        })
+    }
+
+    /// Reports how every `man -k` line is parsed and filtered
+    pub fn debug_index(options: &LoadOptions) -> Result<Vec<IndexLine>> {
+        let section = &options.section;
+        let mut warnings = Vec::new();
+        let output_str = Self::run_index_command(options, &mut warnings)?;
+        for warning in warnings {
+            eprintln!("{warning}");
+        }
+
+        Ok(output_str
+            .lines()
+            .map(|line| {
+                let entry = Self::parse_man_k_line(line);
+                let kept = entry.as_ref().is_some_and(|e| {
+                    section_matches(section, &e.section) && options.filter.keeps(e)
+                });
+                IndexLine {
+                    raw: line.to_string(),
+                    entry,
+                    kept,
+                }
+            })
+            .collect())
     }
 
     pub fn get_description(&self, command: &str) -> Option<String> {
         self.man_map.get(command).cloned()
     }
 
+    /// Gets the description from the man page's NAME section (cached)
+    pub async fn get_name_line(&self, command: &str) -> Option<String> {
+        {
+            let cache = self.name_cache.lock().await;
+            if let Some(line) = cache.get(command) {
+                return line.clone();
+            }
+        }
+
+        let page = self.get_man_page(command).await;
+        let line = extract_name_line(&page.lines);
+
+        let mut cache = self.name_cache.lock().await;
+        cache.insert(command.to_string(), line.clone());
+        line
+    }
+
+    /// Forgets every loaded page so the next request runs the external command again
+    pub async fn clear_caches(&self) {
+        self.man_cache.lock().await.clear();
+        self.tldr_cache.lock().await.clear();
+        self.info_cache.lock().await.clear();
+        self.name_cache.lock().await.clear();
+        if let Some(dir) = &self.page_cache
+            && let Err(e) = std::fs::remove_dir_all(dir)
+            && e.kind() != ErrorKind::NotFound
+        {
+            log::warn!("Failed to clear {}: {e}", dir.display());
+        }
+        log::debug!("Page caches cleared");
+    }
+
+    /// Where the man page of command from its indexed section is kept on disk,
+    /// if pages are cached there
+    pub fn man_cache_path(&self, command: &str, section: Option<&str>) -> Option<PathBuf> {
+        let section = section.or_else(|| self.get_section(command));
+        let name = match section {
+            Some(section) => format!("{command}.{section}"),
+            None => command.to_string(),
+        };
+        // Rendering settings are part of the path so changing them never serves stale pages
+        let form_feeds = format!("{:?}", self.form_feeds).to_lowercase();
+        self.disk_cache_path(&format!("man-{}-{form_feeds}", self.tab_width), &name)
+    }
+
+    /// Where the tldr page of command is kept on disk, if pages are cached there.
+    /// Local tldr pages are read directly, so they are never cached
+    pub fn tldr_cache_path(&self, command: &str) -> Option<PathBuf> {
+        if self.tldr_dir.is_some() {
+            return None;
+        }
+        self.disk_cache_path(&format!("tldr-{}", self.tab_width), command)
+    }
+
+    fn disk_cache_path(&self, kind: &str, name: &str) -> Option<PathBuf> {
+        // Names become file names, so skip any that could leave the cache directory
+        if name.contains(['/', '\\']) || name.starts_with('.') {
+            return None;
+        }
+        Some(self.page_cache.as_ref()?.join(kind).join(name))
+    }
+
+    /// Gets an already extracted NAME description without blocking
+    pub fn cached_name_line(&self, command: &str) -> Option<String> {
+        self.name_cache
+            .try_lock()
+            .ok()?
+            .get(command)
+            .cloned()
+            .flatten()
+    }
+
+    /// Gets the manual section a command was indexed from
+    pub fn get_section(&self, command: &str) -> Option<&str> {
+        self.sections.get(command).map(String::as_str)
+    }
+
+    /// Gets every section documenting command, in manual order
+    pub fn get_available_sections(&self, command: &str) -> &[String] {
+        self.available_sections
+            .get(command)
+            .map_or(&[], Vec::as_slice)
+    }
+
     /// Loads man page content
-    fn load_man_page(command: &str) -> Result<Vec<String>> {
-        let output = Command::new("man")
-            .arg(command)
-            .env("PAGER", "cat")
-            .output()?;
+    fn load_man_page(
+        command: &str,
+        section: Option<&str>,
+        host: Option<&str>,
+        tab_width: usize,
+        form_feeds: FormFeeds,
+    ) -> Result<Vec<String>> {
+        let args = section.into_iter().chain([command]);
+        let output = run_man(host, man_command(host, args).env("PAGER", "cat"))?;
 
         if !output.status.success() {
             return Err(anyhow!("man command failed"));
         }
 
         let content = String::from_utf8(output.stdout)?;
-        Ok(content.lines().map(|s| s.to_string()).collect())
+        Ok(apply_form_feeds(
+            split_lines(&content, tab_width),
+            form_feeds,
+        ))
+    }
+
+    /// Reads a page rendered into a bundle
+    fn load_bundle_page(dir: &Path, command: &str, section: Option<&str>) -> Result<Vec<String>> {
+        let section = section.ok_or_else(|| anyhow!("{command} is not in the bundle"))?;
+        let path = bundle_page_path(dir, command, section);
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Failed to read {}: {e}", path.display()))?;
+        Ok(text.lines().map(String::from).collect())
     }
 
     /// Loads tldr page content
-    fn load_tldr_page(command: &str) -> Result<Vec<String>> {
-        let output = Command::new("tldr").arg(command).output()?;
+    fn load_tldr_page(command: &str, tab_width: usize) -> Result<Vec<String>> {
+        let output = run_logged(Command::new("tldr").arg(command))?;
 
         if !output.status.success() {
             return Err(anyhow!("tldr command failed"));
         }
 
         let content = String::from_utf8(output.stdout)?;
-        Ok(content.lines().map(|s| s.to_string()).collect())
+        Ok(split_lines(&content, tab_width))
+    }
+
+    /// Loads `<dir>/<command>.md` if present
+    fn load_local_tldr_page(dir: &Path, command: &str, tab_width: usize) -> Option<Vec<String>> {
+        let text = std::fs::read_to_string(dir.join(format!("{command}.md"))).ok()?;
+        Some(split_lines(&render_tldr_markdown(&text), tab_width))
+    }
+
+    /// Loads info page content
+    fn load_info_page(command: &str, tab_width: usize) -> Result<Vec<String>> {
+        // `info` silently renders the man page when no info node exists
+        let location = run_logged(Command::new("info").arg("-w").arg(command))?;
+        let location = String::from_utf8_lossy(&location.stdout);
+        let location = location.trim();
+        if location.is_empty() || location == "*manpages*" {
+            return Err(anyhow!("no info page for {command}"));
+        }
+
+        let output = run_logged(Command::new("info").arg("--output=-").arg(command))?;
+
+        if !output.status.success() {
+            return Err(anyhow!("info command failed"));
+        }
+
+        let content = String::from_utf8(output.stdout)?;
+        Ok(split_lines(&content, tab_width))
+    }
+}
+
+/// Runs command to completion, logging how it went and how long it took
+fn run_logged(command: &mut Command) -> std::io::Result<Output> {
+    run_logged_counting(command, None)
+}
+
+/// Like `run_logged`, bumping lines for each line of output as it arrives
+fn run_logged_counting(
+    command: &mut Command,
+    lines: Option<&AtomicUsize>,
+) -> std::io::Result<Output> {
+    let started = Instant::now();
+    let output = match lines {
+        Some(lines) => output_counting(command, lines),
+        None => command.output(),
+    };
+    let line = format!(
+        "{} {}",
+        command.get_program().to_string_lossy(),
+        command
+            .get_args()
+            .map(|a| a.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    match &output {
+        Ok(output) => log::debug!(
+            "`{line}` exited with {} in {:?}, {} bytes of output",
+            output.status,
+            started.elapsed(),
+            output.stdout.len()
+        ),
+        Err(e) => log::warn!("`{line}` failed to start: {e}"),
+    }
+    output
+}
+
+/// Collects command's output like `Command::output`, reading stdout a line at a time
+fn output_counting(command: &mut Command, lines: &AtomicUsize) -> std::io::Result<Output> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Drained alongside stdout so a chatty stderr can't fill its pipe and stall the child
+    let stderr = child.stderr.take().map(|mut pipe| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = pipe.read_to_end(&mut buf);
+            buf
+        })
+    });
+
+    let mut stdout = Vec::new();
+    let read: std::io::Result<()> = child.stdout.take().map_or(Ok(()), |pipe| {
+        let mut reader = BufReader::new(pipe);
+        while reader.read_until(b'\n', &mut stdout)? > 0 {
+            lines.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(())
+    });
+    // Reaped even when reading failed, so no zombie is left behind
+    let status = child.wait()?;
+    read?;
+    let stderr = stderr
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+    Ok(Output {
+        status,
+        stdout,
+        stderr,
+    })
+}
+
+/// Location of command's page from section inside a bundle
+pub fn bundle_page_path(dir: &Path, command: &str, section: &str) -> PathBuf {
+    dir.join(BUNDLE_PAGES).join(format!("{command}.{section}"))
+}
+
+/// Whether a `man` binary can be started at all
+fn man_available(host: Option<&str>) -> bool {
+    match run_logged(&mut man_command(host, ["-w"])) {
+        // A missing `ssh` is left for the index to report
+        Err(e) => host.is_some() || e.kind() != ErrorKind::NotFound,
+        // A remote shell exits with 127 for a command it can't find
+        Ok(output) => host.is_none() || output.status.code() != Some(127),
     }
 }
 
+/// Builds a `man` invocation with args, run over `ssh` on host when set
+fn man_command<'a>(host: Option<&str>, args: impl IntoIterator<Item = &'a str>) -> Command {
+    match host {
+        Some(host) => {
+            let mut ssh = Command::new("ssh");
+            // No password prompts, so a background load fails instead of hanging
+            ssh.args(["-o", "BatchMode=yes", host, "--"])
+                .arg(remote_man_line(Some(("PAGER", "cat")), args));
+            ssh
+        }
+        None => {
+            let mut man = Command::new("man");
+            man.args(args);
+            man
+        }
+    }
+}
+
+/// Remote shell line running `man` with args and an optional environment
+/// variable, each quoted so the shell passes it as is
+fn remote_man_line<'a>(
+    env: Option<(&str, &str)>,
+    args: impl IntoIterator<Item = &'a str>,
+) -> String {
+    let mut line = String::new();
+    if let Some((name, value)) = env {
+        line.push_str(&format!("{name}={} ", shell_quote(value)));
+    }
+    line.push_str("man");
+    for arg in args {
+        line.push(' ');
+        line.push_str(&shell_quote(arg));
+    }
+    line
+}
+
+/// Single-quotes arg so a POSIX shell passes it as one word, as is
+pub fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Runs a `man_command`, turning a failed `ssh` connection into a clear error
+fn run_man(host: Option<&str>, command: &mut Command) -> Result<Output> {
+    run_man_counting(host, command, None)
+}
+
+/// Like `run_man`, bumping lines for each line of output as it arrives
+fn run_man_counting(
+    host: Option<&str>,
+    command: &mut Command,
+    lines: Option<&AtomicUsize>,
+) -> Result<Output> {
+    let output = run_logged_counting(command, lines).map_err(|e| match host {
+        Some(host) => anyhow!("Failed to run ssh for {host}: {e}"),
+        None => e.into(),
+    })?;
+    if let Some(host) = host
+        && output.status.code() == Some(SSH_FAILURE)
+    {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("ssh to {host} failed: {}", stderr.trim()));
+    }
+    Ok(output)
+}
+
+/// Lists `<command>.md` pages in dir with their `> ` description line
+fn list_local_tldr_pages(dir: &Path) -> Option<Vec<IndexEntry>> {
+    let entries: Vec<IndexEntry> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "md" {
+                return None;
+            }
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            let text = std::fs::read_to_string(&path).unwrap_or_default();
+            Some(IndexEntry {
+                name,
+                section: String::new(),
+                description: tldr_description(&text).unwrap_or_default(),
+            })
+        })
+        .collect();
+    (!entries.is_empty()).then_some(entries)
+}
+
+/// First `> ` line of a tldr page, its one-line description
+fn tldr_description(text: &str) -> Option<String> {
+    text.lines()
+        .find_map(|line| line.trim().strip_prefix("> "))
+        .map(|desc| desc.trim().to_string())
+}
+
+/// Extracts the description from the first line of a man page's NAME section
+fn extract_name_line(content: &[String]) -> Option<String> {
+    let heading = content.iter().position(|line| line.trim() == "NAME")?;
+    let line = content[heading + 1..]
+        .iter()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())?;
+
+    let description = line.split_once(" - ").map_or(line, |(_, desc)| desc).trim();
+    (!description.is_empty()).then(|| description.to_string())
+}
+
+/// Converts tldr-flavored markdown into plain text like the `tldr` client prints
+fn render_tldr_markdown(text: &str) -> String {
+    let mut out = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some(title) = trimmed.strip_prefix("# ") {
+            out.push(title.to_string());
+        } else if let Some(desc) = trimmed.strip_prefix("> ") {
+            out.push(format!("  {desc}"));
+        } else if let Some(code) = trimmed.strip_prefix('`').and_then(|t| t.strip_suffix('`')) {
+            out.push(format!("      {code}"));
+        } else if let Some(item) = trimmed.strip_prefix("- ") {
+            out.push(format!("  - {item}"));
+        } else {
+            out.push(trimmed.to_string());
+        }
+    }
+    out.join("\n")
+}
+
+/// Checks whether an entry's section passes the section filter.
+///
+/// A bare number like `3` also matches its subsections (`3p`, `3ssl`).
+fn section_matches(filter: &str, section: &str) -> bool {
+    if filter == section {
+        return true;
+    }
+    filter.chars().all(|c| c.is_ascii_digit())
+        && section
+            .strip_prefix(filter)
+            .is_some_and(|rest| rest.chars().next().is_some_and(|c| c.is_ascii_alphabetic()))
+}
+
+/// Time since the newest page in a local tldr directory was modified
+pub fn tldr_dir_age(dir: &Path) -> Option<Duration> {
+    let newest = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok())
+        .max()?;
+    SystemTime::now().duration_since(newest).ok()
+}
+
+/// Sort key putting sections in manual order: `1`, `1ssl`, `2`, …, `10`, `n`
+pub fn section_order(section: &str) -> (u32, &str) {
+    let digits = section.chars().take_while(char::is_ascii_digit).count();
+    let number = section[..digits].parse().unwrap_or(u32::MAX);
+    (number, &section[digits..])
+}
+
+/// Splits content into lines with tabs expanded
+fn split_lines(content: &str, tab_width: usize) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| expand_tabs(line, tab_width))
+        .collect()
+}
+
+/// Strips form feeds from lines, or splits them out as `PAGE_BREAK` lines
+fn apply_form_feeds(lines: Vec<String>, mode: FormFeeds) -> Vec<String> {
+    if !lines.iter().any(|line| line.contains('\x0c')) {
+        return lines;
+    }
+
+    let mut out = Vec::with_capacity(lines.len());
+    for line in lines {
+        if !line.contains('\x0c') {
+            out.push(line);
+            continue;
+        }
+        match mode {
+            FormFeeds::Strip => out.push(line.replace('\x0c', "")),
+            FormFeeds::Rule => {
+                for (i, part) in line.split('\x0c').enumerate() {
+                    if i > 0 {
+                        out.push(PAGE_BREAK.to_string());
+                    }
+                    if !part.is_empty() {
+                        out.push(part.to_string());
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Replaces tabs with spaces up to the next tab stop
+fn expand_tabs(line: &str, tab_width: usize) -> String {
+    if !line.contains('\t') || tab_width == 0 {
+        return line.to_string();
+    }
+
+    let mut out = String::with_capacity(line.len());
+    let mut column = 0;
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = tab_width - column % tab_width;
+            out.extend(std::iter::repeat_n(' ', spaces));
+            column += spaces;
+        } else {
+            out.push(c);
+            column += 1;
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod man_db_tests {
     use super::*;
-    
+
     use tokio::runtime::Runtime;
 
+    #[test]
+    fn test_parse_man_k_line() {
+        let entry =
+            ManDb::parse_man_k_line("ls (1)               - list directory contents").unwrap();
+        assert_eq!(
+            entry,
+            IndexEntry {
+                name: "ls".to_string(),
+                section: "1".to_string(),
+                description: "list directory contents".to_string(),
+            }
+        );
+
+        let entry = ManDb::parse_man_k_line("pthread_create (3p) - create a thread").unwrap();
+        assert_eq!(entry.section, "3p");
+        let entry = ManDb::parse_man_k_line("openssl (1ssl) - OpenSSL tool").unwrap();
+        assert_eq!(entry.section, "1ssl");
+
+        assert!(ManDb::parse_man_k_line("garbage line").is_none());
+        assert!(ManDb::parse_man_k_line("nosection - description").is_none());
+    }
+
+    #[test]
+    fn test_section_matches() {
+        assert!(section_matches("1", "1"));
+        assert!(section_matches("1", "1ssl"));
+        assert!(section_matches("3p", "3p"));
+        assert!(!section_matches("3p", "3"));
+        assert!(!section_matches("1", "10"));
+        assert!(!section_matches("3", "1"));
+    }
+
+    #[test]
+    fn test_index_filter() {
+        let entry = |name: &str, section: &str| IndexEntry {
+            name: name.to_string(),
+            section: section.to_string(),
+            description: String::new(),
+        };
+
+        let filter = IndexFilter::new(vec!["3".to_string()], &["git-*".to_string()], &[]).unwrap();
+        assert!(filter.keeps(&entry("git", "1")));
+        assert!(!filter.keeps(&entry("git-log", "1")));
+        assert!(!filter.keeps(&entry("pthread_create", "3p")));
+
+        let filter = IndexFilter::new(Vec::new(), &[], &["l?".to_string()]).unwrap();
+        assert!(filter.keeps(&entry("ls", "1")));
+        assert!(!filter.keeps(&entry("lsof", "1")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_available_sections() {
+        let options = LoadOptions {
+            index_command: Some(
+                "printf 'printf (3p) - a\\nprintf (3) - b\\nprintf (1) - c\\nls (1) - d\\n'"
+                    .to_string(),
+            ),
+            ..LoadOptions::default()
+        };
+
+        let index = ManDb::load_man_k(&options).unwrap();
+        assert_eq!(index.commands, vec!["ls", "printf"]);
+        assert_eq!(index.available_sections["printf"], vec!["1", "3", "3p"]);
+        assert_eq!(index.available_sections["ls"], vec!["1"]);
+        assert_eq!(index.source_counts["printf"], 1);
+
+        let options = LoadOptions {
+            section: "3".to_string(),
+            ..options
+        };
+        let index = ManDb::load_man_k(&options).unwrap();
+        assert_eq!(index.commands, vec!["printf"]);
+        assert_eq!(index.source_counts["printf"], 2);
+    }
+
+    #[test]
+    fn test_pager_command_honors_pager() {
+        // A local man reads the pager from the environment it inherits
+        let man = ManDb::pager_command("ls", Some("1"), None, Some("bat -l man"));
+        assert_eq!(man.get_program(), "man");
+        assert_eq!(man.get_envs().count(), 0);
+        assert_eq!(man.get_args().collect::<Vec<_>>(), ["1", "ls"]);
+
+        let ssh = ManDb::pager_command("ls", Some("1"), Some("web1"), Some("bat -l man"));
+        assert_eq!(ssh.get_program(), "ssh");
+        assert_eq!(
+            ssh.get_args().collect::<Vec<_>>(),
+            ["-t", "web1", "--", "MANPAGER='bat -l man' man '1' 'ls'"]
+        );
+        let ssh = ManDb::pager_command("ls", None, Some("web1"), None);
+        assert_eq!(ssh.get_args().last().unwrap(), "man 'ls'");
+    }
+
+    #[test]
+    fn test_remote_man_command() {
+        let ssh = man_command(Some("admin@web1"), ["1", "it's"]);
+        assert_eq!(ssh.get_program(), "ssh");
+        let args: Vec<_> = ssh.get_args().collect();
+        assert_eq!(
+            args,
+            [
+                "-o",
+                "BatchMode=yes",
+                "admin@web1",
+                "--",
+                r"PAGER='cat' man '1' 'it'\''s'"
+            ]
+        );
+        assert_eq!(man_command(None, ["-k", "."]).get_program(), "man");
+
+        let man_db = ManDb::load(&LoadOptions {
+            index_command: Some("echo 'ls (1) - list directory contents'".to_string()),
+            page_cache: Some(PathBuf::from("/cache")),
+            host: Some("admin@web1".to_string()),
+            ..LoadOptions::default()
+        })
+        .unwrap();
+        assert!(
+            man_db
+                .man_cache_path("ls", None)
+                .unwrap()
+                .starts_with("/cache/admin@web1")
+        );
+    }
+
+    #[test]
+    fn test_parse_whatis_sections() {
+        let output = "printf (3) - formatted output\nprintf (1) - format and print data\n\
+                      printf (3p) - print formatted output\nprintf-x (1) - other\n";
+        assert_eq!(
+            ManDb::parse_whatis_sections("printf", output),
+            ["1", "3", "3p"]
+        );
+        assert!(ManDb::parse_whatis_sections("nope", "nope: nothing appropriate.").is_empty());
+    }
+
+    #[test]
+    fn test_pager_command_reads_page_files() {
+        let man = ManDb::pager_command("./foo.1", None, None, None);
+        assert_eq!(man.get_args().collect::<Vec<_>>(), ["-l", "./foo.1"]);
+        let man = ManDb::pager_command("foo.1.gz", Some("1"), None, None);
+        assert_eq!(man.get_args().collect::<Vec<_>>(), ["-l", "foo.1.gz"]);
+
+        assert!(is_page_file("foo.1.xz"));
+        assert!(!is_page_file("python3.12"));
+    }
+
+    #[test]
+    fn test_tldr_dir_age() {
+        let dir = std::env::temp_dir().join(format!("rtfm-tldr-age-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(tldr_dir_age(&dir).is_none());
+
+        std::fs::write(dir.join("tar.md"), "# tar").unwrap();
+        let age = tldr_dir_age(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(age < Duration::from_secs(60));
+        assert!(tldr_dir_age(&dir).is_none());
+    }
+
+    #[test]
+    fn test_extract_name_line() {
+        let content: Vec<String> = [
+            "LS(1)",
+            "",
+            "NAME",
+            "       ls - list directory contents",
+            "",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_eq!(
+            extract_name_line(&content).as_deref(),
+            Some("list directory contents")
+        );
+        assert!(extract_name_line(&["no heading".to_string()]).is_none());
+    }
+
+    #[test]
+    fn test_render_tldr_markdown() {
+        let page = "# tar\n\n> Archiving utility.\n\n- Create an archive:\n\n`tar cf {{target.tar}} {{file}}`\n";
+        assert_eq!(
+            render_tldr_markdown(page),
+            "tar\n\n  Archiving utility.\n\n  - Create an archive:\n\n      tar cf {{target.tar}} {{file}}"
+        );
+    }
+
+    #[test]
+    fn test_local_tldr_page() {
+        let dir = std::env::temp_dir().join(format!("rtfm-tldr-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("deploy.md"), "# deploy\n\n> Ship it.\n").unwrap();
+
+        let content = ManDb::load_local_tldr_page(&dir, "deploy", 8).unwrap();
+        assert_eq!(content, vec!["deploy", "", "  Ship it."]);
+        assert!(ManDb::load_local_tldr_page(&dir, "missing", 8).is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_local_tldr_index() {
+        let dir = std::env::temp_dir().join(format!("rtfm-tldr-index-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("tar.md"), "# tar\n\n> Archiving utility.\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a page").unwrap();
+
+        let options = LoadOptions {
+            tldr_dir: Some(dir.clone()),
+            ..LoadOptions::default()
+        };
+        let index = ManDb::load_tldr_index(&options).unwrap();
+        assert!(index.tldr_only);
+        assert_eq!(index.commands, vec!["tar"]);
+        assert_eq!(index.man_map["tar"], "Archiving utility.");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fold_case_duplicates() {
+        let mut options = LoadOptions {
+            index_command: Some(
+                "printf 'Mail (1) - a\\nmail (1) - b\\nGET (1) - c\\nGet (1) - d\\nGet (1) - e\\n'"
+                    .to_string(),
+            ),
+            ..LoadOptions::default()
+        };
+        let index = ManDb::load_man_k(&options).unwrap();
+        assert_eq!(index.commands, vec!["GET", "Get", "Mail", "mail"]);
+
+        options.fold_case = true;
+        let index = ManDb::load_man_k(&options).unwrap();
+        assert_eq!(index.commands, vec!["Get", "mail"]);
+        assert_eq!(index.man_map["mail"], "b");
+        assert!(!index.man_map.contains_key("Mail"));
+        assert!(!index.sections.contains_key("GET"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_index_progress() {
+        let progress = Arc::new(AtomicUsize::new(0));
+        let options = LoadOptions {
+            index_command: Some("printf 'a (1) - x\\nb (1) - y\\nc (1) - z\\n'".to_string()),
+            progress: Some(progress.clone()),
+            ..LoadOptions::default()
+        };
+        let index = ManDb::load_man_k(&options).unwrap();
+        assert_eq!(index.commands, vec!["a", "b", "c"]);
+        assert_eq!(progress.load(Ordering::Relaxed), 3);
+
+        // Without `man` the fallback fails, leaving nothing counted
+        let options = LoadOptions {
+            index_command: Some("printf 'junk\\njunk\\n'".to_string()),
+            progress: Some(progress.clone()),
+            ..LoadOptions::default()
+        };
+        if ManDb::load_man_k(&options).is_err() {
+            assert_eq!(progress.load(Ordering::Relaxed), 0);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_index_cap() {
+        let options = LoadOptions {
+            index_command: Some("printf 'a (1) - x\\nb (1) - y\\nc (1) - z\\n'".to_string()),
+            ..LoadOptions::default()
+        };
+        let mut index = ManDb::load_man_k(&options).unwrap();
+        index.cap(0);
+        assert_eq!(index.commands.len(), 3);
+        assert!(!index.truncated);
+
+        index.cap(2);
+        assert_eq!(index.commands, vec!["a", "b"]);
+        assert!(!index.man_map.contains_key("c"));
+        assert!(index.warnings[0].contains("3 commands"));
+        assert!(index.truncated);
+
+        let options = LoadOptions {
+            index_command: Some(
+                "printf 'b (1) - y\\na (1) - x\\nb (8) - w\\nc (1) - z\\n'".to_string(),
+            ),
+            max_index_size: 2,
+            ..LoadOptions::default()
+        };
+        let index = ManDb::load_man_k(&options).unwrap();
+        assert_eq!(index.commands, vec!["a", "b"]);
+        assert!(!index.available_sections.contains_key("c"));
+        assert!(index.warnings[0].contains("over 2 commands"));
+        assert!(index.truncated);
+    }
+
+    #[test]
+    fn test_apply_form_feeds() {
+        let lines = || vec!["a".to_string(), "\x0cb".to_string(), "c\x0c".to_string()];
+        assert_eq!(apply_form_feeds(lines(), FormFeeds::Strip), ["a", "b", "c"]);
+        assert_eq!(
+            apply_form_feeds(lines(), FormFeeds::Rule),
+            ["a", PAGE_BREAK, "b", "c", PAGE_BREAK]
+        );
+    }
+
+    #[test]
+    fn test_expand_tabs() {
+        assert_eq!(expand_tabs("a\tb", 8), "a       b");
+        assert_eq!(expand_tabs("abcd\tef\tg", 4), "abcd    ef  g");
+        assert_eq!(expand_tabs("\t", 2), "  ");
+        assert_eq!(expand_tabs("no tabs", 8), "no tabs");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_custom_index_command() {
+        let options = LoadOptions {
+            section: "8".to_string(),
+            index_command: Some("echo 'mount ({section}) - mount a filesystem'".to_string()),
+            ..LoadOptions::default()
+        };
+
+        let index = ManDb::load_man_k(&options).unwrap();
+        assert_eq!(index.commands, vec!["mount"]);
+        assert_eq!(index.man_map["mount"], "mount a filesystem");
+        assert_eq!(index.sections["mount"], "8");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_commands_matching_regex() {
+        let options = LoadOptions {
+            index_command: Some(
+                "printf 'cp (1) - copy files\\nls (1) - list directory contents\\n'".to_string(),
+            ),
+            ..LoadOptions::default()
+        };
+        let man_db = ManDb::load(&options).unwrap();
+
+        let pattern = Regex::new(r"(?i)\bfiles?\b").unwrap();
+        assert_eq!(man_db.commands_matching_regex(&pattern), vec!["cp"]);
+        let pattern = Regex::new("^l").unwrap();
+        assert_eq!(man_db.commands_matching_regex(&pattern), vec!["ls"]);
+    }
+
+    #[test]
+    fn test_read_command_list() {
+        let input = "rg\n  fd \n\nrg\nbat\n";
+        let index = ManDb::read_command_list(input.as_bytes()).unwrap();
+        assert_eq!(index.commands, vec!["bat", "fd", "rg"]);
+        assert!(index.man_map.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_clear_caches() {
+        let rt = Runtime::new().unwrap();
+        let man_db = ManDb::load(&LoadOptions {
+            index_command: Some("printf 'nope (1) - missing\\n'".to_string()),
+            ..LoadOptions::default()
+        })
+        .unwrap();
+
+        rt.block_on(async {
+            man_db.get_man_page("nope").await;
+            man_db.get_tldr_page("nope").await;
+            assert_eq!(man_db.man_cache.lock().await.len(), 1);
+
+            man_db.clear_caches().await;
+            assert!(man_db.man_cache.lock().await.is_empty());
+            assert!(man_db.tldr_cache.lock().await.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_bundle() {
+        let dir = std::env::temp_dir().join(format!("rtfm-bundle-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join(BUNDLE_PAGES)).unwrap();
+        std::fs::write(dir.join(BUNDLE_INDEX), "ls (1) - list directory contents\n").unwrap();
+        std::fs::write(
+            bundle_page_path(&dir, "ls", "1"),
+            "LS(1)\n\nNAME\n  ls - list",
+        )
+        .unwrap();
+
+        let rt = Runtime::new().unwrap();
+        let man_db = ManDb::load(&LoadOptions {
+            bundle: Some(dir.clone()),
+            ..LoadOptions::default()
+        })
+        .unwrap();
+        assert_eq!(man_db.get_commands(), &vec!["ls".to_string()]);
+        assert_eq!(
+            man_db.page_paths("ls", Some("1")).unwrap(),
+            vec![bundle_page_path(&dir, "ls", "1")]
+        );
+        assert!(man_db.page_paths("ls", Some("8")).unwrap().is_empty());
+        rt.block_on(async {
+            // Scanning reads the page without keeping it in memory
+            assert_eq!(man_db.scan_man_page("ls").await.lines[0], "LS(1)");
+            assert!(man_db.man_cache.lock().await.is_empty());
+
+            let page = man_db.get_man_page("ls").await;
+            assert_eq!(page.lines[0], "LS(1)");
+            assert!(!page.failed);
+            assert!(man_db.get_man_page("cat").await.failed);
+        });
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_page_cache() {
+        let dir = std::env::temp_dir().join(format!("rtfm-page-cache-{}", std::process::id()));
+        let rt = Runtime::new().unwrap();
+        let man_db = ManDb::load(&LoadOptions {
+            index_command: Some("echo 'ls (1) - list directory contents'".to_string()),
+            page_cache: Some(dir.clone()),
+            ..LoadOptions::default()
+        })
+        .unwrap();
+
+        let path = man_db.man_cache_path("ls", None).unwrap();
+        assert!(path.starts_with(&dir));
+        assert!(path.ends_with("ls.1"));
+        assert!(man_db.man_cache_path("../ls", None).is_none());
+        assert!(!is_fresh(&path));
+
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "LS(1)\n\nNAME").unwrap();
+        assert!(is_fresh(&path));
+        rt.block_on(async {
+            assert_eq!(man_db.get_man_page("ls").await.lines[0], "LS(1)");
+            man_db.clear_caches().await;
+        });
+        assert!(!dir.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stale_tldr_cache_is_refreshed() {
+        let dir = std::env::temp_dir().join(format!("rtfm-tldr-refresh-{}", std::process::id()));
+        let rt = Runtime::new().unwrap();
+        let man_db = ManDb::load(&LoadOptions {
+            index_command: Some("echo 'tar (1) - archiving utility'".to_string()),
+            page_cache: Some(dir.clone()),
+            ..LoadOptions::default()
+        })
+        .unwrap();
+
+        let path = man_db.tldr_cache_path("tar").unwrap();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "cached tar").unwrap();
+        rt.block_on(async {
+            assert_eq!(man_db.scan_tldr_page("tar").await.lines[0], "cached tar");
+            assert!(man_db.tldr_cache.lock().await.is_empty());
+            assert_eq!(man_db.get_tldr_page("tar").await.lines[0], "cached tar");
+        });
+
+        let old = SystemTime::now() - PAGE_CACHE_MAX_AGE - Duration::from_secs(60);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        // The page is loaded again from the tldr client, whether or not it's installed
+        rt.block_on(async {
+            man_db.tldr_cache.lock().await.clear();
+            assert_ne!(man_db.get_tldr_page("tar").await.lines[0], "cached tar");
+        });
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_cache_behavior() {
         let rt = Runtime::new().unwrap();
-        let man_db = ManDb::load(1).unwrap();
+        let man_db = ManDb::load(&LoadOptions::default()).unwrap();
 
         rt.block_on(async {
-            let content = man_db.get_man_page("ls").await;
+            let content = man_db.get_man_page("ls").await.lines;
             assert!(!content.is_empty());
 
-            let cached_content = man_db.get_man_page("ls").await;
+            let cached_content = man_db.get_man_page("ls").await.lines;
             assert_eq!(content.len(), cached_content.len());
 
             let cache = man_db.man_cache.lock().await;
@@ -0,0 +1,182 @@
+use clap::Command;
+use std::fmt::Write;
+
+/// Renders a roff man page for `cmd` with KEYBINDINGS and FILTER SYNTAX sections
+pub fn render(
+    cmd: &Command,
+    keybindings: &[(&str, &str, &str)],
+    filter_syntax: &[(&str, &str)],
+) -> String {
+    // Building fills in value arity and the generated help/version flags
+    let mut cmd = cmd.clone();
+    cmd.build();
+    let cmd = &cmd;
+
+    let name = cmd.get_name();
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        ".TH {} 1 \"\" \"{name} {}\"",
+        name.to_uppercase(),
+        cmd.get_version().unwrap_or_default()
+    );
+
+    out.push_str(".SH NAME\n");
+    let about = cmd.get_about().map(|a| a.to_string()).unwrap_or_default();
+    let _ = writeln!(out, "{name} \\- {}", escape(&about));
+
+    out.push_str(".SH SYNOPSIS\n");
+    let mut synopsis = format!("\\fB{name}\\fR [\\fIOPTIONS\\fR]");
+    for arg in cmd.get_positionals() {
+        let _ = write!(
+            synopsis,
+            " [\\fI{}\\fR]",
+            arg.get_id().as_str().to_uppercase()
+        );
+    }
+    let _ = writeln!(out, "{synopsis} [\\fICOMMAND\\fR]");
+
+    out.push_str(".SH OPTIONS\n");
+    write_args(&mut out, cmd);
+
+    let subcommands: Vec<&Command> = cmd.get_subcommands().filter(|c| !c.is_hide_set()).collect();
+    if !subcommands.is_empty() {
+        out.push_str(".SH COMMANDS\n");
+        for sub in subcommands {
+            let mut usage = format!("\\fB{}\\fR", sub.get_name());
+            for arg in sub.get_positionals() {
+                let _ = write!(usage, " \\fI{}\\fR", arg.get_id().as_str().to_uppercase());
+            }
+            let _ = writeln!(out, ".TP\n{usage}");
+            let about = sub.get_about().map(|a| a.to_string()).unwrap_or_default();
+            let _ = writeln!(out, "{}", escape(&about));
+            write_args(&mut out, sub);
+        }
+    }
+
+    out.push_str(".SH KEYBINDINGS\n");
+    let mut context = "";
+    for (ctx, key, action) in keybindings {
+        if *ctx != context {
+            context = ctx;
+            let _ = writeln!(out, ".SS {}", escape(ctx));
+        }
+        let _ = writeln!(out, ".TP\n\\fB{}\\fR\n{}", escape(key), escape(action));
+    }
+
+    out.push_str(".SH FILTER SYNTAX\n");
+    for (syntax, meaning) in filter_syntax {
+        let _ = writeln!(out, ".TP\n\\fB{}\\fR\n{}", escape(syntax), escape(meaning));
+    }
+
+    out
+}
+
+/// Renders keybindings one per line as tab-separated context, keys and action,
+/// for cheatsheets and scripts
+pub fn render_keymap(keybindings: &[(&str, &str, &str)]) -> String {
+    let mut out = String::new();
+    for (ctx, key, action) in keybindings {
+        let _ = writeln!(out, "{ctx}\t{key}\t{action}");
+    }
+    out
+}
+
+/// Writes `.TP` entries for every visible flag of `cmd`
+fn write_args(out: &mut String, cmd: &Command) {
+    for arg in cmd.get_arguments() {
+        if arg.is_hide_set() || arg.is_positional() {
+            continue;
+        }
+
+        let mut flags = Vec::new();
+        if let Some(short) = arg.get_short() {
+            flags.push(format!("\\fB\\-{short}\\fR"));
+        }
+        if let Some(long) = arg.get_long() {
+            flags.push(format!("\\fB\\-\\-{}\\fR", escape(long)));
+        }
+
+        let takes_value = arg.get_num_args().is_some_and(|n| n.takes_values());
+        let value = if takes_value {
+            let names = arg
+                .get_value_names()
+                .map(|n| {
+                    n.iter()
+                        .map(|s| s.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .unwrap_or_else(|| arg.get_id().as_str().to_uppercase());
+            format!(" \\fI{}\\fR", escape(&names))
+        } else {
+            String::new()
+        };
+
+        let help = arg.get_help().map(|h| h.to_string()).unwrap_or_default();
+        let _ = writeln!(out, ".TP\n{}{value}\n{}", flags.join(", "), escape(&help));
+    }
+}
+
+/// Escapes text for roff
+fn escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{escaped}")
+    } else {
+        escaped
+    }
+}
+
+#[cfg(test)]
+mod manpage_tests {
+    use super::*;
+    use clap::{Arg, ArgAction};
+
+    #[test]
+    fn test_render_manpage() {
+        let cmd = Command::new("rtfm")
+            .version("1.0")
+            .about("Browse man pages")
+            .arg(
+                Arg::new("section")
+                    .short('s')
+                    .long("section")
+                    .help("Manual section"),
+            )
+            .arg(Arg::new("query"))
+            .subcommand(
+                Command::new("getman")
+                    .about("Show man page")
+                    .arg(Arg::new("command").required(true))
+                    .arg(Arg::new("all").long("all").action(ArgAction::SetTrue)),
+            );
+
+        let roff = render(
+            &cmd,
+            &[("Content", "n", "Next match")],
+            &[("^prefix", "Match by prefix")],
+        );
+        assert!(roff.starts_with(".TH RTFM 1"));
+        assert!(roff.contains("rtfm \\- Browse man pages"));
+        assert!(roff.contains("[\\fIOPTIONS\\fR] [\\fIQUERY\\fR] [\\fICOMMAND\\fR]"));
+        assert!(roff.contains("\\fB\\-s\\fR, \\fB\\-\\-section\\fR \\fISECTION\\fR"));
+        assert!(roff.contains("\\fBgetman\\fR \\fICOMMAND\\fR"));
+        assert!(roff.contains("\\fB\\-\\-all\\fR\n"));
+        assert!(roff.contains(".SS Content\n.TP\n\\fBn\\fR\nNext match"));
+        assert!(roff.contains(".SH FILTER SYNTAX\n.TP\n\\fB^prefix\\fR\nMatch by prefix"));
+    }
+
+    #[test]
+    fn test_render_keymap() {
+        let keymap = render_keymap(&[("Content", "n", "Next match"), ("Search", "Esc", "Cancel")]);
+        assert_eq!(keymap, "Content\tn\tNext match\nSearch\tEsc\tCancel\n");
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(".hidden"), "\\&.hidden");
+        assert_eq!(escape("a-b\\c"), "a\\-b\\ec");
+    }
+}
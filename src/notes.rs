@@ -0,0 +1,67 @@
+use anyhow::{Result, anyhow};
+use std::path::{Path, PathBuf};
+
+/// Directory holding notes, inside the config directory
+pub const NOTES_DIR: &str = "notes";
+
+/// Path of the note file for command
+fn note_path(dir: &Path, command: &str) -> PathBuf {
+    // Command names can't contain path separators, but piped lists might
+    let name: String = command
+        .chars()
+        .map(|c| if c == '/' || c == '\\' { '_' } else { c })
+        .collect();
+    dir.join(format!("{name}.txt"))
+}
+
+/// Loads the note for command, if one was saved
+pub fn load(dir: &Path, command: &str) -> Option<String> {
+    std::fs::read_to_string(note_path(dir, command))
+        .ok()
+        .filter(|text| !text.trim().is_empty())
+}
+
+/// Saves the note for command, removing it when text is blank
+pub fn save(dir: &Path, command: &str, text: &str) -> Result<()> {
+    let path = note_path(dir, command);
+    if text.trim().is_empty() {
+        return match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(anyhow!("Failed to remove {}: {e}", path.display()))
+            }
+            _ => Ok(()),
+        };
+    }
+
+    std::fs::create_dir_all(dir).map_err(|e| anyhow!("Failed to create {}: {e}", dir.display()))?;
+    std::fs::write(&path, text).map_err(|e| anyhow!("Failed to write {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod notes_tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("rtfm-notes-{}", std::process::id()));
+
+        assert!(load(&dir, "tar").is_none());
+        save(&dir, "tar", "use -C to extract elsewhere").unwrap();
+        assert_eq!(
+            load(&dir, "tar").as_deref(),
+            Some("use -C to extract elsewhere")
+        );
+
+        save(&dir, "tar", "  \n").unwrap();
+        assert!(load(&dir, "tar").is_none());
+        assert!(!dir.join("tar.txt").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_note_path_escapes_separators() {
+        let path = note_path(Path::new("/n"), "a/b");
+        assert_eq!(path, Path::new("/n/a_b.txt"));
+    }
+}
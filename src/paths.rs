@@ -0,0 +1,88 @@
+use anyhow::{Result, anyhow};
+use std::path::PathBuf;
+
+const APP_NAME: &str = "rtfm";
+
+/// Resolved storage locations for config and persistent caches
+#[derive(Clone, Debug)]
+pub struct Paths {
+    pub config_dir: PathBuf,
+    pub cache_dir: PathBuf,
+}
+
+impl Paths {
+    /// Resolves directories from the process environment
+    pub fn resolve(cache_override: Option<PathBuf>) -> Result<Self> {
+        Self::resolve_with(|key| std::env::var(key).ok(), cache_override)
+    }
+
+    /// Resolves directories using `env` to look up variables
+    fn resolve_with<F>(env: F, cache_override: Option<PathBuf>) -> Result<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let lookup = |key: &str| env(key).filter(|v| !v.is_empty()).map(PathBuf::from);
+
+        let (config_base, cache_base) = if cfg!(windows) {
+            let roaming = lookup("APPDATA").ok_or_else(|| anyhow!("APPDATA is not set"))?;
+            let local = lookup("LOCALAPPDATA").unwrap_or_else(|| roaming.clone());
+            (roaming, local)
+        } else {
+            let home = lookup("HOME");
+            let config =
+                lookup("XDG_CONFIG_HOME").or_else(|| home.as_ref().map(|h| h.join(".config")));
+            let cache =
+                lookup("XDG_CACHE_HOME").or_else(|| home.as_ref().map(|h| h.join(".cache")));
+            match (config, cache) {
+                (Some(config), Some(cache)) => (config, cache),
+                _ => return Err(anyhow!("Neither HOME nor XDG directories are set")),
+            }
+        };
+
+        Ok(Self {
+            config_dir: config_base.join(APP_NAME),
+            cache_dir: cache_override.unwrap_or_else(|| cache_base.join(APP_NAME)),
+        })
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+mod paths_tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env_from(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let map: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| map.get(key).cloned()
+    }
+
+    #[test]
+    fn test_paths_fall_back_to_home() {
+        let paths = Paths::resolve_with(env_from(&[("HOME", "/home/u")]), None).unwrap();
+        assert_eq!(paths.config_dir, PathBuf::from("/home/u/.config/rtfm"));
+        assert_eq!(paths.cache_dir, PathBuf::from("/home/u/.cache/rtfm"));
+    }
+
+    #[test]
+    fn test_paths_honor_xdg_and_override() {
+        let env = env_from(&[
+            ("HOME", "/home/u"),
+            ("XDG_CONFIG_HOME", "/xdg/config"),
+            ("XDG_CACHE_HOME", "/xdg/cache"),
+        ]);
+        let paths = Paths::resolve_with(&env, None).unwrap();
+        assert_eq!(paths.config_dir, PathBuf::from("/xdg/config/rtfm"));
+        assert_eq!(paths.cache_dir, PathBuf::from("/xdg/cache/rtfm"));
+
+        let paths = Paths::resolve_with(&env, Some(PathBuf::from("/tmp/c"))).unwrap();
+        assert_eq!(paths.cache_dir, PathBuf::from("/tmp/c"));
+    }
+
+    #[test]
+    fn test_paths_fail_without_home() {
+        assert!(Paths::resolve_with(env_from(&[]), None).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use crate::man_db::{LoadedPage, ManDb};
+use std::future::Future;
+use std::pin::Pin;
+
+/// Future resolving to page content
+pub type PageFuture<'a> = Pin<Box<dyn Future<Output = LoadedPage> + Send + 'a>>;
+
+/// Future resolving to an optional line of text
+pub type LineFuture<'a> = Pin<Box<dyn Future<Output = Option<String>> + Send + 'a>>;
+
+/// Future completing once some work is done
+pub type DoneFuture<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+/// Source of commands and their pages for the TUI
+pub trait PageProvider: Send + Sync {
+    /// Gets all commands, sorted
+    fn commands(&self) -> &Vec<String>;
+
+    /// Extends prefix by the part every command starting with it shares
+    fn complete_prefix(&self, prefix: &str) -> String;
+
+    /// Gets the one-line description of command
+    fn description(&self, command: &str) -> Option<String>;
+
+    /// Gets the manual section command was indexed from
+    fn section(&self, command: &str) -> Option<&str>;
+
+    /// Gets every section documenting command
+    fn available_sections(&self, command: &str) -> Vec<String>;
+
+    /// Gets man page content
+    fn man_page<'a>(&'a self, command: &'a str) -> PageFuture<'a>;
+
+    /// Gets man page content from a specific section
+    fn man_page_in<'a>(&'a self, command: &'a str, section: &'a str) -> PageFuture<'a>;
+
+    /// Gets tldr page content
+    fn tldr_page<'a>(&'a self, command: &'a str) -> PageFuture<'a>;
+
+    /// Gets info page content
+    fn info_page<'a>(&'a self, command: &'a str) -> PageFuture<'a>;
+
+    /// Gets the description from the page's NAME section
+    fn name_line<'a>(&'a self, command: &'a str) -> LineFuture<'a>;
+
+    /// Gets an already fetched NAME description without blocking
+    fn cached_name_line(&self, command: &str) -> Option<String>;
+
+    /// Drops every cached page, NAME line included
+    fn clear_caches(&self) -> DoneFuture<'_>;
+
+    /// Whether only tldr pages exist, with no `man` to show the others
+    fn tldr_only(&self) -> bool;
+
+    /// Whether the index was cut short at its size cap
+    fn truncated(&self) -> bool;
+}
+
+impl PageProvider for ManDb {
+    fn commands(&self) -> &Vec<String> {
+        self.get_commands()
+    }
+
+    fn complete_prefix(&self, prefix: &str) -> String {
+        ManDb::complete_prefix(self, prefix)
+    }
+
+    fn description(&self, command: &str) -> Option<String> {
+        self.get_description(command)
+    }
+
+    fn section(&self, command: &str) -> Option<&str> {
+        self.get_section(command)
+    }
+
+    fn available_sections(&self, command: &str) -> Vec<String> {
+        self.get_available_sections(command).to_vec()
+    }
+
+    fn man_page<'a>(&'a self, command: &'a str) -> PageFuture<'a> {
+        Box::pin(self.get_man_page(command))
+    }
+
+    fn man_page_in<'a>(&'a self, command: &'a str, section: &'a str) -> PageFuture<'a> {
+        Box::pin(self.get_man_page_in(command, section))
+    }
+
+    fn tldr_page<'a>(&'a self, command: &'a str) -> PageFuture<'a> {
+        Box::pin(self.get_tldr_page(command))
+    }
+
+    fn info_page<'a>(&'a self, command: &'a str) -> PageFuture<'a> {
+        Box::pin(self.get_info_page(command))
+    }
+
+    fn name_line<'a>(&'a self, command: &'a str) -> LineFuture<'a> {
+        Box::pin(self.get_name_line(command))
+    }
+
+    fn cached_name_line(&self, command: &str) -> Option<String> {
+        ManDb::cached_name_line(self, command)
+    }
+
+    fn clear_caches(&self) -> DoneFuture<'_> {
+        Box::pin(ManDb::clear_caches(self))
+    }
+
+    fn tldr_only(&self) -> bool {
+        ManDb::tldr_only(self)
+    }
+
+    fn truncated(&self) -> bool {
+        ManDb::truncated(self)
+    }
+}
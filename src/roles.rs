@@ -0,0 +1,88 @@
+use crate::{examples, tables};
+
+/// What a content line is, worked out once when a page loads so rendering
+/// doesn't rescan the page every frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineRole {
+    Normal,
+    /// Unindented uppercase section title, like `DESCRIPTION`
+    Heading,
+    /// Starts with a `-flag`
+    Option,
+    /// Inside an indented example block
+    Example,
+    /// Inside a space-aligned table
+    TableRow,
+}
+
+impl LineRole {
+    /// Whether the line keeps its layout, clipped rather than wrapped
+    pub fn is_preformatted(self) -> bool {
+        matches!(self, LineRole::Example | LineRole::TableRow)
+    }
+}
+
+/// Classifies every line of content; with find_examples off, indented blocks
+/// stay normal text, as in tldr pages that mark their examples already
+pub fn classify(content: &[String], find_examples: bool) -> Vec<LineRole> {
+    let mut roles: Vec<LineRole> = content.iter().map(|line| line_role(line)).collect();
+    for table in tables::detect(content) {
+        roles[table].fill(LineRole::TableRow);
+    }
+    if find_examples {
+        for block in examples::detect(content) {
+            roles[block].fill(LineRole::Example);
+        }
+    }
+    roles
+}
+
+/// Role of a line judged on its own, before blocks spanning lines are found
+fn line_role(line: &str) -> LineRole {
+    if is_heading(line) {
+        LineRole::Heading
+    } else if line.trim_start().starts_with('-') {
+        LineRole::Option
+    } else {
+        LineRole::Normal
+    }
+}
+
+/// Whether line is an unindented title without lowercase letters, like `SEE ALSO`
+pub fn is_heading(line: &str) -> bool {
+    let heading = line.trim_end();
+    !heading.is_empty()
+        && !heading.starts_with(char::is_whitespace)
+        && heading.chars().any(char::is_alphabetic)
+        && !heading.chars().any(char::is_lowercase)
+}
+
+#[cfg(test)]
+mod roles_tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_classify() {
+        let content = lines(
+            "SEE ALSO\n\
+             \x20      Plain text\n\
+             \x20      -a, --all\n\
+             \x20      See ls(1).\n\
+             \x20      Read https://example.com\n\
+             \n\
+             \x20          $ ls -a",
+        );
+        use LineRole::*;
+        assert_eq!(
+            classify(&content, true),
+            [Heading, Normal, Option, Normal, Normal, Normal, Example]
+        );
+        assert_eq!(classify(&content, false)[6], Normal);
+        assert!(Example.is_preformatted() && TableRow.is_preformatted());
+        assert!(!Heading.is_preformatted());
+    }
+}
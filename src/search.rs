@@ -0,0 +1,176 @@
+use regex::Regex;
+use std::ops::Range;
+
+/// Parsed content search query
+///
+/// Whitespace-separated terms must all appear in a line (AND);
+/// terms separated by `|` match if any appears (OR).
+#[derive(Clone, Debug, Default)]
+pub struct SearchQuery {
+    terms: Vec<String>,
+    any: bool,
+    case_sensitive: bool,
+    pattern: Option<Regex>,
+}
+
+impl SearchQuery {
+    /// Parses raw query text into terms
+    pub fn parse(query: &str, case_sensitive: bool) -> Self {
+        let fold = |t: &str| {
+            if case_sensitive {
+                t.to_string()
+            } else {
+                t.to_lowercase()
+            }
+        };
+        let any = query.contains('|');
+        let terms: Vec<String> = if any {
+            query
+                .split('|')
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(fold)
+                .collect()
+        } else {
+            query.split_whitespace().map(fold).collect()
+        };
+
+        let pattern = (!terms.is_empty()).then(|| {
+            let alternatives: Vec<String> = terms
+                .iter()
+                .map(|t| format!("({})", regex::escape(t)))
+                .collect();
+            let flags = if case_sensitive { "" } else { "(?i)" };
+            Regex::new(&format!("{flags}{}", alternatives.join("|"))).unwrap()
+        });
+
+        Self {
+            terms,
+            any,
+            case_sensitive,
+            pattern,
+        }
+    }
+
+    /// Checks whether line satisfies the query
+    pub fn matches(&self, line: &str) -> bool {
+        if self.terms.is_empty() {
+            return false;
+        }
+
+        let line = if self.case_sensitive {
+            line.to_string()
+        } else {
+            line.to_lowercase()
+        };
+        if self.any {
+            self.terms.iter().any(|t| line.contains(t.as_str()))
+        } else {
+            self.terms.iter().all(|t| line.contains(t.as_str()))
+        }
+    }
+
+    /// Finds byte ranges of term occurrences in line with the matching term index
+    pub fn highlights(&self, line: &str) -> Vec<(Range<usize>, usize)> {
+        let Some(pattern) = &self.pattern else {
+            return Vec::new();
+        };
+
+        pattern
+            .captures_iter(line)
+            .filter_map(|caps| {
+                (1..caps.len()).find_map(|group| caps.get(group).map(|m| (m.range(), group - 1)))
+            })
+            .collect()
+    }
+}
+
+/// Scores how well description matches query terms, `None` if any term is missing
+///
+/// Each occurrence adds to the score, whole-word occurrences count double,
+/// and matches near the start of the description earn a bonus.
+pub fn description_relevance(
+    query: &str,
+    description: &str,
+    case_sensitive: bool,
+) -> Option<usize> {
+    let fold = |t: &str| {
+        if case_sensitive {
+            t.to_string()
+        } else {
+            t.to_lowercase()
+        }
+    };
+    let description = fold(description);
+    let mut score = 0;
+
+    for term in query.split_whitespace().map(fold) {
+        let positions: Vec<usize> = description.match_indices(&term).map(|(i, _)| i).collect();
+        let first = *positions.first()?;
+
+        for &pos in &positions {
+            let before = description[..pos].chars().next_back();
+            let after = description[pos + term.len()..].chars().next();
+            let whole_word = !before.is_some_and(char::is_alphanumeric)
+                && !after.is_some_and(char::is_alphanumeric);
+            score += if whole_word { 20 } else { 10 };
+        }
+        score += 100usize.saturating_sub(first);
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod search_tests {
+    use super::*;
+
+    #[test]
+    fn test_and_semantics() {
+        let query = SearchQuery::parse("foo bar", false);
+        assert!(query.matches("a FOO and a bar"));
+        assert!(!query.matches("only foo here"));
+    }
+
+    #[test]
+    fn test_case_sensitive_query() {
+        let query = SearchQuery::parse("Foo", true);
+        assert!(query.matches("a Foo here"));
+        assert!(!query.matches("a foo here"));
+        assert_eq!(query.highlights("foo Foo"), vec![(4..7, 0)]);
+        assert!(description_relevance("Zip", "zip files", true).is_none());
+    }
+
+    #[test]
+    fn test_or_semantics() {
+        let query = SearchQuery::parse("foo|bar", false);
+        assert!(query.matches("only foo here"));
+        assert!(query.matches("only bar here"));
+        assert!(!query.matches("neither"));
+    }
+
+    #[test]
+    fn test_description_relevance() {
+        let gzip = description_relevance("compress", "compress or expand files", false).unwrap();
+        let lib = description_relevance(
+            "compress",
+            "library routines for handling zlib data, used to decompress",
+            false,
+        )
+        .unwrap();
+        assert!(gzip > lib);
+        assert!(description_relevance("compress", "list directory contents", false).is_none());
+        assert!(description_relevance("copy files", "copy files and directories", false).is_some());
+        assert!(description_relevance("copy zip", "copy files and directories", false).is_none());
+    }
+
+    #[test]
+    fn test_highlights_report_term_index() {
+        let query = SearchQuery::parse("foo bar", false);
+        assert_eq!(
+            query.highlights("Bar then foo"),
+            vec![(0..3, 1), (9..12, 0)]
+        );
+        assert!(!SearchQuery::parse("  ", false).matches("anything"));
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+/// Fewest consecutive aligned lines treated as a table
+const MIN_TABLE_ROWS: usize = 3;
+/// Spaces separating two table columns
+const MIN_COLUMN_GAP: usize = 2;
+
+/// Finds line ranges that look like space-aligned tables
+pub fn detect(lines: &[String]) -> Vec<Range<usize>> {
+    let columns: Vec<BTreeSet<usize>> = lines.iter().map(|line| column_starts(line)).collect();
+    let mut tables = Vec::new();
+    let mut start = 0;
+
+    for idx in 1..=lines.len() {
+        let aligned = idx < lines.len()
+            && !columns[idx].is_empty()
+            && columns[idx]
+                .intersection(&columns[idx - 1])
+                .next()
+                .is_some();
+        if !aligned {
+            if idx - start >= MIN_TABLE_ROWS {
+                tables.push(start..idx);
+            }
+            start = idx;
+        }
+    }
+
+    tables
+}
+
+/// Char positions where a column starts after a wide gap
+fn column_starts(line: &str) -> BTreeSet<usize> {
+    let mut starts = BTreeSet::new();
+    let mut spaces = 0;
+    let mut seen_text = false;
+
+    for (idx, c) in line.chars().enumerate() {
+        if c == ' ' {
+            spaces += 1;
+            continue;
+        }
+        if seen_text && spaces >= MIN_COLUMN_GAP {
+            starts.insert(idx);
+        }
+        seen_text = true;
+        spaces = 0;
+    }
+
+    starts
+}
+
+#[cfg(test)]
+mod tables_tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_detect_aligned_block() {
+        let content = lines(
+            "Fields are:\n\
+             \x20 pid      process id\n\
+             \x20 comm     filename\n\
+             \x20 state    process state\n\
+             The rest is prose  with a gap.",
+        );
+        assert_eq!(detect(&content), vec![1..4]);
+    }
+
+    #[test]
+    fn test_detect_ignores_short_and_misaligned() {
+        let content = lines("a  b\nc  d\n\nlong  x\nshort   y\nz    w");
+        assert!(detect(&content).is_empty());
+    }
+
+    #[test]
+    fn test_column_starts() {
+        assert_eq!(column_starts("  a  bb c   d"), BTreeSet::from([5, 12]));
+        assert!(column_starts("   indented only").is_empty());
+    }
+}
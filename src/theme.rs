@@ -0,0 +1,240 @@
+use crate::config::{self, Value};
+use anyhow::{Result, anyhow};
+use std::path::Path;
+use tui::style::Color;
+
+/// Colors of every styled element in the TUI
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// Background of the top status bar
+    pub status_bar_bg: Color,
+    /// Text typed into the filter, search and prompts
+    pub input_fg: Color,
+    /// Background of the selected command
+    pub selection_bg: Color,
+    /// Characters of command names matched by the filter
+    pub list_match: Color,
+    /// Background of the content cursor line
+    pub cursor_bg: Color,
+    /// Text of the description box
+    pub description_fg: Color,
+    /// Text of saved notes
+    pub note_fg: Color,
+    /// Border of the focused pane
+    pub border_focused: Color,
+    /// Line numbers, clip markers and other secondary text
+    pub muted: Color,
+    /// Lines around a match in the peek popup
+    pub peek_fg: Color,
+    /// Notices that need attention, like an empty index
+    pub warning: Color,
+    /// Header of a page that failed to load
+    pub error: Color,
+    /// Words ending in `:` that start a line
+    pub heading: Color,
+    /// Words starting with `-`
+    pub option: Color,
+    /// Links to web pages
+    pub url: Color,
+    /// Words in `[brackets]`
+    pub optional: Color,
+    /// Words in `<angle brackets>`
+    pub argument: Color,
+    /// Program names in tldr examples
+    pub command: Color,
+    /// Quoted strings in tldr examples
+    pub string: Color,
+    /// Environment variables like `$HOME`
+    pub variable: Color,
+    /// Absolute and home-relative paths like `/etc/passwd` and `~/.profile`
+    pub path: Color,
+    /// Background of indented example blocks in man and info pages
+    pub example_bg: Color,
+    /// Pipes, redirections and command separators in tldr examples
+    pub operator: Color,
+    /// `{{placeholders}}` in tldr examples
+    pub placeholder: Color,
+    /// Text of the current search match
+    pub current_match_fg: Color,
+    /// Text of the other search matches
+    pub other_match_fg: Color,
+    /// Backgrounds of the current match, one per search term
+    pub current_match: Vec<Color>,
+    /// Backgrounds of the other matches, one per search term
+    pub other_match: Vec<Color>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            status_bar_bg: Color::DarkGray,
+            input_fg: Color::Yellow,
+            selection_bg: Color::DarkGray,
+            list_match: Color::Yellow,
+            cursor_bg: Color::DarkGray,
+            description_fg: Color::Cyan,
+            note_fg: Color::Green,
+            border_focused: Color::Yellow,
+            muted: Color::DarkGray,
+            peek_fg: Color::Gray,
+            warning: Color::Yellow,
+            error: Color::Red,
+            heading: Color::Yellow,
+            option: Color::Green,
+            url: Color::Blue,
+            optional: Color::Magenta,
+            argument: Color::Blue,
+            command: Color::Cyan,
+            string: Color::Yellow,
+            variable: Color::LightMagenta,
+            path: Color::LightGreen,
+            example_bg: Color::Indexed(236),
+            operator: Color::Magenta,
+            placeholder: Color::LightBlue,
+            current_match_fg: Color::White,
+            other_match_fg: Color::Black,
+            current_match: vec![Color::Red, Color::Magenta, Color::Blue, Color::Green],
+            other_match: vec![Color::DarkGray, Color::Yellow, Color::Cyan, Color::Gray],
+        }
+    }
+}
+
+impl Theme {
+    /// Loads a theme file; keys it leaves out keep their default colors
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read {}: {e}", path.display()))?;
+        Self::parse(&text).map_err(|e| anyhow!("{}: {e}", path.display()))
+    }
+
+    /// Parses `element = "color"` lines
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut theme = Self::default();
+
+        for (key, value) in config::parse_table(text)? {
+            if let "current_match" | "other_match" = key.as_str() {
+                let Value::List(items) = value else {
+                    return Err(anyhow!("`{key}` must be a list of colors"));
+                };
+                let colors = items
+                    .into_iter()
+                    .map(|item| color_value(&key, item))
+                    .collect::<Result<Vec<_>>>()?;
+                if colors.is_empty() {
+                    return Err(anyhow!("`{key}` needs at least one color"));
+                }
+                match key.as_str() {
+                    "current_match" => theme.current_match = colors,
+                    _ => theme.other_match = colors,
+                }
+                continue;
+            }
+
+            let color = color_value(&key, value)?;
+            let slot = match key.as_str() {
+                "status_bar_bg" => &mut theme.status_bar_bg,
+                "input_fg" => &mut theme.input_fg,
+                "selection_bg" => &mut theme.selection_bg,
+                "list_match" => &mut theme.list_match,
+                "cursor_bg" => &mut theme.cursor_bg,
+                "description_fg" => &mut theme.description_fg,
+                "note_fg" => &mut theme.note_fg,
+                "border_focused" => &mut theme.border_focused,
+                "muted" => &mut theme.muted,
+                "peek_fg" => &mut theme.peek_fg,
+                "warning" => &mut theme.warning,
+                "error" => &mut theme.error,
+                "heading" => &mut theme.heading,
+                "option" => &mut theme.option,
+                "url" => &mut theme.url,
+                "optional" => &mut theme.optional,
+                "argument" => &mut theme.argument,
+                "command" => &mut theme.command,
+                "string" => &mut theme.string,
+                "variable" => &mut theme.variable,
+                "path" => &mut theme.path,
+                "example_bg" => &mut theme.example_bg,
+                "operator" => &mut theme.operator,
+                "placeholder" => &mut theme.placeholder,
+                "current_match_fg" => &mut theme.current_match_fg,
+                "other_match_fg" => &mut theme.other_match_fg,
+                _ => return Err(anyhow!("unknown theme element `{key}`")),
+            };
+            *slot = color;
+        }
+
+        Ok(theme)
+    }
+}
+
+fn color_value(key: &str, value: Value) -> Result<Color> {
+    match value {
+        Value::Str(name) => {
+            parse_color(&name).ok_or_else(|| anyhow!("`{key}`: unknown color `{name}`"))
+        }
+        other => Err(anyhow!("`{key}` must be a color string, got {other:?}")),
+    }
+}
+
+/// Parses a color name like `light-blue` or a `#rrggbb` hex value
+fn parse_color(name: &str) -> Option<Color> {
+    if let Some(hex) = name.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |idx: usize| u8::from_str_radix(&hex[idx..idx + 2], 16).ok();
+        return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+
+    let normalized: String = name
+        .chars()
+        .filter(|c| !matches!(c, '-' | '_' | ' '))
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    Some(match normalized.as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod theme_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_theme() {
+        let theme = Theme::parse(
+            "status_bar_bg = \"#1e1e2e\"\nheading = \"light-magenta\"\ncurrent_match = [\"red\"]",
+        )
+        .unwrap();
+        assert_eq!(theme.status_bar_bg, Color::Rgb(0x1e, 0x1e, 0x2e));
+        assert_eq!(theme.heading, Color::LightMagenta);
+        assert_eq!(theme.current_match, vec![Color::Red]);
+        assert_eq!(theme.option, Theme::default().option);
+    }
+
+    #[test]
+    fn test_parse_theme_errors() {
+        assert!(Theme::parse("sidebar = \"red\"").is_err());
+        assert!(Theme::parse("heading = \"bright\"").is_err());
+        assert!(Theme::parse("heading = \"#12345\"").is_err());
+        assert!(Theme::parse("heading = 3").is_err());
+        assert!(Theme::parse("other_match = []").is_err());
+    }
+}
@@ -54,6 +54,28 @@ impl Trie {
         results
     }
 
+    /// Finds all words starting with prefix, shortest (closest to prefix) first
+    pub fn ranked_words_starting_with(&self, prefix: &str) -> Vec<String> {
+        let mut results = self.words_starting_with(prefix);
+        results.sort_unstable_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+        results
+    }
+
+    /// Extends prefix by the characters every word starting with it shares,
+    /// stopping at a branch or a complete word
+    pub fn longest_common_prefix(&self, prefix: &str) -> String {
+        let mut result = prefix.to_string();
+        let Some(mut node) = self.get_node(prefix) else {
+            return result;
+        };
+        while !node.is_word && node.children.len() == 1 {
+            let (c, child) = node.children.iter().next().unwrap();
+            result.push(*c);
+            node = child;
+        }
+        result
+    }
+
     /// Gets node for given prefix
     fn get_node(&self, prefix: &str) -> Option<&TrieNode> {
         let mut node = &self.root;
@@ -96,6 +118,33 @@ mod trie_tests {
         assert!(results.is_empty());
     }
 
+    #[test]
+    fn test_ranked_words_starting_with() {
+        let mut trie = Trie::new();
+        for word in ["gitk", "git-upload-pack", "git", "gist", "gitweb"] {
+            trie.insert(word);
+        }
+
+        let results = trie.ranked_words_starting_with("gi");
+        assert_eq!(
+            results,
+            vec!["git", "gist", "gitk", "gitweb", "git-upload-pack"]
+        );
+    }
+
+    #[test]
+    fn test_longest_common_prefix() {
+        let mut trie = Trie::new();
+        for word in ["git-upload-pack", "git-upload-archive", "gitk", "git"] {
+            trie.insert(word);
+        }
+
+        assert_eq!(trie.longest_common_prefix("git-u"), "git-upload-");
+        assert_eq!(trie.longest_common_prefix("gi"), "git");
+        assert_eq!(trie.longest_common_prefix("git"), "git");
+        assert_eq!(trie.longest_common_prefix("svn"), "svn");
+    }
+
     #[test]
     fn test_trie_case_sensitivity() {
         let mut trie = Trie::new();
@@ -1,27 +1,431 @@
-use crate::man_db::ManDb;
+use crate::config::{self, CONFIG_FILE, Value};
+use crate::fuzzy::{self, CaseMode, MatchMode};
+use crate::links;
+use crate::man_db::{
+    EMPTY_INDEX_WARNING, LoadOptions, LoadedPage, ManDb, PAGE_BREAK, section_name, section_order,
+    shell_quote, tldr_dir_age,
+};
+use crate::notes::{self, NOTES_DIR};
+use crate::paths::Paths;
+use crate::provider::PageProvider;
+use crate::roles::{self, LineRole};
+use crate::search::{self, SearchQuery};
+use crate::theme::Theme;
 use anyhow::Result;
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
-        KeyModifiers,
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
     },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
-use std::sync::Arc;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock};
 use std::time::{Duration, Instant};
 use tui::{
+    Terminal,
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    symbols::line,
     text::{Span, Spans},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
-    Terminal,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Widget, Wrap},
 };
 
+/// Matches a tldr placeholder like `{{path/to/file}}`
+static PLACEHOLDER_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{\{(.*?)\}\}").unwrap());
+
+/// Smallest terminal the full layout fits in
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 14;
+
 const PAGE_SIZE: usize = 30;
 const LIST_SIZE: usize = 50;
 const DEBOUNCE_DELAY_MS: u64 = 150;
+/// Columns scrolled by Left/Right when wrapping is off
+const H_SCROLL_STEP: usize = 8;
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+/// Lines shown above and below the match in the peek popup
+const PEEK_CONTEXT: usize = 2;
+/// Chars of the mark column `list_label` puts before each command
+const LIST_MARKER_WIDTH: usize = 2;
+/// Headings `--jump-to options` lands on, alone or ending a longer heading
+const OPTIONS_HEADINGS: [&str; 3] = ["OPTIONS", "FLAGS", "SWITCHES"];
+/// Default age in days after which local tldr pages are reported as stale
+pub const DEFAULT_TLDR_MAX_AGE_DAYS: u64 = 30;
+/// Default margin kept around the selection and current match
+pub const DEFAULT_SCROLL_OFF: usize = 3;
+/// Default blank columns between the content and the pane's side borders
+pub const DEFAULT_CONTENT_PADDING: usize = 1;
+/// Man pages keep table columns aligned when not wrapped
+pub const DEFAULT_WRAP_MAN: bool = false;
+/// tldr pages are prose and read best wrapped
+pub const DEFAULT_WRAP_TLDR: bool = true;
+/// Wrapped rows start without a continuation marker
+pub const DEFAULT_WRAP_MARKERS: bool = false;
+/// Blank line runs are shown as the page has them
+pub const DEFAULT_COMPACT_BLANK_LINES: bool = false;
+/// Typing a search scrolls to its first match right away
+pub const DEFAULT_JUMP_WHILE_SEARCHING: bool = true;
+/// Default percentage of the pane height above a revealed search match
+pub const DEFAULT_MATCH_POSITION: usize = 50;
+/// Percentage `{` and `}` move the match position by
+const MATCH_POSITION_STEP: usize = 10;
+/// Default for highlighting the filter's matched characters in the command list
+pub const DEFAULT_HIGHLIGHT_MATCHES: bool = true;
+/// Default display limit for a single content line, in chars
+pub const DEFAULT_MAX_LINE_WIDTH: usize = 2000;
+/// Appended to lines clipped for display
+const CLIPPED_MARKER: &str = " …";
+/// Clipped line marker for `--ascii`
+const ASCII_CLIPPED_MARKER: &str = " ...";
+
+/// How long a launched terminal gets to fail before it counts as started
+const LAUNCH_CHECK: Duration = Duration::from_millis(200);
+/// Longest OSC 52 payload sent, as terminals drop or cut off larger ones
+const OSC52_MAX_LEN: usize = 100_000;
+/// Commands that set the system clipboard from stdin, tried in order
+const CLIPBOARD_COMMANDS: [(&str, &[&str]); 3] = [
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+];
+
+/// Starts each row continuing a wrapped line
+const WRAP_MARKER: &str = "↪ ";
+
+/// Plain ASCII replacement of `WRAP_MARKER`
+const ASCII_WRAP_MARKER: &str = "+ ";
+
+/// Filter query forms as (syntax, meaning), documented apart from the keys
+pub const FILTER_SYNTAX: &[(&str, &str)] = &[(
+    "^prefix, suffix$, ^name$, 'exact",
+    "Match names by prefix, suffix, whole name or substring instead of the match mode",
+)];
+
+/// Built-in keybindings as (context, key, action), used for generated docs
+pub const KEYBINDINGS: &[(&str, &str, &str)] = &[
+    ("Global", "q, Ctrl+C", "Quit"),
+    (
+        "Global",
+        "Tab",
+        "Switch focus between list and content, or complete a typed name",
+    ),
+    ("Global", "Esc", "Return to the command list"),
+    (
+        "Global",
+        "Ctrl+O",
+        "Toggle mouse capture, off allows terminal text selection",
+    ),
+    (
+        "Global",
+        "Ctrl+L",
+        "Clear cached pages and reload the current one",
+    ),
+    (
+        "Global",
+        "Ctrl+T",
+        "Start the selected command in a new terminal window",
+    ),
+    (
+        "Global",
+        "Ctrl+Home, Ctrl+End",
+        "Scroll content to top or bottom",
+    ),
+    ("Command list", "Type, Backspace", "Edit the filter"),
+    ("Command list", "Up, Down", "Move selection"),
+    (
+        "Command list",
+        "PageUp, PageDown",
+        "Move selection by a page",
+    ),
+    ("Command list", "Home, End", "Jump to first or last command"),
+    (
+        "Command list",
+        "Enter",
+        "Load the selected page now, or run the --on-enter action",
+    ),
+    (
+        "Command list",
+        "Ctrl+D",
+        "Toggle filtering by name or description",
+    ),
+    (
+        "Command list",
+        "Space",
+        "Mark the command; Enter then shows every marked page in turn",
+    ),
+    (
+        "Command list",
+        "Ctrl+N",
+        "Toggle the description between apropos and the page's NAME line",
+    ),
+    (
+        "Command list",
+        "Ctrl+R",
+        "Cycle sorting: alphabetical, reverse, by section",
+    ),
+    (
+        "Command list",
+        "Ctrl+S",
+        "Toggle showing each command's section; in a note Ctrl+S saves instead",
+    ),
+    (
+        "Command list",
+        "Ctrl+G",
+        "Toggle a multi-column grid, Left and Right move across it",
+    ),
+    ("Content", "Up, Down", "Move the cursor line"),
+    (
+        "Content",
+        "PageUp, PageDown",
+        "Scroll and move the cursor one page",
+    ),
+    ("Content", "Home, End", "Jump to the first or last line"),
+    ("Content", "/, f", "Search in content"),
+    (
+        "Content",
+        "g",
+        "Jump to another command without leaving the content",
+    ),
+    (
+        "Content",
+        "#",
+        "Cycle line numbers: absolute, relative, off",
+    ),
+    ("Content", "n, N", "Next or previous match"),
+    (
+        "Content",
+        "{, }",
+        "Reveal matches higher or lower in the pane",
+    ),
+    ("Content", "t", "Cycle man, tldr and info pages"),
+    (
+        "Content",
+        "[, ]",
+        "Show the man page from the previous or next section",
+    ),
+    ("Content", "o", "Open the first link on or below the cursor"),
+    ("Content", "e", "Edit your note for the command"),
+    ("Content", "p", "Toggle previewing each match in a popup"),
+    (
+        "Content",
+        "Enter",
+        "Run or copy the tldr example or follow the man page reference on the cursor line, picking one when it has several",
+    ),
+    (
+        "Content",
+        "Backspace",
+        "Go back after following a reference",
+    ),
+    (
+        "Content",
+        "w",
+        "Toggle line wrapping for the current source",
+    ),
+    (
+        "Content",
+        "W",
+        "Toggle markers on rows continuing a wrapped line",
+    ),
+    ("Content", "b", "Toggle collapsing runs of blank lines"),
+    (
+        "Content",
+        "Y, y",
+        "Copy the whole page or the lines in view as plain text",
+    ),
+    (
+        "Content",
+        "Left, Right",
+        "Scroll sideways when not wrapping",
+    ),
+    ("Search", "Enter", "Apply search"),
+    ("Search", "Up, Down", "Recall earlier searches"),
+    ("Search", "Esc", "Cancel search"),
+    ("Notes", "Enter", "Start a new line"),
+    ("Notes", "Ctrl+S", "Save the note, empty to delete it"),
+    ("Notes", "Esc", "Discard changes"),
+    (
+        "Placeholders",
+        "Enter",
+        "Accept the value, empty keeps the placeholder text",
+    ),
+    ("Placeholders", "Esc", "Cancel running the example"),
+    ("Jump", "Type, Backspace", "Filter commands"),
+    ("Jump", "Up, Down", "Move selection"),
+    (
+        "Jump",
+        "Enter",
+        "Open the selected command, Backspace in content returns",
+    ),
+    ("Jump", "Esc", "Close the prompt"),
+    ("Sections", "Up, Down", "Move selection"),
+    (
+        "Sections",
+        "1-9",
+        "Select the section starting with the digit",
+    ),
+    (
+        "Sections",
+        "Enter",
+        "Open the selected section, the lowest by default",
+    ),
+    ("Sections", "Esc", "Close the picker"),
+    ("References", "Up, Down", "Move selection"),
+    ("References", "Enter", "Open the selected reference"),
+    ("References", "Esc", "Close the picker"),
+];
+
+/// Interactive behavior settings
+#[derive(Clone, Debug)]
+pub struct TuiOptions {
+    /// Load pages immediately when moving the selection
+    pub instant_preview: bool,
+    /// Skip loading pages while the filter matches more commands than this
+    pub max_preview_matches: Option<usize>,
+    /// Display limit for a content line in chars, 0 for unlimited
+    pub max_line_width: usize,
+    /// Widest text column for content, centered in the pane, 0 for the full pane
+    pub max_width: usize,
+    /// Blank columns kept on each side of the content inside its border
+    pub content_padding: usize,
+    /// Algorithm used to filter the command list
+    pub match_mode: MatchMode,
+    /// How letter case affects filtering and content search
+    pub case_mode: CaseMode,
+    /// Text to start the command list filter with
+    pub initial_query: Option<String>,
+    /// Wrap long lines of man and info pages
+    pub wrap_man: bool,
+    /// Wrap long lines of tldr pages
+    pub wrap_tldr: bool,
+    /// Mark rows continuing a wrapped line
+    pub wrap_markers: bool,
+    /// Highlight the characters of each command the filter matched
+    pub highlight_matches: bool,
+    /// Collapse runs of blank lines and drop them at either end of a page
+    pub compact_blank_lines: bool,
+    /// How far down the pane search matches are revealed, in percent
+    pub match_position: usize,
+    /// Scroll to the first match on every search keystroke instead of only on Enter
+    pub jump_while_searching: bool,
+    /// Draw borders and markers with ASCII only
+    pub ascii: bool,
+    /// Capture mouse events at startup
+    pub mouse: bool,
+    /// Time without a keypress after which the TUI returns to its starting state
+    pub idle_reset: Option<Duration>,
+    /// Rows kept between the selection or match and the pane edges
+    pub scroll_off: usize,
+    /// Local tldr pages checked for staleness at startup
+    pub tldr_dir: Option<PathBuf>,
+    /// Days before local tldr pages count as stale, 0 to never check
+    pub tldr_max_age_days: u64,
+    /// Where the content pane sits relative to the command list
+    pub preview: PreviewPosition,
+    /// Pane focused at startup
+    pub focus: StartFocus,
+    /// Where a newly opened man page starts
+    pub jump_to: JumpTo,
+    /// What Enter does in the command list
+    pub on_enter: OnEnter,
+    /// Command line opening a terminal, `{cmd}` replaced by the command to run
+    pub launcher: Option<String>,
+    /// Text shown in the description box
+    pub description_source: DescriptionSource,
+    /// Colors of every styled element
+    pub theme: Theme,
+}
+
+impl Default for TuiOptions {
+    fn default() -> Self {
+        Self {
+            instant_preview: false,
+            max_preview_matches: None,
+            max_line_width: DEFAULT_MAX_LINE_WIDTH,
+            max_width: 0,
+            content_padding: DEFAULT_CONTENT_PADDING,
+            match_mode: MatchMode::default(),
+            case_mode: CaseMode::default(),
+            initial_query: None,
+            wrap_man: DEFAULT_WRAP_MAN,
+            wrap_tldr: DEFAULT_WRAP_TLDR,
+            wrap_markers: DEFAULT_WRAP_MARKERS,
+            highlight_matches: DEFAULT_HIGHLIGHT_MATCHES,
+            compact_blank_lines: DEFAULT_COMPACT_BLANK_LINES,
+            match_position: DEFAULT_MATCH_POSITION,
+            jump_while_searching: DEFAULT_JUMP_WHILE_SEARCHING,
+            ascii: false,
+            mouse: true,
+            idle_reset: None,
+            scroll_off: DEFAULT_SCROLL_OFF,
+            tldr_dir: None,
+            tldr_max_age_days: DEFAULT_TLDR_MAX_AGE_DAYS,
+            preview: PreviewPosition::default(),
+            focus: StartFocus::default(),
+            jump_to: JumpTo::default(),
+            on_enter: OnEnter::default(),
+            launcher: None,
+            description_source: DescriptionSource::default(),
+            theme: Theme::default(),
+        }
+    }
+}
+
+/// Placement of the content pane
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum PreviewPosition {
+    /// Above the command list
+    Top,
+    /// Beside the command list
+    #[default]
+    Right,
+    /// Below the command list
+    Bottom,
+    /// Hidden until the content pane is focused
+    None,
+}
+
+/// Pane focused when the TUI starts
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum StartFocus {
+    /// The command list
+    #[default]
+    List,
+    /// The page of the first match, loaded right away
+    Content,
+}
+
+/// Where a newly opened man page starts
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum JumpTo {
+    /// The first line
+    #[default]
+    Top,
+    /// The OPTIONS or FLAGS heading, or the top without one
+    Options,
+}
+
+/// What Enter does on a command in the list
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum OnEnter {
+    /// Show the page in the content pane
+    #[default]
+    Load,
+    /// Read the man page in the system pager, then return
+    Pager,
+    /// Quit and print the command name, for picking one from a script
+    Print,
+    /// Copy the command name to the clipboard
+    Copy,
+}
 
 /// Tracks command list state
 struct CommandListState {
@@ -30,19 +434,89 @@ struct CommandListState {
     selected_idx: usize,
     list_scroll: usize,
     visible_range: (usize, usize),
+    /// Lay short names out in several columns
+    grid: bool,
+    /// Columns drawn in the last frame, 1 outside grid mode
+    columns: usize,
+    /// Provider commands lowercased once, index-aligned for filtering
+    lowered: Vec<String>,
+    /// Commands marked with Space to read together
+    marked: HashSet<String>,
 }
 
 /// Tracks man page state
 struct ManPageState {
+    /// Command whose page is loaded
+    command: Option<String>,
+    /// Manual section of the loaded man page
+    section: Option<String>,
     content: Arc<Vec<String>>,
+    /// Content as loaded, before blank lines were compacted
+    raw_content: Arc<Vec<String>>,
     scroll: usize,
+    /// Current line, used by line-based actions
+    cursor: usize,
+    h_scroll: usize,
+    visible_height: usize,
+    /// Lines at least partly in view, fewer than `visible_height` when wrapped
+    lines_in_view: usize,
+    /// Role of each content line, found once on load
+    roles: Vec<LineRole>,
+    /// Content is a load failure, drawn as an error page
+    is_error: bool,
+    /// Heading scrolled to on load, shown in the title
+    jumped_to: Option<String>,
+}
+
+/// Page left by following a reference, restored by going back
+struct PageLocation {
+    command: String,
+    section: Option<String>,
+    source: PageSource,
+    scroll: usize,
+    cursor: usize,
 }
 
 /// Tracks search state
 struct SearchState {
     query: String,
+    parsed: SearchQuery,
     matches: Arc<Vec<usize>>,
     current_match: usize,
+    /// Applied queries this session, oldest first
+    history: Vec<String>,
+    /// Entry of history shown in the input while recalling
+    history_idx: Option<usize>,
+}
+
+/// Tracks filling in a tldr example's placeholders before running it
+struct FillState {
+    /// Example with `{{placeholder}}` markers intact
+    template: String,
+    names: Vec<String>,
+    /// Values entered so far, one per finished placeholder
+    values: Vec<String>,
+    input: String,
+}
+
+/// Tracks the jump-to-command prompt over the content
+struct JumpState {
+    query: String,
+    matches: Vec<String>,
+    selected: usize,
+}
+
+/// Tracks the picker listing every section of a command
+struct SectionPicker {
+    command: String,
+    sections: Vec<String>,
+    selected: usize,
+}
+
+/// Tracks the picker listing every reference on the cursor line
+struct ReferencePicker {
+    references: Vec<(String, String)>,
+    selected: usize,
 }
 
 /// Application state container
@@ -51,11 +525,54 @@ pub struct AppState {
     man_page: ManPageState,
     search: SearchState,
     focus: Focus,
-    man_db: Arc<ManDb>,
+    provider: Arc<dyn PageProvider>,
     loading: bool,
     last_input_time: Instant,
+    /// Last keypress since the last idle reset
+    last_key_time: Option<Instant>,
     pending_man_load: bool,
+    /// The pending load reloads the open page instead of the selected command
+    pending_reload: bool,
+    load_delay: Duration,
     page_source: PageSource,
+    /// `man` is missing, so the page source stays on tldr
+    tldr_only: bool,
+    filter_mode: FilterMode,
+    sort_mode: SortMode,
+    description_source: DescriptionSource,
+    show_sections: bool,
+    notice: Option<String>,
+    wrap_man: bool,
+    wrap_tldr: bool,
+    /// Show the peek popup whenever jumping to a match
+    peek_mode: bool,
+    line_numbers: LineNumbers,
+    /// Peek popup is visible until the next keystroke
+    show_peek: bool,
+    /// Example awaiting confirmation before it's run
+    pending_run: Option<String>,
+    /// Enter action on a command, left to the event loop since it needs the terminal
+    pending_enter: Option<(OnEnter, String)>,
+    /// Text to copy to the clipboard with a description of it, left to the event loop
+    pending_copy: Option<(String, String)>,
+    /// Shell line of a started terminal, with word if it exits early
+    launch_check: Option<(String, std::sync::mpsc::Receiver<std::io::Error>)>,
+    /// Marked pages loading in the background, shown together once all arrive
+    pending_batch: Option<tokio::task::JoinHandle<LoadedPage>>,
+    /// Mouse events go to rtfm rather than the terminal
+    mouse_capture: bool,
+    /// Saved note for the loaded command
+    note: Option<String>,
+    /// Note text being edited
+    note_draft: String,
+    jump: JumpState,
+    sections: SectionPicker,
+    references: ReferencePicker,
+    fill: FillState,
+    /// Pages left by following references, most recent last
+    history: Vec<PageLocation>,
+    options: TuiOptions,
+    paths: Paths,
 }
 
 /// UI focus areas
@@ -63,117 +580,386 @@ enum Focus {
     CommandList,
     ManPage,
     Search,
+    Notes,
+    Jump,
+    Sections,
+    References,
+    Placeholders,
+}
+
+/// What the command-list filter matches against
+enum FilterMode {
+    /// Command names, alphabetical
+    Name,
+    /// Apropos descriptions, ranked by relevance
+    Description,
+}
+
+/// Where the description box text comes from
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum DescriptionSource {
+    /// The apropos one-liner, falling back to the NAME line when empty
+    #[default]
+    Apropos,
+    /// The page's own NAME section, falling back to apropos until it loads
+    Name,
+}
+
+/// Order of the filtered command list
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum SortMode {
+    /// Alphabetical, or best match first while filtering
+    #[default]
+    Natural,
+    /// Reverse alphabetical
+    Descending,
+    /// Grouped by manual section, alphabetical within each
+    Section,
+}
+
+impl SortMode {
+    fn next(self) -> Self {
+        match self {
+            SortMode::Natural => SortMode::Descending,
+            SortMode::Descending => SortMode::Section,
+            SortMode::Section => SortMode::Natural,
+        }
+    }
+}
+
+/// Line number gutter shown beside content
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum LineNumbers {
+    Off,
+    Absolute,
+    /// Distance from the cursor line, which shows its own number
+    Relative,
 }
 
 /// Content source options
+#[derive(Clone, Copy, Debug)]
 enum PageSource {
     Man,
     Tldr,
+    Info,
 }
 
 fn scroll_to_top(app: &mut AppState) {
     app.man_page.scroll = 0;
+    app.man_page.cursor = 0;
 }
 
 fn scroll_to_bottom(app: &mut AppState) {
-    app.man_page.scroll = app.man_page.content.len().saturating_sub(PAGE_SIZE);
+    let len = app.man_page.content.len();
+    app.man_page.scroll = len.saturating_sub(app.man_page.visible_height.max(1));
+    app.man_page.cursor = len.saturating_sub(1);
+}
+
+impl AppState {
+    /// Creates initial state, filtering the commands by any initial query
+    pub fn new(provider: Arc<dyn PageProvider>, paths: Paths, options: TuiOptions) -> Self {
+        let filtered_commands = Arc::new(provider.commands().clone());
+        let tldr_only = provider.tldr_only();
+
+        let mut app = Self {
+            command_list: CommandListState {
+                input: String::new(),
+                filtered_commands,
+                selected_idx: 0,
+                list_scroll: 0,
+                visible_range: (0, 0),
+                grid: false,
+                columns: 1,
+                lowered: fuzzy::lowercase_all(provider.commands()),
+                marked: HashSet::new(),
+            },
+            man_page: ManPageState {
+                command: None,
+                section: None,
+                content: Arc::new(Vec::new()),
+                raw_content: Arc::new(Vec::new()),
+                scroll: 0,
+                cursor: 0,
+                h_scroll: 0,
+                visible_height: PAGE_SIZE,
+                lines_in_view: PAGE_SIZE,
+                roles: Vec::new(),
+                is_error: false,
+                jumped_to: None,
+            },
+            search: SearchState {
+                query: String::new(),
+                parsed: SearchQuery::default(),
+                matches: Arc::new(Vec::new()),
+                current_match: 0,
+                history: Vec::new(),
+                history_idx: None,
+            },
+            focus: Focus::CommandList,
+            provider,
+            loading: false,
+            last_input_time: Instant::now(),
+            last_key_time: None,
+            pending_man_load: true,
+            pending_reload: false,
+            load_delay: debounce_delay(),
+            page_source: if tldr_only {
+                PageSource::Tldr
+            } else {
+                PageSource::Man
+            },
+            tldr_only,
+            filter_mode: FilterMode::Name,
+            sort_mode: SortMode::default(),
+            description_source: options.description_source,
+            show_sections: false,
+            notice: None,
+            wrap_man: options.wrap_man,
+            wrap_tldr: options.wrap_tldr,
+            peek_mode: false,
+            line_numbers: LineNumbers::Off,
+            show_peek: false,
+            pending_run: None,
+            pending_enter: None,
+            pending_copy: None,
+            launch_check: None,
+            pending_batch: None,
+            mouse_capture: options.mouse,
+            note: None,
+            note_draft: String::new(),
+            jump: JumpState {
+                query: String::new(),
+                matches: Vec::new(),
+                selected: 0,
+            },
+            sections: SectionPicker {
+                command: String::new(),
+                sections: Vec::new(),
+                selected: 0,
+            },
+            references: ReferencePicker {
+                references: Vec::new(),
+                selected: 0,
+            },
+            fill: FillState {
+                template: String::new(),
+                names: Vec::new(),
+                values: Vec::new(),
+                input: String::new(),
+            },
+            history: Vec::new(),
+            options,
+            paths,
+        };
+
+        if let Some(query) = app.options.initial_query.clone() {
+            app.command_list.input = query;
+            filter_commands(&mut app);
+        }
+        if app.options.focus == StartFocus::Content
+            && !app.command_list.filtered_commands.is_empty()
+        {
+            // Prefer the command named exactly by the query over the first match
+            let input = &app.command_list.input;
+            if let Some(idx) = app
+                .command_list
+                .filtered_commands
+                .iter()
+                .position(|c| c == input)
+            {
+                app.command_list.selected_idx = idx;
+            }
+            app.focus = Focus::ManPage;
+            schedule_load(&mut app, Duration::ZERO);
+        }
+        app
+    }
 }
 
 /// Runs the TUI application
-pub async fn run_tui(man_db: ManDb) -> Result<()> {
+pub async fn run_tui(load_options: LoadOptions, paths: Paths, options: TuiOptions) -> Result<()> {
     enable_raw_mode()?;
     let mut stdout = std::io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let man_db = Arc::new(man_db);
-    let commands = man_db.get_commands();
-    let filtered_commands = Arc::new(commands.clone());
-
-    let mut app = AppState {
-        command_list: CommandListState {
-            input: String::new(),
-            filtered_commands,
-            selected_idx: 0,
-            list_scroll: 0,
-            visible_range: (0, 0),
-        },
-        man_page: ManPageState {
-            content: Arc::new(Vec::new()),
-            scroll: 0,
-        },
-        search: SearchState {
-            query: String::new(),
-            matches: Arc::new(Vec::new()),
-            current_match: 0,
-        },
-        focus: Focus::CommandList,
-        man_db: man_db.clone(),
-        loading: false,
-        last_input_time: Instant::now(),
-        pending_man_load: true,
-        page_source: PageSource::Man,
+    let host = load_options.host.clone();
+    let man_db = match build_index(&mut terminal, load_options, &options).await {
+        Ok(Some(man_db)) => man_db,
+        Ok(None) => return restore_terminal(&mut terminal),
+        Err(e) => {
+            restore_terminal(&mut terminal)?;
+            return Err(e);
+        }
+    };
+
+    let warnings = man_db.warnings().join("; ");
+    let initial = (paths.clone(), options.clone());
+    let mut picked = None;
+    let mut app = AppState::new(Arc::new(man_db), paths, options);
+    if !warnings.is_empty() {
+        app.notice = Some(warnings);
+    }
+    set_mouse_capture(&mut terminal, app.mouse_capture)?;
+
+    // Reading modification times can be slow on network mounts, so check off-thread
+    let mut staleness = match (&app.options.tldr_dir, app.options.tldr_max_age_days) {
+        (Some(dir), max_days) if max_days > 0 => {
+            let dir = dir.clone();
+            Some(tokio::task::spawn_blocking(move || {
+                stale_tldr_hint(&dir, max_days)
+            }))
+        }
+        _ => None,
     };
 
     loop {
         let now = Instant::now();
 
+        if is_idle(&app) {
+            log::info!("No keypress for {:?}, resetting", app.options.idle_reset);
+            app = AppState::new(app.provider.clone(), initial.0.clone(), initial.1.clone());
+            set_mouse_capture(&mut terminal, app.mouse_capture)?;
+        }
+
         // Handle delayed man page loading
-        if app.pending_man_load
-            && app.last_input_time.elapsed() > Duration::from_millis(DEBOUNCE_DELAY_MS)
+        if app.pending_man_load && app.last_input_time.elapsed() >= app.load_delay {
+            load_pending_page(&mut app).await;
+        }
+
+        check_launch(&mut app);
+
+        if app
+            .pending_batch
+            .as_ref()
+            .is_some_and(|batch| batch.is_finished())
         {
-            load_current_page(&mut app).await;
-            app.pending_man_load = false;
+            show_marked_pages(&mut app).await;
+        }
+
+        if let Some(check) = staleness.take_if(|check| check.is_finished())
+            && let Ok(Some(hint)) = check.await
+        {
+            // Index warnings may already be showing, so keep them in view
+            app.notice = Some(match app.notice.take() {
+                Some(notice) => format!("{notice}; {hint}"),
+                None => hint,
+            });
         }
 
         terminal.draw(|f| render_ui(f, &mut app))?;
 
-        if event::poll(Duration::from_millis(16))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind != KeyEventKind::Press {
-                    continue;
+        let event = if event::poll(Duration::from_millis(16))? {
+            Some(event::read()?)
+        } else {
+            None
+        };
+        if let Some(Event::Paste(text)) = &event {
+            app.last_key_time = Some(Instant::now());
+            handle_paste(&mut app, text);
+        }
+        if let Some(Event::Key(key)) = event {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            app.last_key_time = Some(Instant::now());
+            app.notice = None;
+            app.show_peek = false;
+
+            if let Some(example) = app.pending_run.take() {
+                app.notice = Some(match key.code {
+                    KeyCode::Char('y') => run_example(&mut terminal, &example, app.mouse_capture)?,
+                    KeyCode::Char('c') => copy_to_clipboard(&mut terminal, &example, "the example"),
+                    _ => "Cancelled".to_string(),
+                });
+                continue;
+            }
+
+            // Ctrl+C quits from anywhere, prompts included
+            if let KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } = key
+            {
+                break;
+            }
+
+            // Editors and prompts take every other key as text
+            if matches!(
+                app.focus,
+                Focus::Notes
+                    | Focus::Jump
+                    | Focus::Sections
+                    | Focus::References
+                    | Focus::Placeholders
+            ) {
+                handle_key(&mut app, key).await;
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    toggle_mouse(&mut app);
+                    set_mouse_capture(&mut terminal, app.mouse_capture)?;
+                }
+                KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    clear_caches(&mut app).await;
+                }
+                KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    open_terminal(&mut app);
+                }
+                KeyCode::Char('q') => break,
+                KeyCode::Tab if complete_filter(&mut app) => {}
+                KeyCode::Tab => toggle_focus(&mut app),
+                KeyCode::Esc => app.focus = Focus::CommandList,
+                KeyCode::Char('/') if matches!(app.focus, Focus::ManPage) => {
+                    start_search(&mut app);
+                }
+                KeyCode::Char('t') if matches!(app.focus, Focus::ManPage) => {
+                    switch_page_source(&mut app);
                 }
+                _ => handle_key(&mut app, key).await,
+            }
 
-                // Handle Ctrl combinations first
-                if let KeyEvent {
-                    code: KeyCode::Char('c'),
+            match key {
+                KeyEvent {
+                    code: KeyCode::Home,
+                    modifiers: KeyModifiers::CONTROL,
+                    ..
+                } => scroll_to_top(&mut app),
+                KeyEvent {
+                    code: KeyCode::End,
                     modifiers: KeyModifiers::CONTROL,
                     ..
-                } = key
-                {
+                } => scroll_to_bottom(&mut app),
+                _ => {}
+            }
+
+            match app.pending_enter.take() {
+                Some((OnEnter::Print, command)) => {
+                    picked = Some(command);
                     break;
                 }
-
-                match key.code {
-                    KeyCode::Char('q') => break,
-                    KeyCode::Tab => toggle_focus(&mut app),
-                    KeyCode::Esc => app.focus = Focus::CommandList,
-                    KeyCode::Char('/') if matches!(app.focus, Focus::ManPage) => {
-                        app.focus = Focus::Search;
-                        app.search.query.clear();
-                    }
-                    KeyCode::Char('t') if matches!(app.focus, Focus::ManPage) => {
-                        toggle_page_source(&mut app);
-                        app.pending_man_load = true;
-                        app.last_input_time = Instant::now();
-                    }
-                    _ => handle_key(&mut app, key).await,
+                Some((OnEnter::Pager, _)) if man_unavailable(&mut app) => {}
+                Some((OnEnter::Pager, command)) => {
+                    let section = app.provider.section(&command).map(String::from);
+                    app.notice = Some(open_in_pager(
+                        &mut terminal,
+                        &command,
+                        section.as_deref(),
+                        host.as_deref(),
+                        app.mouse_capture,
+                    )?);
                 }
-
-                match key {
-                    KeyEvent {
-                        code: KeyCode::Home,
-                        modifiers: KeyModifiers::CONTROL,
-                        ..
-                    } => scroll_to_top(&mut app),
-                    KeyEvent {
-                        code: KeyCode::End,
-                        modifiers: KeyModifiers::CONTROL,
-                        ..
-                    } => scroll_to_bottom(&mut app),
-                    _ => {}
+                Some((OnEnter::Copy, command)) => {
+                    app.notice = Some(copy_to_clipboard(&mut terminal, &command, &command));
                 }
+                Some((OnEnter::Load, _)) | None => {}
+            }
+            if let Some((text, label)) = app.pending_copy.take() {
+                app.notice = Some(copy_to_clipboard(&mut terminal, &text, &label));
             }
         }
 
@@ -184,100 +970,303 @@ pub async fn run_tui(man_db: ManDb) -> Result<()> {
         }
     }
 
+    restore_terminal(&mut terminal)?;
+    // Printed once the screen is restored, so it stays visible and pipes get only the name
+    if let Some(command) = picked {
+        println!("{command}");
+    }
+    Ok(())
+}
+
+/// Whether `idle_reset` has passed since the last keypress; a fresh or just reset
+/// app has none, so it isn't reset again until someone uses it. An open note
+/// editor is never reset, as that would throw away the unsaved draft
+fn is_idle(app: &AppState) -> bool {
+    if matches!(app.focus, Focus::Notes) {
+        return false;
+    }
+    match (app.options.idle_reset, app.last_key_time) {
+        (Some(limit), Some(last)) => last.elapsed() >= limit,
+        _ => false,
+    }
+}
+
+/// Builds the index off-thread behind a splash screen; `None` if the user quit first
+async fn build_index(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    mut load_options: LoadOptions,
+    options: &TuiOptions,
+) -> Result<Option<ManDb>> {
+    let progress = Arc::new(AtomicUsize::new(0));
+    load_options.progress = Some(progress.clone());
+    let build = tokio::task::spawn_blocking(move || ManDb::load(&load_options));
+
+    while !build.is_finished() {
+        let entries = progress.load(Ordering::Relaxed);
+        terminal.draw(|f| render_indexing(f, options, entries))?;
+        if event::poll(Duration::from_millis(50))?
+            && let Event::Key(key) = event::read()?
+            && is_cancel_key(key)
+        {
+            // A running `man -k` can't be interrupted; it's dropped with the process
+            return Ok(None);
+        }
+    }
+
+    Ok(Some(build.await??))
+}
+
+/// Keys that abandon indexing at startup
+fn is_cancel_key(key: KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char('c') => key.modifiers.contains(KeyModifiers::CONTROL),
+        KeyCode::Char('q') | KeyCode::Esc => true,
+        _ => false,
+    }
+}
+
+/// Draws the splash, with the count of index lines read once `man -k` starts printing
+fn render_indexing<B: tui::backend::Backend>(
+    f: &mut tui::Frame<B>,
+    options: &TuiOptions,
+    entries: usize,
+) {
+    let size = f.size();
+    let ellipsis = if options.ascii { "..." } else { "…" };
+    let area = Rect::new(
+        size.x,
+        size.y + size.height / 2,
+        size.width,
+        1.min(size.height),
+    );
+    let count = match entries {
+        0 => String::new(),
+        1 => " 1 entry".to_string(),
+        n => format!(" {n} entries"),
+    };
+    let splash = Paragraph::new(format!(
+        "Indexing man pages{ellipsis}{count} (Ctrl+C to cancel)"
+    ))
+    .alignment(Alignment::Center)
+    .style(Style::default().fg(options.theme.muted));
+    f.render_widget(splash, area);
+}
+
+/// Leaves the alternate screen and raw mode
+fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>) -> Result<()> {
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
     Ok(())
 }
 
+/// Hint shown when the newest local tldr page is older than max_days
+fn stale_tldr_hint(dir: &Path, max_days: u64) -> Option<String> {
+    let days = tldr_dir_age(dir)?.as_secs() / SECONDS_PER_DAY;
+    (days > max_days).then(|| {
+        format!(
+            "tldr pages in {} are {days} days old, consider updating them",
+            dir.display()
+        )
+    })
+}
+
+/// Shows a notice and returns true when `man` is missing, for man-only actions
+fn man_unavailable(app: &mut AppState) -> bool {
+    if app.tldr_only {
+        app.notice = Some("`man` is not installed, only tldr pages can be shown".to_string());
+    }
+    app.tldr_only
+}
+
 fn toggle_page_source(app: &mut AppState) {
+    if man_unavailable(app) {
+        return;
+    }
     app.page_source = match app.page_source {
         PageSource::Man => PageSource::Tldr,
-        PageSource::Tldr => PageSource::Man,
+        PageSource::Tldr => PageSource::Info,
+        PageSource::Info => PageSource::Man,
     };
+    log::debug!("Page source is now {:?}", app.page_source);
+}
+
+/// Moves to the next page source and reloads the open page from it
+fn switch_page_source(app: &mut AppState) {
+    toggle_page_source(app);
+    schedule_reload(app, debounce_delay());
+}
+
+/// Extends the typed name shell-style to what every matching command shares;
+/// false when there's nothing to add, leaving Tab to switch focus
+fn complete_filter(app: &mut AppState) -> bool {
+    if !matches!(app.focus, Focus::CommandList)
+        || !matches!(app.filter_mode, FilterMode::Name)
+        || app.command_list.input.is_empty()
+    {
+        return false;
+    }
+    let completed = app.provider.complete_prefix(&app.command_list.input);
+    if completed.len() <= app.command_list.input.len() {
+        return false;
+    }
+    app.command_list.input = completed;
+    filter_commands(app);
+    schedule_filter_load(app);
+    true
 }
 
 fn toggle_focus(app: &mut AppState) {
     app.focus = match app.focus {
         Focus::CommandList => Focus::ManPage,
         Focus::ManPage => Focus::CommandList,
-        Focus::Search => Focus::ManPage,
+        Focus::Search | Focus::Notes | Focus::Jump | Focus::References | Focus::Placeholders => {
+            Focus::ManPage
+        }
+        Focus::Sections => Focus::CommandList,
     };
 }
 
 async fn handle_key(app: &mut AppState, key: KeyEvent) {
     match app.focus {
         Focus::CommandList => handle_command_list_keys(app, key).await,
-        Focus::ManPage => handle_man_page_keys(app, key),
+        Focus::ManPage => handle_man_page_keys(app, key).await,
         Focus::Search => handle_search_keys(app, key),
+        Focus::Notes => handle_notes_keys(app, key),
+        Focus::Jump => handle_jump_keys(app, key).await,
+        Focus::Sections => handle_section_picker_keys(app, key).await,
+        Focus::References => handle_reference_picker_keys(app, key).await,
+        Focus::Placeholders => handle_placeholder_keys(app, key),
+    }
+}
+
+/// Inserts pasted text into the focused input in one step; single-line inputs get
+/// its lines joined with spaces
+fn handle_paste(app: &mut AppState, text: &str) {
+    let single_line = || {
+        text.split(['\r', '\n'])
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    match app.focus {
+        Focus::CommandList => {
+            app.command_list.input.push_str(&single_line());
+            filter_commands(app);
+            schedule_filter_load(app);
+        }
+        Focus::Search => {
+            app.search.query.push_str(&single_line());
+            search_as_you_type(app);
+        }
+        Focus::Jump => {
+            app.jump.query.push_str(&single_line());
+            update_jump_matches(app);
+        }
+        Focus::Placeholders => app.fill.input.push_str(&single_line()),
+        Focus::Notes => app.note_draft.push_str(&text.replace("\r\n", "\n")),
+        Focus::ManPage | Focus::Sections | Focus::References => {}
     }
 }
 
 async fn handle_command_list_keys(app: &mut AppState, key: KeyEvent) {
+    clamp_selection(app);
     let commands_len = app.command_list.filtered_commands.len();
+    let columns = app.command_list.columns.max(1);
 
     match key.code {
-        KeyCode::Char(c) => {
+        KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.filter_mode = match app.filter_mode {
+                FilterMode::Name => FilterMode::Description,
+                FilterMode::Description => FilterMode::Name,
+            };
+            filter_commands(app);
+            schedule_filter_load(app);
+        }
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.show_sections = !app.show_sections;
+        }
+        KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.command_list.grid = !app.command_list.grid;
+        }
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => cycle_sort(app),
+        KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            toggle_description_source(app).await;
+        }
+        // Names never contain spaces, but description queries do
+        KeyCode::Char(' ') if matches!(app.filter_mode, FilterMode::Name) && commands_len > 0 => {
+            toggle_mark(app);
+            if app.command_list.selected_idx + 1 < commands_len {
+                app.command_list.selected_idx += 1;
+                update_list_scroll(app);
+                schedule_navigation_load(app);
+            }
+        }
+        KeyCode::Char(c) if !is_control(key) => {
             app.command_list.input.push(c);
             filter_commands(app);
-            app.pending_man_load = true;
-            app.last_input_time = Instant::now();
+            schedule_filter_load(app);
         }
         KeyCode::Backspace => {
             app.command_list.input.pop();
             filter_commands(app);
-            app.pending_man_load = true;
-            app.last_input_time = Instant::now();
+            schedule_filter_load(app);
         }
-        KeyCode::Up if commands_len > 0 => {
-            if app.command_list.selected_idx > 0 {
-                app.command_list.selected_idx -= 1;
-                update_list_scroll(app);
-                app.pending_man_load = true;
-                app.last_input_time = Instant::now();
-            }
+        KeyCode::Up if app.command_list.selected_idx >= columns => {
+            app.command_list.selected_idx -= columns;
+            update_list_scroll(app);
+            schedule_navigation_load(app);
         }
-        KeyCode::Down if commands_len > 0 => {
-            if app.command_list.selected_idx < commands_len - 1 {
-                app.command_list.selected_idx += 1;
-                update_list_scroll(app);
-                app.pending_man_load = true;
-                app.last_input_time = Instant::now();
-            }
+        KeyCode::Down if app.command_list.selected_idx + columns < commands_len => {
+            app.command_list.selected_idx += columns;
+            update_list_scroll(app);
+            schedule_navigation_load(app);
+        }
+        KeyCode::Left if columns > 1 && app.command_list.selected_idx > 0 => {
+            app.command_list.selected_idx -= 1;
+            update_list_scroll(app);
+            schedule_navigation_load(app);
+        }
+        KeyCode::Right if columns > 1 && app.command_list.selected_idx + 1 < commands_len => {
+            app.command_list.selected_idx += 1;
+            update_list_scroll(app);
+            schedule_navigation_load(app);
         }
         KeyCode::Home if commands_len > 0 => {
             app.command_list.selected_idx = 0;
             update_list_scroll(app);
-            app.pending_man_load = true;
-            app.last_input_time = Instant::now();
+            schedule_navigation_load(app);
         }
         KeyCode::End if commands_len > 0 => {
             app.command_list.selected_idx = commands_len - 1;
             update_list_scroll(app);
-            app.pending_man_load = true;
-            app.last_input_time = Instant::now();
+            schedule_navigation_load(app);
         }
         KeyCode::PageUp if commands_len > 0 => {
-            app.command_list.selected_idx = app
-                .command_list
-                .selected_idx
-                .saturating_sub(LIST_SIZE)
-                .max(0);
+            app.command_list.selected_idx = app.command_list.selected_idx.saturating_sub(LIST_SIZE);
             update_list_scroll(app);
-            app.pending_man_load = true;
-            app.last_input_time = Instant::now();
+            schedule_navigation_load(app);
         }
         KeyCode::PageDown if commands_len > 0 => {
             app.command_list.selected_idx =
                 (app.command_list.selected_idx + LIST_SIZE).min(commands_len - 1);
             update_list_scroll(app);
-            app.pending_man_load = true;
-            app.last_input_time = Instant::now();
+            schedule_navigation_load(app);
+        }
+        KeyCode::Enter if !app.command_list.marked.is_empty() => {
+            load_marked_pages(app);
+            app.pending_man_load = false;
         }
+        KeyCode::Enter if commands_len > 0 && app.options.on_enter != OnEnter::Load => {
+            let cmd = app.command_list.filtered_commands[app.command_list.selected_idx].clone();
+            app.pending_enter = Some((app.options.on_enter, cmd));
+        }
+        KeyCode::Enter if commands_len > 0 && open_section_picker(app) => {}
         KeyCode::Enter if commands_len > 0 => {
             app.pending_man_load = true;
             load_current_page(app).await;
@@ -287,422 +1276,4189 @@ async fn handle_command_list_keys(app: &mut AppState, key: KeyEvent) {
     }
 }
 
+/// Lists the selected command's sections when several document it; false
+/// leaves Enter to load the page directly
+fn open_section_picker(app: &mut AppState) -> bool {
+    if !matches!(app.page_source, PageSource::Man) || app.tldr_only {
+        return false;
+    }
+    let command = app.command_list.filtered_commands[app.command_list.selected_idx].clone();
+    let sections = app.provider.available_sections(&command);
+    if sections.len() < 2 {
+        return false;
+    }
+    app.sections = SectionPicker {
+        command,
+        sections,
+        selected: 0,
+    };
+    app.focus = Focus::Sections;
+    true
+}
+
+async fn handle_section_picker_keys(app: &mut AppState, key: KeyEvent) {
+    let picker = &mut app.sections;
+    match key.code {
+        KeyCode::Up => picker.selected = picker.selected.saturating_sub(1),
+        KeyCode::Down if picker.selected + 1 < picker.sections.len() => picker.selected += 1,
+        KeyCode::Char(c) if c.is_ascii_digit() && !is_control(key) => {
+            if let Some(idx) = picker.sections.iter().position(|s| s.starts_with(c)) {
+                picker.selected = idx;
+            }
+        }
+        KeyCode::Enter => {
+            app.focus = Focus::CommandList;
+            let command = app.sections.command.clone();
+            let section = app.sections.sections[app.sections.selected].clone();
+            if app.provider.section(&command) == Some(section.as_str()) {
+                load_current_page(app).await;
+            } else {
+                open_man_page(app, command, Some(section)).await;
+            }
+        }
+        KeyCode::Esc => app.focus = Focus::CommandList,
+        _ => {}
+    }
+}
+
+/// Delay before loading a page after typing in the filter
+fn debounce_delay() -> Duration {
+    Duration::from_millis(DEBOUNCE_DELAY_MS)
+}
+
+/// Schedules a page load once `delay` passes without further input
+fn schedule_load(app: &mut AppState, delay: Duration) {
+    app.pending_man_load = true;
+    app.pending_reload = false;
+    app.load_delay = delay;
+    app.last_input_time = Instant::now();
+}
+
+/// Schedules reloading the open page, leaving the command list as it is
+fn schedule_reload(app: &mut AppState, delay: Duration) {
+    schedule_load(app, delay);
+    app.pending_reload = true;
+}
+
+/// Runs the load `schedule_load` or `schedule_reload` asked for
+async fn load_pending_page(app: &mut AppState) {
+    if std::mem::take(&mut app.pending_reload) {
+        reload_content(app).await;
+    } else {
+        load_current_page(app).await;
+    }
+    app.pending_man_load = false;
+}
+
+/// Loads the open command again from the current page source, keeping its man
+/// section, so a followed reference or another section isn't lost
+async fn reload_content(app: &mut AppState) {
+    let Some(command) = app.man_page.command.clone() else {
+        return load_current_page(app).await;
+    };
+    app.loading = true;
+    let content = match (app.page_source, app.man_page.section.as_deref()) {
+        (PageSource::Man, Some(section)) if app.provider.section(&command) != Some(section) => {
+            app.provider.man_page_in(&command, section).await
+        }
+        (PageSource::Man, _) => app.provider.man_page(&command).await,
+        (PageSource::Tldr, _) => app.provider.tldr_page(&command).await,
+        (PageSource::Info, _) => app.provider.info_page(&command).await,
+    };
+    show_content(app, content);
+}
+
+/// Schedules a page load after moving the selection
+fn schedule_navigation_load(app: &mut AppState) {
+    let delay = if app.options.instant_preview {
+        Duration::ZERO
+    } else {
+        debounce_delay()
+    };
+    schedule_load(app, delay);
+}
+
+/// Schedules a page load after the filter changed, unless it's still too broad
+fn schedule_filter_load(app: &mut AppState) {
+    let matches = app.command_list.filtered_commands.len();
+    match app.options.max_preview_matches {
+        Some(limit) if matches > limit => {
+            app.pending_man_load = false;
+            show_placeholder(
+                app,
+                format!("{matches} matching commands, keep typing or press Enter to load"),
+            );
+        }
+        _ => schedule_load(app, debounce_delay()),
+    }
+}
+
+/// Scrolls the list so the selection keeps `scroll_off` rows from either edge
 fn update_list_scroll(app: &mut AppState) {
-    let visible_height = app.command_list.visible_range.1 - app.command_list.visible_range.0;
-    let selected_idx = app.command_list.selected_idx;
+    // Scrolling works in rows, which hold several commands in grid mode
+    let list = &mut app.command_list;
+    let columns = list.columns.max(1);
+    let height = (list.visible_range.1 - list.visible_range.0) / columns;
+    if height == 0 {
+        return;
+    }
 
-    if selected_idx == 0 {
-        app.command_list.list_scroll = 0;
-    } else if selected_idx == app.command_list.filtered_commands.len() - 1 {
-        app.command_list.list_scroll = selected_idx.saturating_sub(visible_height - 1);
-    } else if selected_idx < app.command_list.list_scroll {
-        app.command_list.list_scroll = selected_idx;
-    } else if selected_idx >= app.command_list.list_scroll + visible_height {
-        app.command_list.list_scroll = selected_idx - visible_height + 1;
+    let margin = app.options.scroll_off.min((height - 1) / 2);
+    let selected_row = list.selected_idx / columns;
+    let mut scroll_row = list.list_scroll / columns;
+
+    if selected_row < scroll_row + margin {
+        scroll_row = selected_row.saturating_sub(margin);
+    } else if selected_row + margin >= scroll_row + height {
+        scroll_row = selected_row + margin + 1 - height;
     }
+    let rows = list.filtered_commands.len().div_ceil(columns);
+    list.list_scroll = scroll_row.min(rows.saturating_sub(height)) * columns;
 }
 
 fn filter_commands(app: &mut AppState) {
-    let commands = app.man_db.get_commands();
+    let commands = app.provider.commands();
 
     app.command_list.filtered_commands = if app.command_list.input.is_empty() {
         Arc::new(commands.clone())
-    } else {
-        let filtered: Vec<String> = commands
+    } else if let FilterMode::Description = app.filter_mode {
+        let sensitive = app.options.case_mode.is_sensitive(&app.command_list.input);
+        let mut ranked: Vec<(usize, &String)> = commands
             .iter()
-            .filter(|cmd| {
-                cmd.to_lowercase()
-                    .contains(&app.command_list.input.to_lowercase())
+            .filter_map(|cmd| {
+                let description = app.provider.description(cmd)?;
+                search::description_relevance(&app.command_list.input, &description, sensitive)
+                    .map(|score| (score, cmd))
             })
-            .cloned()
             .collect();
-        Arc::new(filtered)
+        ranked.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        Arc::new(ranked.into_iter().map(|(_, cmd)| cmd.clone()).collect())
+    } else {
+        Arc::new(fuzzy::filter(
+            app.options.match_mode,
+            app.options.case_mode,
+            &app.command_list.input,
+            commands,
+            &app.command_list.lowered,
+        ))
     };
 
+    sort_commands(app);
     app.command_list.selected_idx = 0;
     app.command_list.list_scroll = 0;
 }
 
+/// Keeps the selection and list scroll inside the filtered commands, so code
+/// that shrinks the list without resetting them can't index past its end
+fn clamp_selection(app: &mut AppState) {
+    let list = &mut app.command_list;
+    let last = list.filtered_commands.len().saturating_sub(1);
+    list.selected_idx = list.selected_idx.min(last);
+    list.list_scroll = list.list_scroll.min(list.selected_idx);
+}
+
+/// Reorders the filtered commands for the active sort mode
+fn sort_commands(app: &mut AppState) {
+    let provider = app.provider.as_ref();
+    let commands = Arc::make_mut(&mut app.command_list.filtered_commands);
+    match app.sort_mode {
+        SortMode::Natural => {}
+        SortMode::Descending => commands.sort_unstable_by(|a, b| b.cmp(a)),
+        SortMode::Section => commands.sort_by(|a, b| {
+            let order = |cmd: &str| provider.section(cmd).map(section_order);
+            order(a).cmp(&order(b)).then_with(|| a.cmp(b))
+        }),
+    }
+}
+
+/// Switches to the next sort mode, keeping the selected command selected
+fn cycle_sort(app: &mut AppState) {
+    let list = &app.command_list;
+    let selected = list.filtered_commands.get(list.selected_idx).cloned();
+    app.sort_mode = app.sort_mode.next();
+    filter_commands(app);
+    if let Some(idx) = selected.and_then(|cmd| {
+        app.command_list
+            .filtered_commands
+            .iter()
+            .position(|c| *c == cmd)
+    }) {
+        app.command_list.selected_idx = idx;
+    }
+    update_list_scroll(app);
+}
+
 async fn load_current_page(app: &mut AppState) {
     if app.command_list.filtered_commands.is_empty() {
-        app.man_page.content = Arc::new(vec!["No commands found".to_string()]);
+        show_placeholder(app, "No commands found".to_string());
         return;
     }
 
+    clamp_selection(app);
     let cmd = app.command_list.filtered_commands[app.command_list.selected_idx].clone();
     app.loading = true;
+    log::debug!("Loading {:?} page for {cmd}", app.page_source);
+
+    // Fall back to the page's NAME line when apropos has no description
+    if app.description_source == DescriptionSource::Name
+        || app.provider.description(&cmd).is_none_or(|d| d.is_empty())
+    {
+        app.provider.name_line(&cmd).await;
+    }
 
     let content = match app.page_source {
-        PageSource::Man => app.man_db.get_man_page(&cmd).await,
-        PageSource::Tldr => app.man_db.get_tldr_page(&cmd).await,
+        PageSource::Man => app.provider.man_page(&cmd).await,
+        PageSource::Tldr => app.provider.tldr_page(&cmd).await,
+        PageSource::Info => app.provider.info_page(&cmd).await,
     };
 
-    app.man_page.content = content;
-    app.loading = false;
-    app.man_page.scroll = 0;
-    update_search_matches(app);
+    app.note = notes::load(&app.paths.config_dir.join(NOTES_DIR), &cmd);
+    app.man_page.section = app.provider.section(&cmd).map(String::from);
+    app.man_page.command = Some(cmd);
+    show_content(app, content);
 }
 
-fn handle_man_page_keys(app: &mut AppState, key: KeyEvent) {
-    match key.code {
-        KeyCode::Char('f') => {
-            app.focus = Focus::Search;
-            app.search.query.clear();
-        }
-        KeyCode::Up => app.man_page.scroll = app.man_page.scroll.saturating_sub(1),
-        KeyCode::Down => app.man_page.scroll = app.man_page.scroll.saturating_add(1),
-        KeyCode::Home => app.man_page.scroll = 0,
-        KeyCode::End => {
-            app.man_page.scroll = app.man_page.content.len().saturating_sub(PAGE_SIZE)
-        }
-        KeyCode::PageUp => app.man_page.scroll = app.man_page.scroll.saturating_sub(PAGE_SIZE),
-        KeyCode::PageDown => {
-            app.man_page.scroll = (app.man_page.scroll + PAGE_SIZE)
-                .min(app.man_page.content.len().saturating_sub(PAGE_SIZE))
-        }
-        KeyCode::Char('n') => next_search_match(app),
-        KeyCode::Char('N') => prev_search_match(app),
-        _ => {}
+/// Switches the description box between apropos and the NAME line
+async fn toggle_description_source(app: &mut AppState) {
+    app.description_source = match app.description_source {
+        DescriptionSource::Apropos => DescriptionSource::Name,
+        DescriptionSource::Name => DescriptionSource::Apropos,
+    };
+    let list = &app.command_list;
+    if app.description_source == DescriptionSource::Name
+        && let Some(cmd) = list.filtered_commands.get(list.selected_idx).cloned()
+    {
+        app.provider.name_line(&cmd).await;
     }
-}
 
-fn handle_search_keys(app: &mut AppState, key: KeyEvent) {
-    match key.code {
-        KeyCode::Enter => {
-            update_search_matches(app);
-            app.focus = Focus::ManPage;
-        }
-        KeyCode::Char(c) => {
-            app.search.query.push(c);
-            update_search_matches(app);
-        }
-        KeyCode::Backspace => {
-            app.search.query.pop();
-            update_search_matches(app);
-        }
-        KeyCode::Esc => {
-            app.search.query.clear();
-            app.search.matches = Arc::new(Vec::new());
-            app.focus = Focus::ManPage;
-        }
-        _ => {}
-    }
+    let (name, label) = match app.description_source {
+        DescriptionSource::Apropos => ("apropos", "apropos"),
+        DescriptionSource::Name => ("name", "NAME lines"),
+    };
+    save_setting(
+        app,
+        "description_source",
+        &Value::Str(name.to_string()),
+        format!("Descriptions from {label}"),
+    );
 }
 
-fn update_search_matches(app: &mut AppState) {
-    let mut matches = Vec::new();
-
-    if !app.search.query.is_empty() {
-        for (i, line) in app.man_page.content.iter().enumerate() {
-            if line
-                .to_lowercase()
-                .contains(&app.search.query.to_lowercase())
-            {
-                matches.push(i);
-            }
-        }
-    }
-
-    app.search.matches = Arc::new(matches);
-    app.search.current_match = 0;
+/// Saves a setting changed in the TUI to the config, noting message once saved
+fn save_setting(app: &mut AppState, key: &str, value: &Value, message: String) {
+    let path = app.paths.config_dir.join(CONFIG_FILE);
+    app.notice = Some(match config::store(&path, key, value) {
+        Ok(()) => message,
+        Err(e) => e.to_string(),
+    });
+}
 
-    if !app.search.matches.is_empty() {
-        app.man_page.scroll = app.search.matches[0].saturating_sub(PAGE_SIZE / 2);
-    }
+/// Word for a toggled setting in a notice
+fn on_off(enabled: bool) -> &'static str {
+    if enabled { "on" } else { "off" }
 }
 
-fn next_search_match(app: &mut AppState) {
-    if app.search.matches.is_empty() {
+/// Marks the selected command, or unmarks it if already marked
+fn toggle_mark(app: &mut AppState) {
+    let list = &mut app.command_list;
+    let Some(cmd) = list.filtered_commands.get(list.selected_idx) else {
         return;
+    };
+    if !list.marked.remove(cmd) {
+        list.marked.insert(cmd.clone());
     }
-
-    app.search.current_match = (app.search.current_match + 1) % app.search.matches.len();
-    let target_line = app.search.matches[app.search.current_match];
-    app.man_page.scroll = target_line.saturating_sub(PAGE_SIZE / 2);
 }
 
-fn prev_search_match(app: &mut AppState) {
-    if app.search.matches.is_empty() {
+/// Starts loading the man pages of every marked command at once, to be shown
+/// one after another by `show_marked_pages`. The marks are used up, so a later
+/// Enter opens the selected command again
+fn load_marked_pages(app: &mut AppState) {
+    if man_unavailable(app) {
         return;
     }
+    let mut commands: Vec<String> = app.command_list.marked.drain().collect();
+    commands.sort_unstable();
+    app.loading = true;
 
-    app.search.current_match = app
-        .search
-        .current_match
-        .checked_sub(1)
-        .unwrap_or(app.search.matches.len() - 1);
+    let rule = if app.options.ascii {
+        "----"
+    } else {
+        "────"
+    };
+    let provider = app.provider.clone();
+    app.pending_batch = Some(tokio::spawn(async move {
+        let mut loads = tokio::task::JoinSet::new();
+        for cmd in commands {
+            let provider = provider.clone();
+            loads.spawn(async move {
+                let page = provider.man_page(&cmd).await;
+                (cmd, page)
+            });
+        }
+        let mut pages = loads.join_all().await;
+        pages.sort_unstable_by(|a, b| a.0.cmp(&b.0));
 
-    let target_line = app.search.matches[app.search.current_match];
-    app.man_page.scroll = target_line.saturating_sub(PAGE_SIZE / 2);
+        let mut content = Vec::new();
+        for (cmd, page) in pages {
+            if !content.is_empty() {
+                content.push(String::new());
+            }
+            content.push(format!("{rule} {cmd} {rule}"));
+            content.extend(page.lines.iter().cloned());
+        }
+        LoadedPage::new(content)
+    }));
 }
 
-fn render_ui<B: tui::backend::Backend>(f: &mut tui::Frame<B>, app: &mut AppState) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(
-            [
-                Constraint::Length(1),
-                Constraint::Length(3),
-                Constraint::Min(10),
-            ]
-                .as_ref(),
-        )
-        .split(f.size());
+/// Shows the marked pages `load_marked_pages` started loading
+async fn show_marked_pages(app: &mut AppState) {
+    let Some(batch) = app.pending_batch.take() else {
+        return;
+    };
+    let Ok(page) = batch.await else {
+        app.loading = false;
+        return;
+    };
 
-    render_status_bar(f, app, chunks[0]);
-    render_input(f, app, chunks[1]);
-    render_main_content(f, app, chunks[2]);
+    app.page_source = PageSource::Man;
+    app.note = None;
+    app.man_page.section = None;
+    app.man_page.command = None;
+    show_content(app, page);
 }
 
-fn render_status_bar<B: tui::backend::Backend>(f: &mut tui::Frame<B>, app: &AppState, area: Rect) {
-    let source_label = match app.page_source {
-        PageSource::Man => "MAN",
-        PageSource::Tldr => "TLDR",
-    };
-
-    let status = if app.loading {
-        format!("Loading {source_label}...")
+/// Displays freshly loaded content from the top
+fn show_content(app: &mut AppState, page: LoadedPage) {
+    let content = page.lines;
+    app.man_page.raw_content = content.clone();
+    let content = if app.options.compact_blank_lines {
+        let kept = compact_line_indices(&content);
+        Arc::new(kept.into_iter().map(|idx| content[idx].clone()).collect())
     } else {
-        let x = &*format!(
-            "RTFM // {source_label} PAGE [Tab:Switch /:Search t:Toggle Home/End]"
-        );
-        match app.focus {
-            Focus::CommandList => "RTFM // COMMAND LIST [Tab:Switch Home/End]",
-            Focus::ManPage => x,
-            Focus::Search => "RTFM // SEARCH MODE [Enter:Apply Esc:Cancel]",
-        }
-            .parse()
-            .unwrap()
+        content
     };
+    app.man_page.is_error = page.failed;
+    // tldr pages mark their examples already
+    let find_examples = !matches!(app.page_source, PageSource::Tldr);
+    app.man_page.roles = roles::classify(&content, find_examples);
+    app.man_page.content = content;
+    app.loading = false;
+    app.man_page.scroll = 0;
+    app.man_page.cursor = 0;
+    app.man_page.h_scroll = 0;
+    app.man_page.jumped_to = None;
+    if app.options.jump_to == JumpTo::Options
+        && matches!(app.page_source, PageSource::Man)
+        && !app.man_page.is_error
+        && let Some(line) = options_heading(&app.man_page.content)
+    {
+        let max_scroll = app
+            .man_page
+            .content
+            .len()
+            .saturating_sub(app.man_page.visible_height);
+        app.man_page.jumped_to = Some(app.man_page.content[line].trim().to_string());
+        app.man_page.scroll = line.min(max_scroll);
+        app.man_page.cursor = line;
+    }
+    update_search_matches(app);
+}
 
-    let status_bar = Paragraph::new(status)
-        .block(Block::default())
-        .style(Style::default().bg(Color::DarkGray));
+/// Shows a notice in place of a page, dropping the previous page with its
+/// command, note and search matches
+fn show_placeholder(app: &mut AppState, text: String) {
+    app.man_page.command = None;
+    app.man_page.section = None;
+    app.note = None;
+    show_content(app, LoadedPage::new(vec![text]));
+}
 
-    f.render_widget(status_bar, area);
+/// Indices of the lines kept when each run of blank lines collapses into one
+/// and those at either end are dropped
+fn compact_line_indices(lines: &[String]) -> Vec<usize> {
+    let is_blank = |idx: usize| lines[idx].trim().is_empty();
+    let mut kept: Vec<usize> = Vec::with_capacity(lines.len());
+    for idx in 0..lines.len() {
+        let after_blank = kept.last().is_none_or(|&prev| is_blank(prev));
+        if !(is_blank(idx) && after_blank) {
+            kept.push(idx);
+        }
+    }
+    if kept.last().is_some_and(|&idx| is_blank(idx)) {
+        kept.pop();
+    }
+    kept
 }
 
-fn render_input<B: tui::backend::Backend>(f: &mut tui::Frame<B>, app: &AppState, area: Rect) {
-    let input_text = match app.focus {
-        Focus::CommandList | Focus::ManPage => format!("> {}", app.command_list.input),
-        Focus::Search => format!("/{}", app.search.query),
+/// Re-shows the loaded page with blank lines compacted or not, keeping the cursor
+/// on the same text
+fn toggle_compact_blank_lines(app: &mut AppState) {
+    let kept = compact_line_indices(&app.man_page.raw_content);
+    let (scroll, cursor) = (app.man_page.scroll, app.man_page.cursor);
+    app.options.compact_blank_lines = !app.options.compact_blank_lines;
+    let compact = app.options.compact_blank_lines;
+    let raw = LoadedPage {
+        lines: app.man_page.raw_content.clone(),
+        failed: app.man_page.is_error,
     };
+    show_content(app, raw);
 
-    let input = Paragraph::new(input_text.as_str())
-        .block(Block::default().borders(Borders::ALL))
-        .style(Style::default().fg(Color::Yellow));
+    let target = if compact {
+        kept.partition_point(|&idx| idx < cursor)
+    } else {
+        kept.get(cursor).copied().unwrap_or(cursor)
+    }
+    .min(app.man_page.content.len().saturating_sub(1));
+    app.man_page.cursor = target;
+    app.man_page.scroll = target.saturating_sub(cursor.saturating_sub(scroll));
 
-    f.render_widget(input, area);
+    save_setting(
+        app,
+        "compact_blank_lines",
+        &Value::Bool(compact),
+        format!("Compact blank lines {}", on_off(compact)),
+    );
 }
 
-fn render_main_content<B: tui::backend::Backend>(
-    f: &mut tui::Frame<B>,
-    app: &mut AppState,
-    area: Rect,
-) {
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
-        .split(area);
+/// Flips markers on wrapped rows and saves the setting to the config
+fn toggle_wrap_markers(app: &mut AppState) {
+    app.options.wrap_markers = !app.options.wrap_markers;
+    let markers = app.options.wrap_markers;
+    save_setting(
+        app,
+        "wrap_markers",
+        &Value::Bool(markers),
+        format!("Wrap markers {}", on_off(markers)),
+    );
+}
 
-    render_command_list(f, app, chunks[0]);
-    render_man_page(f, app, chunks[1]);
+/// Finds an unindented heading naming options, like `OPTIONS` or `GLOBAL FLAGS`
+fn options_heading(content: &[String]) -> Option<usize> {
+    content.iter().position(|line| {
+        let heading = line.trim_end();
+        roles::is_heading(line)
+            && OPTIONS_HEADINGS
+                .iter()
+                .any(|name| heading == *name || heading.ends_with(&format!(" {name}")))
+    })
 }
 
-fn render_command_list<B: tui::backend::Backend>(
-    f: &mut tui::Frame<B>,
-    app: &mut AppState,
-    area: Rect,
-) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Min(5), Constraint::Length(3)].as_ref())
-        .split(area);
+/// Opens the first man page reference on the cursor line, indexed or not
+async fn follow_reference(app: &mut AppState) {
+    let mut references: Vec<(String, String)> = app
+        .man_page
+        .content
+        .get(app.man_page.cursor)
+        .map(|line| links::find_man_refs(line))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, section)| (name.to_string(), section.to_string()))
+        .collect();
 
-    render_command_list_items(f, app, chunks[0]);
-    render_command_description(f, app, chunks[1]);
+    match references.len() {
+        0 => app.notice = Some("No man page reference on the cursor line".to_string()),
+        1 => {
+            let (name, section) = references.remove(0);
+            open_reference(app, name, section).await;
+        }
+        _ => {
+            app.references = ReferencePicker {
+                references,
+                selected: 0,
+            };
+            app.focus = Focus::References;
+        }
+    }
 }
 
-fn render_command_list_items<B: tui::backend::Backend>(
-    f: &mut tui::Frame<B>,
-    app: &mut AppState,
-    area: Rect,
-) {
-    let height = area.height as usize;
-    app.command_list.visible_range = (
-        app.command_list.list_scroll,
-        app.command_list.list_scroll + height,
-    );
+/// Opens a referenced page, remembering the current one for going back
+async fn open_reference(app: &mut AppState, name: String, section: String) {
+    remember_location(app);
+    open_man_page(app, name, Some(section)).await;
+}
 
-    if app.command_list.filtered_commands.is_empty() {
-        let empty_msg = ListItem::new("No commands found".to_string());
-        let list = List::new(vec![empty_msg])
-            .block(Block::default().borders(Borders::ALL).title("Commands"));
-        f.render_widget(list, area);
-        return;
+async fn handle_reference_picker_keys(app: &mut AppState, key: KeyEvent) {
+    let picker = &mut app.references;
+    match key.code {
+        KeyCode::Up => picker.selected = picker.selected.saturating_sub(1),
+        KeyCode::Down if picker.selected + 1 < picker.references.len() => picker.selected += 1,
+        KeyCode::Enter => {
+            app.focus = Focus::ManPage;
+            let (name, section) = app.references.references[app.references.selected].clone();
+            open_reference(app, name, section).await;
+        }
+        KeyCode::Esc => app.focus = Focus::ManPage,
+        _ => {}
     }
+}
 
-    let end = std::cmp::min(
-        app.command_list.list_scroll + height,
-        app.command_list.filtered_commands.len(),
-    );
-    let visible_commands = &app.command_list.filtered_commands[app.command_list.list_scroll..end];
+/// Saves the open page so going back can return to it
+fn remember_location(app: &mut AppState) {
+    if let Some(command) = app.man_page.command.clone() {
+        app.history.push(PageLocation {
+            command,
+            section: app.man_page.section.clone(),
+            source: app.page_source,
+            scroll: app.man_page.scroll,
+            cursor: app.man_page.cursor,
+        });
+    }
+}
 
-    let items: Vec<ListItem> = visible_commands
-        .iter()
-        .map(|cmd| {
-            let prefix = { "  " };
-            ListItem::new(format!("{prefix}{cmd}"))
-        })
-        .collect();
+/// Shows command's page from the current page source
+async fn open_page(app: &mut AppState, command: String) {
+    let content = match app.page_source {
+        PageSource::Man => return open_man_page(app, command, None).await,
+        PageSource::Tldr => app.provider.tldr_page(&command).await,
+        PageSource::Info => app.provider.info_page(&command).await,
+    };
 
-    let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Commands"))
-        .highlight_style(Style::default().bg(Color::DarkGray));
+    let section = app.provider.section(&command).map(String::from);
+    show_page(app, command, section, content);
+}
 
-    let mut state = ListState::default();
-    state.select(Some(
-        app.command_list.selected_idx - app.command_list.list_scroll,
-    ));
-    f.render_stateful_widget(list, area, &mut state);
+/// Shows content as command's page, with the command's note
+fn show_page(app: &mut AppState, command: String, section: Option<String>, content: LoadedPage) {
+    app.note = notes::load(&app.paths.config_dir.join(NOTES_DIR), &command);
+    app.man_page.section = section;
+    app.man_page.command = Some(command);
+    show_content(app, content);
 }
 
-fn render_command_description<B: tui::backend::Backend>(
-    f: &mut tui::Frame<B>,
-    app: &AppState,
-    area: Rect,
-) {
-    let description = if !app.command_list.filtered_commands.is_empty() {
-        if let Some(cmd) = app
-            .command_list
-            .filtered_commands
-            .get(app.command_list.selected_idx)
-        {
-            app.man_db.get_description(cmd).unwrap_or_default()
-        } else {
-            String::new()
-        }
-    } else {
-        "No commands to show".to_string()
+/// Returns to the page the last reference was followed from
+async fn go_back(app: &mut AppState) {
+    let Some(location) = app.history.pop() else {
+        app.notice = Some("No earlier page".to_string());
+        return;
     };
 
-    let desc_block = Paragraph::new(description)
-        .block(Block::default().borders(Borders::ALL).title("Description"))
-        .wrap(Wrap { trim: true })
-        .style(Style::default().fg(Color::Cyan));
+    match location.source {
+        PageSource::Man => open_man_page(app, location.command, location.section).await,
+        source => {
+            app.page_source = source;
+            open_page(app, location.command).await;
+        }
+    }
+    app.man_page.scroll = location.scroll;
+    app.man_page.cursor = location.cursor;
+    app.man_page.jumped_to = None;
+}
 
-    f.render_widget(desc_block, area);
+/// Shows command's man page from section, or from its indexed section
+async fn open_man_page(app: &mut AppState, command: String, section: Option<String>) {
+    // Without man, references and going back show the tldr page instead
+    if app.tldr_only {
+        let content = app.provider.tldr_page(&command).await;
+        let section = app.provider.section(&command).map(String::from);
+        return show_page(app, command, section, content);
+    }
+    app.page_source = PageSource::Man;
+    let content = match &section {
+        Some(section) => app.provider.man_page_in(&command, section).await,
+        None => app.provider.man_page(&command).await,
+    };
+
+    show_page(app, command, section, content);
 }
 
-fn render_man_page<B: tui::backend::Backend>(f: &mut tui::Frame<B>, app: &AppState, area: Rect) {
-    let height = area.height as usize;
-    let start_line = app.man_page.scroll;
-    let end_line = std::cmp::min(start_line + height, app.man_page.content.len());
+/// Schedules loading the open command's man page from the next or previous
+/// known section, so a key held down doesn't run `man` for every step
+fn cycle_section(app: &mut AppState, forward: bool) {
+    if man_unavailable(app) {
+        return;
+    }
+    let Some(cmd) = app.man_page.command.clone() else {
+        return;
+    };
+    let sections = app.provider.available_sections(&cmd);
+    if sections.len() < 2 {
+        app.notice = Some(format!("No other sections document {cmd}"));
+        return;
+    }
 
-    let visible_content: Vec<Spans> = app
+    let current = app
         .man_page
-        .content
-        .iter()
-        .enumerate()
-        .skip(start_line)
-        .take(end_line - start_line)
-        .map(|(idx, line)| {
-            let global_idx = idx + start_line;
-            if app.search.matches.contains(&global_idx) {
-                let search_index = app
-                    .search
-                    .matches
-                    .iter()
-                    .position(|&i| i == global_idx)
-                    .unwrap();
-                let highlight = search_index == app.search.current_match;
+        .section
+        .as_ref()
+        .and_then(|s| sections.iter().position(|known| known == s));
+    let next = match (current, forward) {
+        (Some(idx), true) => (idx + 1) % sections.len(),
+        (Some(idx), false) => idx.checked_sub(1).unwrap_or(sections.len() - 1),
+        (None, _) => 0,
+    };
+    app.page_source = PageSource::Man;
+    app.man_page.section = Some(sections[next].clone());
+    schedule_reload(app, debounce_delay());
+}
 
-                let mut spans = Vec::new();
-                let mut remaining = line.as_str();
+async fn handle_man_page_keys(app: &mut AppState, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char(']') => cycle_section(app, true),
+        KeyCode::Char('[') => cycle_section(app, false),
+        KeyCode::Char('f') => start_search(app),
+        KeyCode::Char('g') => start_jump(app),
+        KeyCode::Up => move_cursor(app, app.man_page.cursor.saturating_sub(1)),
+        KeyCode::Down => move_cursor(app, app.man_page.cursor + 1),
+        KeyCode::Enter if matches!(app.page_source, PageSource::Tldr) => confirm_example(app),
+        KeyCode::Enter => follow_reference(app).await,
+        KeyCode::Backspace => go_back(app).await,
+        KeyCode::Home => scroll_to_top(app),
+        KeyCode::End => scroll_to_bottom(app),
+        KeyCode::PageUp => scroll_page(app, false),
+        KeyCode::PageDown => scroll_page(app, true),
+        KeyCode::Char('n') => {
+            next_search_match(app);
+            app.show_peek = app.peek_mode;
+        }
+        KeyCode::Char('N') => {
+            prev_search_match(app);
+            app.show_peek = app.peek_mode;
+        }
+        KeyCode::Char('p') => {
+            app.peek_mode = !app.peek_mode;
+            app.show_peek = false;
+            app.notice = Some(format!("Match preview {}", on_off(app.peek_mode)));
+        }
+        KeyCode::Char('#') => {
+            app.line_numbers = match app.line_numbers {
+                LineNumbers::Off => LineNumbers::Absolute,
+                LineNumbers::Absolute => LineNumbers::Relative,
+                LineNumbers::Relative => LineNumbers::Off,
+            };
+        }
+        KeyCode::Char('{') => adjust_match_position(app, false),
+        KeyCode::Char('}') => adjust_match_position(app, true),
+        KeyCode::Char('b') => toggle_compact_blank_lines(app),
+        KeyCode::Char('Y') => copy_page(app, false),
+        KeyCode::Char('y') => copy_page(app, true),
+        KeyCode::Char('W') => toggle_wrap_markers(app),
+        KeyCode::Char('o') => open_cursor_url(app),
+        KeyCode::Char('w') => toggle_wrap(app),
+        KeyCode::Char('e') if app.man_page.command.is_some() => {
+            app.note_draft = app.note.clone().unwrap_or_default();
+            app.focus = Focus::Notes;
+        }
+        KeyCode::Left => {
+            app.man_page.h_scroll = app.man_page.h_scroll.saturating_sub(H_SCROLL_STEP)
+        }
+        KeyCode::Right if !wrap_enabled(app) => app.man_page.h_scroll += H_SCROLL_STEP,
+        _ => {}
+    }
+}
 
-                while let Some(pos) = remaining.find(&app.search.query) {
-                    let (before, after) = remaining.split_at(pos);
-                    let (match_text, rest) = after.split_at(app.search.query.len());
+/// Moves the view and the cursor by one page
+fn scroll_page(app: &mut AppState, forward: bool) {
+    let page = &mut app.man_page;
+    let height = page.visible_height.max(1);
+    let max_scroll = page.content.len().saturating_sub(height);
+    let last_line = page.content.len().saturating_sub(1);
 
-                    spans.push(Span::raw(before));
-                    spans.push(Span::styled(
-                        match_text,
-                        Style::default()
-                            .bg(if highlight {
-                                Color::Red
-                            } else {
-                                Color::DarkGray
-                            })
-                            .fg(if highlight {
-                                Color::White
-                            } else {
-                                Color::Black
-                            }),
-                    ));
+    if forward {
+        page.scroll = (page.scroll + height).min(max_scroll);
+        page.cursor = (page.cursor + height).min(last_line);
+    } else {
+        page.scroll = page.scroll.saturating_sub(height);
+        page.cursor = page.cursor.saturating_sub(height);
+    }
+    // Near either end the view stops early, so keep the cursor inside it
+    page.cursor = page
+        .cursor
+        .clamp(page.scroll, page.scroll + height - 1)
+        .min(last_line);
+}
 
-                    remaining = rest;
-                }
-                spans.push(Span::raw(remaining));
+/// Moves the cursor to line, scrolling just enough to keep it visible
+fn move_cursor(app: &mut AppState, line: usize) {
+    let page = &mut app.man_page;
+    page.cursor = line.min(page.content.len().saturating_sub(1));
+    let height = page.visible_height.max(1);
 
-                Spans::from(spans)
-            } else {
-                // Apply syntax highlighting
-                let highlighted = syntax_highlight(line);
-                Spans::from(highlighted)
-            }
+    if page.cursor < page.scroll {
+        page.scroll = page.cursor;
+    } else if page.cursor >= page.scroll + height {
+        page.scroll = page.cursor + 1 - height;
+    }
+}
+
+/// Extracts the command from a rendered tldr example line, placeholders intact
+fn tldr_template(line: &str) -> Option<&str> {
+    // Examples are indented deeper than titles and descriptions
+    let indent = line.len() - line.trim_start().len();
+    let example = line.trim();
+    if indent < 4 || example.is_empty() || example.starts_with("- ") {
+        return None;
+    }
+    Some(example)
+}
+
+/// Substitutes values for placeholders in order, keeping the text of the rest
+fn fill_placeholders(template: &str, values: &[String]) -> String {
+    let mut idx = 0;
+    PLACEHOLDER_RE
+        .replace_all(template, |caps: &regex::Captures| {
+            let value = values.get(idx).filter(|v| !v.is_empty());
+            idx += 1;
+            value.map_or_else(|| caps[1].to_string(), Clone::clone)
         })
-        .collect();
+        .into_owned()
+}
 
-    let paragraph = Paragraph::new(visible_content)
-        .block(Block::default().borders(Borders::ALL).title("Content"))
-        .wrap(Wrap { trim: true });
+/// Fills in the tldr example on the cursor line, then asks to run it
+fn confirm_example(app: &mut AppState) {
+    let line = app.man_page.content.get(app.man_page.cursor);
+    let Some(template) = line.and_then(|line| tldr_template(line)) else {
+        app.notice = Some("Cursor line is not an example".to_string());
+        return;
+    };
 
-    f.render_widget(paragraph, area);
+    app.fill = FillState {
+        template: template.to_string(),
+        names: PLACEHOLDER_RE
+            .captures_iter(template)
+            .map(|caps| caps[1].to_string())
+            .collect(),
+        values: Vec::new(),
+        input: String::new(),
+    };
+    if app.fill.names.is_empty() {
+        ask_to_run(app);
+    } else {
+        app.focus = Focus::Placeholders;
+    }
 }
 
-/// Basic syntax highlighting for man pages
-fn syntax_highlight(line: &str) -> Vec<Span> {
-    let mut spans = Vec::new();
-    let mut words = line.split_whitespace();
+/// Whether Ctrl is held, so prompts don't take the key as typed text
+fn is_control(key: KeyEvent) -> bool {
+    key.modifiers.contains(KeyModifiers::CONTROL)
+}
 
-    if let Some(first) = words.next() {
-        // Highlight headings
-        if first.ends_with(':') {
-            spans.push(Span::styled(
-                first,
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            ));
+fn handle_placeholder_keys(app: &mut AppState, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char(c) if !is_control(key) => app.fill.input.push(c),
+        KeyCode::Backspace => {
+            app.fill.input.pop();
         }
-        // Highlight options
-        else if first.starts_with('-') {
-            spans.push(Span::styled(
-                first,
-                Style::default()
-                    .fg(Color::Green)
-                    .add_modifier(Modifier::BOLD),
-            ));
-        } else {
-            spans.push(Span::raw(first));
+        KeyCode::Enter => {
+            let value = std::mem::take(&mut app.fill.input);
+            app.fill.values.push(value);
+            if app.fill.values.len() == app.fill.names.len() {
+                app.focus = Focus::ManPage;
+                ask_to_run(app);
+            }
+        }
+        KeyCode::Esc => {
+            app.focus = Focus::ManPage;
+            app.notice = Some("Cancelled".to_string());
         }
+        _ => {}
+    }
+}
 
-        for word in words {
-            spans.push(Span::raw(" "));
+/// Asks to run the example assembled from the fill-in state
+fn ask_to_run(app: &mut AppState) {
+    let example = fill_placeholders(&app.fill.template, &app.fill.values);
+    app.notice = Some(format!("Run `{example}`? [y/N, c to copy]"));
+    app.pending_run = Some(example);
+}
 
-            if word.starts_with('-') {
-                spans.push(Span::styled(word, Style::default().fg(Color::Green)));
-            } else if word.starts_with('[') && word.ends_with(']') {
-                spans.push(Span::styled(word, Style::default().fg(Color::Magenta)));
-            } else if word.starts_with('<') && word.ends_with('>') {
-                spans.push(Span::styled(word, Style::default().fg(Color::Blue)));
+/// Hands the terminal back to the shell until `resume_terminal`
+fn suspend_terminal(terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>) -> Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )?;
+    Ok(())
+}
+
+/// Takes the terminal back after `suspend_terminal` and redraws from scratch
+fn resume_terminal(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    mouse_capture: bool,
+) -> Result<()> {
+    enable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        EnterAlternateScreen,
+        EnableBracketedPaste
+    )?;
+    set_mouse_capture(terminal, mouse_capture)?;
+    terminal.clear()?;
+    Ok(())
+}
+
+/// Reads command's man page in the system pager outside the TUI, returning a status notice
+fn open_in_pager(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    command: &str,
+    section: Option<&str>,
+    host: Option<&str>,
+    mouse_capture: bool,
+) -> Result<String> {
+    suspend_terminal(terminal)?;
+    let shown = ManDb::display_man_page_in(command, section, host);
+    resume_terminal(terminal, mouse_capture)?;
+
+    Ok(match shown {
+        Ok(true) => format!("Closed the man page for {command}"),
+        Ok(false) => format!("man found no page for {command}"),
+        Err(e) => format!("Failed to open the pager: {e}"),
+    })
+}
+
+/// Starts the selected command, or the open page's, in a terminal of its own
+/// from the launcher template or `$TERMINAL -e`, leaving rtfm in place
+fn open_terminal(app: &mut AppState) {
+    // Launcher lines are shell syntax, which cmd doesn't share
+    if cfg!(windows) {
+        app.notice = Some("Opening a terminal is not supported on Windows".to_string());
+        return;
+    }
+    let command = match app.focus {
+        Focus::CommandList => app
+            .command_list
+            .filtered_commands
+            .get(app.command_list.selected_idx)
+            .cloned(),
+        _ => app.man_page.command.clone(),
+    };
+    let Some(command) = command else {
+        app.notice = Some("No command selected".to_string());
+        return;
+    };
+    let template = app.options.launcher.clone().or_else(|| {
+        std::env::var("TERMINAL")
+            .ok()
+            .filter(|terminal| !terminal.is_empty())
+            .map(|terminal| format!("{terminal} -e {{cmd}}"))
+    });
+    let Some(template) = template else {
+        app.notice =
+            Some("No terminal to open: set $TERMINAL or `launcher` in the config".to_string());
+        return;
+    };
+
+    let line = launch_line(&template, &command);
+    match spawn_detached(&line) {
+        Ok(check) => {
+            app.notice = Some(format!("Started {command} in a new terminal"));
+            app.launch_check = Some((line, check));
+        }
+        Err(e) => app.notice = Some(format!("Failed to run `{line}`: {e}")),
+    }
+}
+
+/// Reports a started terminal that exited early, once its reaper says so
+fn check_launch(app: &mut AppState) {
+    let Some((line, check)) = &app.launch_check else {
+        return;
+    };
+    match check.try_recv() {
+        Ok(e) => {
+            app.notice = Some(format!("Failed to run `{line}`: {e}"));
+            app.launch_check = None;
+        }
+        Err(std::sync::mpsc::TryRecvError::Empty) => {}
+        Err(std::sync::mpsc::TryRecvError::Disconnected) => app.launch_check = None,
+    }
+}
+
+/// Shell command line from a launcher template with `{cmd}` filled in quoted
+fn launch_line(template: &str, command: &str) -> String {
+    template.replace("{cmd}", &shell_quote(command))
+}
+
+/// Runs line through the shell without waiting for it. A thread reaps it when
+/// it exits, sending an error if it fails within `LAUNCH_CHECK`
+fn spawn_detached(line: &str) -> std::io::Result<std::sync::mpsc::Receiver<std::io::Error>> {
+    let mut child = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(line)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()?;
+    let (failed, check) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        // A missing program or a bad flag makes the shell exit right away
+        let deadline = Instant::now() + LAUNCH_CHECK;
+        while Instant::now() < deadline {
+            let error = match child.try_wait() {
+                Ok(Some(status)) if status.success() => return,
+                Ok(Some(status)) if status.code() == Some(127) => {
+                    std::io::Error::other("program not found on PATH")
+                }
+                Ok(Some(status)) => std::io::Error::other(format!("exited with {status}")),
+                Ok(None) => {
+                    std::thread::sleep(Duration::from_millis(10));
+                    continue;
+                }
+                Err(e) => e,
+            };
+            let _ = failed.send(error);
+            return;
+        }
+        drop(failed);
+        let _ = child.wait();
+    });
+    Ok(check)
+}
+
+/// Copies text with a clipboard command, or else with the OSC 52 escape, which
+/// terminals and tmux forward to the system clipboard even over ssh
+fn copy_to_clipboard(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    text: &str,
+    label: &str,
+) -> String {
+    use std::io::Write;
+    if let Some(program) = copy_with_command(text) {
+        return format!("Copied {label} with {program}");
+    }
+    let encoded = base64(text.as_bytes());
+    if encoded.len() > OSC52_MAX_LEN {
+        return format!(
+            "Failed to copy {label}: too large for the terminal clipboard and no pbcopy, wl-copy or xclip worked"
+        );
+    }
+    let sequence = format!("\x1b]52;c;{encoded}\x07");
+    let backend = terminal.backend_mut();
+    match backend
+        .write_all(sequence.as_bytes())
+        .and_then(|_| backend.flush())
+    {
+        Ok(()) => format!("Sent {label} to the terminal clipboard, which some terminals ignore"),
+        Err(e) => format!("Failed to copy {label}: {e}"),
+    }
+}
+
+/// Pipes text to the first clipboard command that takes it, returning its name
+fn copy_with_command(text: &str) -> Option<&'static str> {
+    use std::io::Write;
+    CLIPBOARD_COMMANDS.iter().find_map(|&(program, args)| {
+        let mut child = std::process::Command::new(program)
+            .args(args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .ok()?;
+        // Dropping stdin closes it, so the command sees the end of the text
+        let written = child.stdin.take()?.write_all(text.as_bytes()).is_ok();
+        let status = child.wait().ok()?;
+        (written && status.success()).then_some(program)
+    })
+}
+
+/// Queues the whole page, or only the lines in view, for copying as plain text
+fn copy_page(app: &mut AppState, visible_only: bool) {
+    if app.man_page.is_error {
+        app.notice = Some("Nothing to copy".to_string());
+        return;
+    }
+    let content = &app.man_page.content;
+    let lines = if visible_only {
+        let start = app.man_page.scroll.min(content.len());
+        let end = (start + app.man_page.lines_in_view).min(content.len());
+        &content[start..end]
+    } else {
+        &content[..]
+    };
+    let label = match lines.len() {
+        1 => "1 line".to_string(),
+        count => format!("{count} lines"),
+    };
+    app.pending_copy = Some((plain_text(lines), label));
+}
+
+/// Lines joined as clean text: page breaks become blank lines and leftover
+/// control characters are dropped
+fn plain_text(lines: &[String]) -> String {
+    lines
+        .iter()
+        .map(|line| {
+            line.chars()
+                .filter(|&c| c == '\t' || !c.is_control())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Standard padded base64 encoding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
             } else {
-                spans.push(Span::raw(word));
+                encoded.push('=');
             }
         }
+    }
+    encoded
+}
+
+/// Runs example in the shell outside the TUI, returning a status notice
+fn run_example(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    example: &str,
+    mouse_capture: bool,
+) -> Result<String> {
+    suspend_terminal(terminal)?;
+
+    println!("$ {example}");
+    let status = if cfg!(windows) {
+        std::process::Command::new("cmd")
+            .arg("/C")
+            .arg(example)
+            .status()
     } else {
-        spans.push(Span::raw(line));
+        std::process::Command::new("sh")
+            .arg("-c")
+            .arg(example)
+            .status()
+    };
+    println!("\nPress Enter to return to rtfm");
+    let waited = wait_for_enter();
+    resume_terminal(terminal, mouse_capture)?;
+    waited?;
+
+    Ok(match status {
+        Ok(status) => format!("`{example}` exited with {status}"),
+        Err(e) => format!("Failed to run `{example}`: {e}"),
+    })
+}
+
+/// Blocks until Enter is pressed. Keys come from the terminal rather than
+/// stdin, which is the drained page pipe under `--stdin`
+fn wait_for_enter() -> Result<()> {
+    loop {
+        if let Event::Key(key) = event::read()?
+            && key.code == KeyCode::Enter
+            && key.kind == KeyEventKind::Press
+        {
+            return Ok(());
+        }
     }
+}
 
-    spans
-}
\ No newline at end of file
+/// Turns terminal mouse reporting on or off
+fn set_mouse_capture(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    enabled: bool,
+) -> Result<()> {
+    if enabled {
+        execute!(terminal.backend_mut(), EnableMouseCapture)?;
+    } else {
+        execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    }
+    Ok(())
+}
+
+/// Drops cached pages and reloads the selected one fresh
+async fn clear_caches(app: &mut AppState) {
+    app.provider.clear_caches().await;
+    schedule_reload(app, Duration::ZERO);
+    app.notice = Some("Caches cleared".to_string());
+}
+
+fn toggle_mouse(app: &mut AppState) {
+    app.mouse_capture = !app.mouse_capture;
+    app.notice = Some(if app.mouse_capture {
+        "Mouse capture on".to_string()
+    } else {
+        "Mouse capture off, select text with the terminal".to_string()
+    });
+}
+
+/// Whether lines of the current page source are wrapped
+fn wrap_enabled(app: &AppState) -> bool {
+    match app.page_source {
+        PageSource::Man | PageSource::Info => app.wrap_man,
+        PageSource::Tldr => app.wrap_tldr,
+    }
+}
+
+/// Flips wrapping for the current page source and saves it to the config
+fn toggle_wrap(app: &mut AppState) {
+    let (key, wrap) = match app.page_source {
+        PageSource::Man | PageSource::Info => {
+            app.wrap_man = !app.wrap_man;
+            ("wrap_man", app.wrap_man)
+        }
+        PageSource::Tldr => {
+            app.wrap_tldr = !app.wrap_tldr;
+            ("wrap_tldr", app.wrap_tldr)
+        }
+    };
+    app.man_page.h_scroll = 0;
+
+    save_setting(
+        app,
+        key,
+        &Value::Bool(wrap),
+        format!("Wrapping {}", on_off(wrap)),
+    );
+}
+
+/// Opens the first URL on or below the cursor line
+fn open_cursor_url(app: &mut AppState) {
+    let url = app
+        .man_page
+        .content
+        .iter()
+        .skip(app.man_page.cursor)
+        .find_map(|line| links::find_urls(line).first().map(|url| url.to_string()));
+
+    app.notice = Some(match url {
+        Some(url) => match links::open_url(&url) {
+            Ok(()) => format!("Opened {url}"),
+            Err(e) => e.to_string(),
+        },
+        None => "No link found below the cursor".to_string(),
+    });
+}
+
+fn start_search(app: &mut AppState) {
+    app.focus = Focus::Search;
+    app.search.query.clear();
+    app.search.history_idx = None;
+}
+
+fn handle_search_keys(app: &mut AppState, key: KeyEvent) {
+    match key.code {
+        KeyCode::Enter => {
+            if app.options.jump_while_searching {
+                update_search_matches(app);
+            } else if let Some(&line) = app.search.matches.get(app.search.current_match) {
+                reveal_match(app, line);
+            }
+            let query = &app.search.query;
+            if !query.is_empty() && app.search.history.last() != Some(query) {
+                app.search.history.push(query.clone());
+            }
+            app.focus = Focus::ManPage;
+        }
+        KeyCode::Up => recall_search(app, true),
+        KeyCode::Down => recall_search(app, false),
+        KeyCode::Char(c) if !is_control(key) => {
+            app.search.query.push(c);
+            search_as_you_type(app);
+        }
+        KeyCode::Backspace => {
+            app.search.query.pop();
+            search_as_you_type(app);
+        }
+        KeyCode::Esc => {
+            app.search.query.clear();
+            app.search.matches = Arc::new(Vec::new());
+            app.focus = Focus::ManPage;
+        }
+        _ => {}
+    }
+}
+
+fn start_jump(app: &mut AppState) {
+    app.jump.query.clear();
+    update_jump_matches(app);
+    app.focus = Focus::Jump;
+}
+
+async fn handle_jump_keys(app: &mut AppState, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char(c) if !is_control(key) => {
+            app.jump.query.push(c);
+            update_jump_matches(app);
+        }
+        KeyCode::Backspace => {
+            app.jump.query.pop();
+            update_jump_matches(app);
+        }
+        KeyCode::Up => app.jump.selected = app.jump.selected.saturating_sub(1),
+        KeyCode::Down if app.jump.selected + 1 < app.jump.matches.len() => app.jump.selected += 1,
+        KeyCode::Enter => {
+            app.focus = Focus::ManPage;
+            if let Some(command) = app.jump.matches.get(app.jump.selected).cloned() {
+                remember_location(app);
+                open_page(app, command).await;
+            }
+        }
+        KeyCode::Esc => app.focus = Focus::ManPage,
+        _ => {}
+    }
+}
+
+/// Refilters the jump prompt's matches with the list's match mode
+fn update_jump_matches(app: &mut AppState) {
+    app.jump.matches = fuzzy::filter(
+        app.options.match_mode,
+        app.options.case_mode,
+        &app.jump.query,
+        app.provider.commands(),
+        &app.command_list.lowered,
+    );
+    app.jump.selected = 0;
+}
+
+/// Steps through search history, past the newest entry back to an empty query
+fn recall_search(app: &mut AppState, older: bool) {
+    let len = app.search.history.len();
+    let idx = match (app.search.history_idx, older) {
+        (_, true) if len == 0 => return,
+        (None, true) => Some(len - 1),
+        (Some(idx), true) => Some(idx.saturating_sub(1)),
+        (None, false) => return,
+        (Some(idx), false) => Some(idx + 1).filter(|&next| next < len),
+    };
+
+    app.search.history_idx = idx;
+    app.search.query = idx.map_or_else(String::new, |idx| app.search.history[idx].clone());
+    search_as_you_type(app);
+}
+
+fn handle_notes_keys(app: &mut AppState, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            save_note(app);
+            app.focus = Focus::ManPage;
+        }
+        KeyCode::Char(c) if !is_control(key) => app.note_draft.push(c),
+        KeyCode::Enter => app.note_draft.push('\n'),
+        KeyCode::Backspace => {
+            app.note_draft.pop();
+        }
+        KeyCode::Esc => {
+            app.notice = Some("Note discarded".to_string());
+            app.focus = Focus::ManPage;
+        }
+        _ => {}
+    }
+}
+
+/// Writes the draft as the loaded command's note
+fn save_note(app: &mut AppState) {
+    let Some(cmd) = app.man_page.command.clone() else {
+        return;
+    };
+    let dir = app.paths.config_dir.join(NOTES_DIR);
+
+    app.notice = Some(match notes::save(&dir, &cmd, &app.note_draft) {
+        Ok(()) => {
+            app.note = notes::load(&dir, &cmd);
+            format!("Saved note for {cmd}")
+        }
+        Err(e) => e.to_string(),
+    });
+}
+
+/// Recomputes matches and scrolls to the first one
+fn update_search_matches(app: &mut AppState) {
+    find_search_matches(app);
+    app.search.current_match = 0;
+    if let Some(&line) = app.search.matches.first() {
+        reveal_match(app, line);
+    }
+}
+
+/// Refreshes matches for an edited query, leaving the viewport alone unless
+/// `jump_while_searching` is set
+fn search_as_you_type(app: &mut AppState) {
+    if app.options.jump_while_searching {
+        update_search_matches(app);
+        return;
+    }
+
+    find_search_matches(app);
+    // Enter then lands on the nearest match below the viewport top
+    let scroll = app.man_page.scroll;
+    app.search.current_match = app
+        .search
+        .matches
+        .iter()
+        .position(|&line| line >= scroll)
+        .unwrap_or(0);
+}
+
+/// Recomputes the lines matching the search query
+fn find_search_matches(app: &mut AppState) {
+    let sensitive = app.options.case_mode.is_sensitive(&app.search.query);
+    app.search.parsed = SearchQuery::parse(&app.search.query, sensitive);
+    let matches: Vec<usize> = app
+        .man_page
+        .content
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| app.search.parsed.matches(line))
+        .map(|(i, _)| i)
+        .collect();
+
+    app.search.matches = Arc::new(matches);
+}
+
+fn next_search_match(app: &mut AppState) {
+    if app.search.matches.is_empty() {
+        return;
+    }
+
+    app.search.current_match = (app.search.current_match + 1) % app.search.matches.len();
+    if app.search.current_match == 0 {
+        app.notice = Some("search hit BOTTOM, continuing at TOP".to_string());
+    }
+    let target_line = app.search.matches[app.search.current_match];
+    reveal_match(app, target_line);
+}
+
+fn prev_search_match(app: &mut AppState) {
+    if app.search.matches.is_empty() {
+        return;
+    }
+
+    app.search.current_match = match app.search.current_match.checked_sub(1) {
+        Some(idx) => idx,
+        None => {
+            app.notice = Some("search hit TOP, continuing at BOTTOM".to_string());
+            app.search.matches.len() - 1
+        }
+    };
+
+    let target_line = app.search.matches[app.search.current_match];
+    reveal_match(app, target_line);
+}
+
+/// Puts the cursor on line and scrolls it `match_position` percent down the pane, unless it's
+/// already visible `scroll_off` lines from either edge; never scrolls past either end
+fn reveal_match(app: &mut AppState, line: usize) {
+    app.man_page.cursor = line;
+    let height = app.man_page.visible_height.max(1);
+    let margin = app.options.scroll_off.min((height - 1) / 2);
+    let scroll = app.man_page.scroll;
+    if line >= scroll + margin && line + margin < scroll + height {
+        return;
+    }
+
+    let max_scroll = app.man_page.content.len().saturating_sub(height);
+    let above = (height * app.options.match_position / 100).min(height - 1);
+    app.man_page.scroll = line.saturating_sub(above).min(max_scroll);
+}
+
+/// Moves where search matches are revealed up or down the pane by a step
+fn adjust_match_position(app: &mut AppState, down: bool) {
+    let position = &mut app.options.match_position;
+    *position = if down {
+        (*position + MATCH_POSITION_STEP).min(100)
+    } else {
+        position.saturating_sub(MATCH_POSITION_STEP)
+    };
+    app.notice = Some(match *position {
+        0 => "Matches revealed at the top".to_string(),
+        50 => "Matches revealed at the center".to_string(),
+        percent => format!("Matches revealed {percent}% down the pane"),
+    });
+}
+
+fn render_ui<B: tui::backend::Backend>(f: &mut tui::Frame<B>, app: &mut AppState) {
+    let size = f.size();
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        let message = format!("Terminal too small (need at least {MIN_WIDTH}x{MIN_HEIGHT})");
+        let paragraph = Paragraph::new(message)
+            .wrap(Wrap { trim: true })
+            .style(Style::default().fg(app.options.theme.warning));
+        f.render_widget(paragraph, size);
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(1),
+                Constraint::Length(3),
+                Constraint::Min(10),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+
+    render_status_bar(f, app, chunks[0]);
+    render_input(f, app, chunks[1]);
+    render_main_content(f, app, chunks[2]);
+
+    if app.options.ascii {
+        f.render_widget(AsciiBorders, f.size());
+    }
+}
+
+/// Replaces box-drawing glyphs in its area with ASCII
+struct AsciiBorders;
+
+impl Widget for AsciiBorders {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get_mut(x, y);
+                if let Some(ascii) = ascii_symbol(&cell.symbol) {
+                    cell.set_symbol(ascii);
+                }
+            }
+        }
+    }
+}
+
+/// ASCII stand-in for a box-drawing symbol
+fn ascii_symbol(symbol: &str) -> Option<&'static str> {
+    match symbol {
+        line::HORIZONTAL | line::DOUBLE_HORIZONTAL | line::THICK_HORIZONTAL => Some("-"),
+        line::VERTICAL | line::DOUBLE_VERTICAL | line::THICK_VERTICAL => Some("|"),
+        line::TOP_LEFT
+        | line::TOP_RIGHT
+        | line::BOTTOM_LEFT
+        | line::BOTTOM_RIGHT
+        | line::ROUNDED_TOP_LEFT
+        | line::ROUNDED_TOP_RIGHT
+        | line::ROUNDED_BOTTOM_LEFT
+        | line::ROUNDED_BOTTOM_RIGHT
+        | line::CROSS
+        | line::VERTICAL_LEFT
+        | line::VERTICAL_RIGHT
+        | line::HORIZONTAL_DOWN
+        | line::HORIZONTAL_UP => Some("+"),
+        _ => None,
+    }
+}
+
+fn render_status_bar<B: tui::backend::Backend>(f: &mut tui::Frame<B>, app: &AppState, area: Rect) {
+    let source_label = match app.page_source {
+        PageSource::Man => "MAN",
+        PageSource::Tldr => "TLDR",
+        PageSource::Info => "INFO",
+    };
+
+    let status = if app.loading {
+        format!("Loading {source_label}...")
+    } else if let Some(notice) = &app.notice {
+        format!("RTFM // {notice}")
+    } else {
+        let x = &*format!(
+            "RTFM // {source_label} PAGE [Tab:Switch /:Search t:Toggle o:Open link Home/End]"
+        );
+        let sort = match app.sort_mode {
+            SortMode::Natural if app.command_list.input.is_empty() => "A-Z",
+            SortMode::Natural
+                if matches!(app.filter_mode, FilterMode::Description)
+                    || fuzzy::ranks(app.options.match_mode, &app.command_list.input) =>
+            {
+                "BEST MATCH"
+            }
+            SortMode::Natural => "A-Z",
+            SortMode::Descending => "Z-A",
+            SortMode::Section => "BY SECTION",
+        };
+        let list = &*match app.filter_mode {
+            FilterMode::Name => format!(
+                "RTFM // COMMAND LIST ({sort}) [Tab:Switch ^D:Search descriptions ^S:Sections ^R:Sort Home/End]"
+            ),
+            FilterMode::Description => format!(
+                "RTFM // DESCRIPTION SEARCH ({sort}) [Tab:Switch ^D:Search names ^R:Sort Home/End]"
+            ),
+        };
+        match app.focus {
+            Focus::CommandList => list,
+            Focus::ManPage => x,
+            Focus::Search => "RTFM // SEARCH MODE [Enter:Apply Esc:Cancel]",
+            Focus::Notes => "RTFM // EDITING NOTE [^S:Save Esc:Discard]",
+            Focus::Jump => "RTFM // JUMP TO COMMAND [Enter:Open Esc:Cancel]",
+            Focus::Sections => "RTFM // PICK A SECTION [Enter:Open Esc:Cancel]",
+            Focus::References => "RTFM // PICK A REFERENCE [Enter:Open Esc:Cancel]",
+            Focus::Placeholders => "RTFM // FILL IN EXAMPLE [Enter:Next Esc:Cancel]",
+        }
+        .parse()
+        .unwrap()
+    };
+
+    let status_bar = Paragraph::new(status)
+        .block(Block::default())
+        .style(Style::default().bg(app.options.theme.status_bar_bg));
+
+    f.render_widget(status_bar, area);
+}
+
+fn render_input<B: tui::backend::Backend>(f: &mut tui::Frame<B>, app: &AppState, area: Rect) {
+    let input_text = match app.focus {
+        Focus::CommandList
+        | Focus::ManPage
+        | Focus::Notes
+        | Focus::Jump
+        | Focus::Sections
+        | Focus::References => match app.filter_mode {
+            FilterMode::Name => format!("> {}", app.command_list.input),
+            FilterMode::Description => format!("desc> {}", app.command_list.input),
+        },
+        Focus::Search => format!("/{}", app.search.query),
+        Focus::Placeholders => {
+            let name = &app.fill.names[app.fill.values.len()];
+            format!("{name}: {}", app.fill.input)
+        }
+    };
+
+    let prompting = matches!(app.focus, Focus::Search | Focus::Placeholders);
+    let input = Paragraph::new(input_text.as_str())
+        .block(pane_block("", prompting, &app.options.theme))
+        .style(Style::default().fg(app.options.theme.input_fg));
+
+    f.render_widget(input, area);
+}
+
+fn render_main_content<B: tui::backend::Backend>(
+    f: &mut tui::Frame<B>,
+    app: &mut AppState,
+    area: Rect,
+) {
+    let (list_area, content_area) = match app.options.preview {
+        PreviewPosition::Right => {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+                .split(area);
+            (chunks[0], chunks[1])
+        }
+        PreviewPosition::Top | PreviewPosition::Bottom => {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+                .split(area);
+            if app.options.preview == PreviewPosition::Top {
+                (chunks[1], chunks[0])
+            } else {
+                (chunks[0], chunks[1])
+            }
+        }
+        PreviewPosition::None => {
+            if matches!(app.focus, Focus::CommandList) {
+                render_command_list(f, app, area);
+            } else {
+                render_content_pane(f, app, area);
+            }
+            return;
+        }
+    };
+
+    render_command_list(f, app, list_area);
+    render_content_pane(f, app, content_area);
+}
+
+/// Draws the page content with its note and note editor
+fn render_content_pane<B: tui::backend::Backend>(
+    f: &mut tui::Frame<B>,
+    app: &mut AppState,
+    area: Rect,
+) {
+    let note_lines = app.note.as_ref().map_or(0, |note| note.lines().count());
+    if note_lines == 0 {
+        render_man_page(f, app, area);
+    } else {
+        let note_height = (note_lines as u16 + 2).min(area.height / 3);
+        let pane = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(note_height), Constraint::Min(3)].as_ref())
+            .split(area);
+        render_note(f, app, pane[0]);
+        render_man_page(f, app, pane[1]);
+    }
+
+    if matches!(app.focus, Focus::Notes) {
+        render_note_editor(f, app, area);
+    }
+    if matches!(app.focus, Focus::Jump) {
+        render_jump_prompt(f, app, area);
+    }
+    if matches!(app.focus, Focus::Sections) {
+        render_section_picker(f, app, area);
+    }
+    if matches!(app.focus, Focus::References) {
+        render_reference_picker(f, app, area);
+    }
+}
+
+/// Draws the sections of the command about to open over the content pane
+fn render_section_picker<B: tui::backend::Backend>(
+    f: &mut tui::Frame<B>,
+    app: &AppState,
+    area: Rect,
+) {
+    let picker = &app.sections;
+    let items = picker
+        .sections
+        .iter()
+        .map(|section| match section_name(section) {
+            Some(name) => format!(" {section}: {name}"),
+            None => format!(" {section}"),
+        })
+        .collect();
+    let title = format!("{} sections", picker.command);
+    render_picker(f, app, area, &title, items, picker.selected);
+}
+
+/// Draws the references on the cursor line over the content pane
+fn render_reference_picker<B: tui::backend::Backend>(
+    f: &mut tui::Frame<B>,
+    app: &AppState,
+    area: Rect,
+) {
+    let picker = &app.references;
+    let items = picker
+        .references
+        .iter()
+        .map(|(name, section)| format!(" {name}({section})"))
+        .collect();
+    render_picker(f, app, area, "References", items, picker.selected);
+}
+
+/// Draws a centered popup listing items with the selected one highlighted
+fn render_picker<B: tui::backend::Backend>(
+    f: &mut tui::Frame<B>,
+    app: &AppState,
+    area: Rect,
+    title: &str,
+    items: Vec<String>,
+    selected: usize,
+) {
+    let lines: Vec<Spans> = items
+        .into_iter()
+        .enumerate()
+        .map(|(idx, text)| {
+            let style = if idx == selected {
+                Style::default().bg(app.options.theme.selection_bg)
+            } else {
+                Style::default()
+            };
+            Spans::from(Span::styled(text, style))
+        })
+        .collect();
+
+    let width = lines
+        .iter()
+        .map(|l| l.width() as u16 + 1)
+        .max()
+        .unwrap_or(0);
+    let width = width.max(title.len() as u16 + 4).min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+    // Scroll so the selection stays in view on short terminals
+    let rows = height.saturating_sub(2) as usize;
+    let first = (selected + 1).saturating_sub(rows) as u16;
+
+    let list = Paragraph::new(lines)
+        .block(pane_block(title, true, &app.options.theme))
+        .scroll((first, 0));
+    f.render_widget(Clear, popup);
+    f.render_widget(list, popup);
+}
+
+/// Draws the jump prompt and its best matches over the content pane
+fn render_jump_prompt<B: tui::backend::Backend>(f: &mut tui::Frame<B>, app: &AppState, area: Rect) {
+    let height = (area.height / 2).max(3).min(area.height);
+    let popup = Rect::new(
+        area.x + 2,
+        area.y + 1.min(area.height - height),
+        area.width.saturating_sub(4),
+        height,
+    );
+    let rows = (height as usize).saturating_sub(3);
+    // Scroll the matches so the selection stays in view
+    let first = (app.jump.selected + 1).saturating_sub(rows);
+
+    let mut lines = vec![Spans::from(Span::styled(
+        format!("> {}_", app.jump.query),
+        Style::default().fg(app.options.theme.input_fg),
+    ))];
+    lines.extend(
+        app.jump
+            .matches
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(rows)
+            .map(|(idx, cmd)| {
+                let style = if idx == app.jump.selected {
+                    Style::default().bg(app.options.theme.selection_bg)
+                } else {
+                    Style::default()
+                };
+                Spans::from(Span::styled(format!("  {cmd}"), style))
+            }),
+    );
+
+    let prompt =
+        Paragraph::new(lines).block(pane_block("Jump to command", true, &app.options.theme));
+    f.render_widget(Clear, popup);
+    f.render_widget(prompt, popup);
+}
+
+fn render_note<B: tui::backend::Backend>(f: &mut tui::Frame<B>, app: &AppState, area: Rect) {
+    let note = Paragraph::new(app.note.as_deref().unwrap_or_default())
+        .block(pane_block("Notes", false, &app.options.theme))
+        .wrap(Wrap { trim: false })
+        .style(Style::default().fg(app.options.theme.note_fg));
+
+    f.render_widget(note, area);
+}
+
+/// Draws the note editor as a popup over the content pane
+fn render_note_editor<B: tui::backend::Backend>(f: &mut tui::Frame<B>, app: &AppState, area: Rect) {
+    let height = (area.height / 2).max(3).min(area.height);
+    let popup = Rect::new(
+        area.x + 2,
+        area.y + (area.height - height) / 2,
+        area.width.saturating_sub(4),
+        height,
+    );
+    let title = format!(
+        "Note for {}",
+        app.man_page.command.as_deref().unwrap_or_default()
+    );
+    let editor = Paragraph::new(format!("{}_", app.note_draft))
+        .block(pane_block(&title, true, &app.options.theme))
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(Clear, popup);
+    f.render_widget(editor, popup);
+}
+
+fn render_command_list<B: tui::backend::Backend>(
+    f: &mut tui::Frame<B>,
+    app: &mut AppState,
+    area: Rect,
+) {
+    // Grow the description box to fit, but never past a third of the pane
+    let description = selected_description(app);
+    let inner_width = area.width.saturating_sub(2) as usize;
+    let max_lines = (area.height / 3).saturating_sub(2).max(1) as usize;
+    let lines = wrapped_line_count(&description, inner_width).clamp(1, max_lines);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(lines as u16 + 2)].as_ref())
+        .split(area);
+
+    render_command_list_items(f, app, chunks[0]);
+    render_command_description(f, app, &description, chunks[1]);
+}
+
+/// Description of the selected command, from apropos or its NAME line
+fn selected_description(app: &AppState) -> String {
+    if app.command_list.filtered_commands.is_empty() {
+        return "No commands to show".to_string();
+    }
+
+    match app
+        .command_list
+        .filtered_commands
+        .get(app.command_list.selected_idx)
+    {
+        Some(cmd) => {
+            let apropos = || app.provider.description(cmd).filter(|d| !d.is_empty());
+            let name = || app.provider.cached_name_line(cmd);
+            match app.description_source {
+                DescriptionSource::Apropos => apropos().or_else(name),
+                DescriptionSource::Name => name().or_else(apropos),
+            }
+            .unwrap_or_default()
+        }
+        None => String::new(),
+    }
+}
+
+/// Rows text takes when word-wrapped to width
+fn wrapped_line_count(text: &str, width: usize) -> usize {
+    if width == 0 {
+        return 0;
+    }
+
+    let mut lines = 0;
+    let mut used = 0;
+    for word in text.split_whitespace() {
+        let len = word.chars().count();
+        if lines == 0 || used + 1 + len > width {
+            // Words longer than the width are broken over several rows
+            lines += len.div_ceil(width).max(1);
+            used = len % width;
+            if used == 0 {
+                used = width;
+            }
+        } else {
+            used += 1 + len;
+        }
+    }
+    lines
+}
+
+fn render_command_list_items<B: tui::backend::Backend>(
+    f: &mut tui::Frame<B>,
+    app: &mut AppState,
+    area: Rect,
+) {
+    let height = area.height as usize;
+    let focused = matches!(app.focus, Focus::CommandList);
+    let title = list_title(app);
+    let columns = grid_columns(app, area);
+    let relayout = columns != app.command_list.columns;
+    if relayout {
+        // Keep the scroll offset on a row boundary of the new layout
+        app.command_list.columns = columns;
+        app.command_list.list_scroll -= app.command_list.list_scroll % columns;
+    }
+    app.command_list.visible_range = (
+        app.command_list.list_scroll,
+        app.command_list.list_scroll + height.saturating_sub(2) * columns,
+    );
+    if relayout {
+        update_list_scroll(app);
+        app.command_list.visible_range.0 = app.command_list.list_scroll;
+    }
+
+    if app.provider.commands().is_empty() {
+        let warning = Paragraph::new(EMPTY_INDEX_WARNING)
+            .block(pane_block(&title, focused, &app.options.theme))
+            .wrap(Wrap { trim: true })
+            .style(
+                Style::default()
+                    .fg(app.options.theme.warning)
+                    .add_modifier(Modifier::BOLD),
+            );
+        f.render_widget(warning, area);
+        return;
+    }
+
+    if app.command_list.filtered_commands.is_empty() {
+        let empty_msg = ListItem::new("No commands found".to_string());
+        let list =
+            List::new(vec![empty_msg]).block(pane_block(&title, focused, &app.options.theme));
+        f.render_widget(list, area);
+        return;
+    }
+
+    clamp_selection(app);
+    if columns > 1 {
+        render_command_grid(f, app, area, columns);
+        return;
+    }
+
+    let end = std::cmp::min(
+        app.command_list.list_scroll + height,
+        app.command_list.filtered_commands.len(),
+    );
+    let visible_commands = &app.command_list.filtered_commands[app.command_list.list_scroll..end];
+
+    let items: Vec<ListItem> = visible_commands
+        .iter()
+        .map(|cmd| ListItem::new(list_label_spans(app, cmd, 0)))
+        .collect();
+
+    let list = List::new(items)
+        .block(pane_block(&title, focused, &app.options.theme))
+        .highlight_style(Style::default().bg(app.options.theme.selection_bg));
+
+    let mut state = ListState::default();
+    state.select(Some(
+        app.command_list.selected_idx - app.command_list.list_scroll,
+    ));
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// Title of the command list pane, saying when the index was cut short
+fn list_title(app: &AppState) -> String {
+    if app.provider.truncated() {
+        format!("Commands (first {})", app.provider.commands().len())
+    } else {
+        "Commands".to_string()
+    }
+}
+
+/// Draws visible commands row by row, several per row
+fn render_command_grid<B: tui::backend::Backend>(
+    f: &mut tui::Frame<B>,
+    app: &AppState,
+    area: Rect,
+    columns: usize,
+) {
+    let list = &app.command_list;
+    let end = list
+        .visible_range
+        .1
+        .min(list.filtered_commands.len())
+        .max(list.list_scroll);
+    let width = grid_cell_width(app);
+
+    let rows: Vec<ListItem> = list.filtered_commands[list.list_scroll..end]
+        .chunks(columns)
+        .enumerate()
+        .map(|(row, cmds)| {
+            let cells: Vec<Span> = cmds
+                .iter()
+                .enumerate()
+                .flat_map(|(col, cmd)| {
+                    let selected = list.list_scroll + row * columns + col == list.selected_idx;
+                    list_label_spans(app, cmd, width)
+                        .0
+                        .into_iter()
+                        .map(move |span| {
+                            if selected {
+                                let style = span.style.bg(app.options.theme.selection_bg);
+                                Span::styled(span.content, style)
+                            } else {
+                                span
+                            }
+                        })
+                })
+                .collect();
+            ListItem::new(Spans::from(cells))
+        })
+        .collect();
+
+    let focused = matches!(app.focus, Focus::CommandList);
+    let title = list_title(app);
+    f.render_widget(
+        List::new(rows).block(pane_block(&title, focused, &app.options.theme)),
+        area,
+    );
+}
+
+/// Command list entry for cmd, with its section when sections are shown
+fn list_label(app: &AppState, cmd: &str) -> String {
+    let prefix = match (app.command_list.marked.contains(cmd), app.options.ascii) {
+        (false, _) => "  ",
+        (true, false) => "✓ ",
+        (true, true) => "* ",
+    };
+    match app.provider.section(cmd) {
+        Some(section) if app.show_sections => format!("{prefix}{cmd} ({section})"),
+        _ => format!("{prefix}{cmd}"),
+    }
+}
+
+/// `list_label` padded to width, with the characters the filter matched highlighted
+fn list_label_spans(app: &AppState, cmd: &str, width: usize) -> Spans<'static> {
+    let label = format!("{:<width$}", list_label(app, cmd));
+    let input = &app.command_list.input;
+    let positions = if app.options.highlight_matches
+        && matches!(app.filter_mode, FilterMode::Name)
+        && !input.is_empty()
+    {
+        fuzzy::match_positions(app.options.match_mode, app.options.case_mode, input, cmd)
+    } else {
+        Vec::new()
+    };
+    if positions.is_empty() {
+        return Spans::from(label);
+    }
+
+    let matched_style = Style::default()
+        .fg(app.options.theme.list_match)
+        .add_modifier(Modifier::BOLD);
+    let mut spans = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (idx, c) in label.chars().enumerate() {
+        let matched = idx
+            .checked_sub(LIST_MARKER_WIDTH)
+            .is_some_and(|pos| positions.binary_search(&pos).is_ok());
+        if matched != run_matched && !run.is_empty() {
+            let style = if run_matched {
+                matched_style
+            } else {
+                Style::default()
+            };
+            spans.push(Span::styled(std::mem::take(&mut run), style));
+        }
+        run_matched = matched;
+        run.push(c);
+    }
+    let style = if run_matched {
+        matched_style
+    } else {
+        Style::default()
+    };
+    spans.push(Span::styled(run, style));
+    Spans::from(spans)
+}
+
+/// Width of one grid cell, fitting the longest filtered entry
+fn grid_cell_width(app: &AppState) -> usize {
+    app.command_list
+        .filtered_commands
+        .iter()
+        .map(|cmd| list_label(app, cmd).chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Columns the command list uses in area, 1 unless the grid helps
+fn grid_columns(app: &AppState, area: Rect) -> usize {
+    let rows = (area.height as usize).saturating_sub(2);
+    let list = &app.command_list;
+    if !list.grid || list.filtered_commands.len() <= rows {
+        return 1;
+    }
+
+    let inner_width = (area.width as usize).saturating_sub(2);
+    (inner_width / grid_cell_width(app).max(1)).max(1)
+}
+
+fn render_command_description<B: tui::backend::Backend>(
+    f: &mut tui::Frame<B>,
+    app: &AppState,
+    description: &str,
+    area: Rect,
+) {
+    let inner_width = area.width.saturating_sub(2) as usize;
+    let truncated = wrapped_line_count(description, inner_width) + 2 > area.height as usize;
+    let source = match app.description_source {
+        DescriptionSource::Apropos => "apropos",
+        DescriptionSource::Name => "NAME",
+    };
+    let title = format!("{} ({source})", description_subject(app));
+    let title = match (truncated, app.options.ascii) {
+        (false, _) => title,
+        (true, false) => format!("{title} …"),
+        (true, true) => format!("{title} ..."),
+    };
+
+    let desc_block = Paragraph::new(description)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .wrap(Wrap { trim: true })
+        .style(Style::default().fg(app.options.theme.description_fg));
+
+    f.render_widget(desc_block, area);
+}
+
+/// Names the selected command and its indexed section, like `Description of git(1)`
+fn description_subject(app: &AppState) -> String {
+    let list = &app.command_list;
+    match list.filtered_commands.get(list.selected_idx) {
+        Some(cmd) => match app.provider.section(cmd) {
+            Some(section) => format!("Description of {cmd}({section})"),
+            None => format!("Description of {cmd}"),
+        },
+        None => "Description".to_string(),
+    }
+}
+
+fn render_man_page<B: tui::backend::Backend>(
+    f: &mut tui::Frame<B>,
+    app: &mut AppState,
+    area: Rect,
+) {
+    let padding = app.options.content_padding;
+    let area = match app.options.max_width {
+        0 => area,
+        text => column_rect(area, text + 2 * padding),
+    };
+    let padding = padding.min((area.width as usize).saturating_sub(2) / 2);
+    // Columns taken by the borders and padding on both sides
+    let frame = 2 + 2 * padding;
+    let height = area.height as usize;
+    app.man_page.visible_height = height.saturating_sub(2);
+    if app.man_page.is_error {
+        render_error_page(f, app, area);
+        return;
+    }
+    let start_line = app.man_page.scroll;
+    let end_line = std::cmp::min(start_line + height, app.man_page.content.len());
+    let wrap = wrap_enabled(app);
+    let focused = matches!(app.focus, Focus::ManPage);
+    let marker = if app.options.ascii {
+        ASCII_CLIPPED_MARKER
+    } else {
+        CLIPPED_MARKER
+    };
+    let gutter_width = match app.line_numbers {
+        LineNumbers::Off => 0,
+        _ => app.man_page.content.len().max(1).to_string().len() + 1,
+    };
+    // Wrapping breaks column alignment, so table rows are clipped to the pane
+    let table_width = (area.width as usize)
+        .saturating_sub(frame + gutter_width + marker.chars().count())
+        .max(1);
+
+    let visible_content: Vec<Spans> = app
+        .man_page
+        .content
+        .iter()
+        .enumerate()
+        .skip(start_line)
+        .take(end_line - start_line)
+        .map(|(idx, full_line)| {
+            if full_line == PAGE_BREAK {
+                let rule = if app.options.ascii { "-" } else { "─" };
+                // Spans the text column, even when scrolled sideways
+                let width = (area.width as usize).saturating_sub(frame + gutter_width)
+                    + app.man_page.h_scroll;
+                let mut spans = vec![Span::styled(
+                    rule.repeat(width),
+                    Style::default().fg(app.options.theme.muted),
+                )];
+                if focused && idx == app.man_page.cursor {
+                    spans[0].style = spans[0].style.bg(app.options.theme.cursor_bg);
+                }
+                return Spans::from(spans);
+            }
+            let max_width = app.options.max_line_width;
+            let role = app
+                .man_page
+                .roles
+                .get(idx)
+                .copied()
+                .unwrap_or(LineRole::Normal);
+            let in_example = role == LineRole::Example;
+            let in_table = wrap && role.is_preformatted();
+            let limit = if in_table && (max_width == 0 || max_width > table_width) {
+                table_width
+            } else {
+                max_width
+            };
+            let (line, clipped) = clip_line(full_line, limit);
+
+            let mut spans = if let Some(search_index) =
+                app.search.matches.iter().position(|&i| i == idx)
+            {
+                let highlight = search_index == app.search.current_match;
+
+                let mut spans = Vec::new();
+                let mut last = 0;
+
+                for (range, term) in app.search.parsed.highlights(line) {
+                    let theme = &app.options.theme;
+                    let (palette, fg) = if highlight {
+                        (&theme.current_match, theme.current_match_fg)
+                    } else {
+                        (&theme.other_match, theme.other_match_fg)
+                    };
+
+                    spans.push(Span::raw(&line[last..range.start]));
+                    spans.push(Span::styled(
+                        &line[range.clone()],
+                        Style::default().bg(palette[term % palette.len()]).fg(fg),
+                    ));
+                    last = range.end;
+                }
+                spans.push(Span::raw(&line[last..]));
+                spans
+            } else if in_example {
+                // Highlighting rejoins words with single spaces, losing the layout
+                vec![Span::raw(keep_indent(line, wrap))]
+            } else if role == LineRole::Heading {
+                let style = Style::default()
+                    .fg(app.options.theme.heading)
+                    .add_modifier(Modifier::BOLD);
+                vec![Span::styled(line, style)]
+            } else if matches!(app.page_source, PageSource::Tldr) && tldr_template(line).is_some() {
+                shell_highlight(line, &app.options.theme)
+            } else {
+                // Apply syntax highlighting
+                syntax_highlight(line, role, &app.options.theme)
+            };
+
+            if in_example {
+                let shade = Style::default().bg(app.options.theme.example_bg);
+                for span in &mut spans {
+                    span.style = shade.patch(span.style);
+                }
+            }
+            if clipped {
+                spans.push(Span::styled(
+                    marker,
+                    Style::default().fg(app.options.theme.muted),
+                ));
+            }
+            if focused && idx == app.man_page.cursor {
+                for span in &mut spans {
+                    span.style = Style::default()
+                        .bg(app.options.theme.cursor_bg)
+                        .patch(span.style);
+                }
+            }
+            Spans::from(spans)
+        })
+        .collect();
+
+    let lines_in_view = if wrap {
+        let width = (area.width as usize).saturating_sub(frame + gutter_width);
+        let marker_width = if app.options.wrap_markers {
+            WRAP_MARKER.chars().count()
+        } else {
+            0
+        };
+        let mut rows = 0;
+        visible_content
+            .iter()
+            .take_while(|spans| {
+                let fits = rows < app.man_page.visible_height;
+                rows += wrap_spans(spans, width, marker_width).len();
+                fits
+            })
+            .count()
+    } else {
+        app.man_page.visible_height
+    };
+
+    let gutter: Vec<Span> = (start_line..end_line)
+        .map(|idx| {
+            let label = gutter_label(app.line_numbers, idx, app.man_page.cursor);
+            let width = gutter_width.saturating_sub(1);
+            // Wrapping trims leading spaces, so wrapped numbers align left
+            let text = if wrap {
+                format!("{label:<width$} ")
+            } else {
+                format!("{label:>width$} ")
+            };
+            Span::styled(text, Style::default().fg(app.options.theme.muted))
+        })
+        .collect();
+
+    let title = content_title(app);
+    let block = pane_block(&title, focused, &app.options.theme);
+    let inner = pad_rect(block.inner(area), padding);
+    f.render_widget(block, area);
+    if wrap || gutter_width == 0 {
+        // A wrapped line numbers only its first row, so the gutter joins the text
+        let visible_content: Vec<Spans> = if wrap && app.options.wrap_markers {
+            let wrap_marker = if app.options.ascii {
+                ASCII_WRAP_MARKER
+            } else {
+                WRAP_MARKER
+            };
+            let width = (area.width as usize).saturating_sub(frame + gutter_width);
+            let marker_style = Style::default().fg(app.options.theme.muted);
+            visible_content
+                .iter()
+                .zip(gutter)
+                .flat_map(|(spans, label)| {
+                    let rows = wrap_spans(spans, width, wrap_marker.chars().count());
+                    rows.into_iter().enumerate().map(move |(row, mut spans)| {
+                        if row > 0 {
+                            spans.insert(0, Span::styled(wrap_marker, marker_style));
+                            spans.insert(0, Span::raw(" ".repeat(gutter_width)));
+                        } else if gutter_width > 0 {
+                            spans.insert(0, label.clone());
+                        }
+                        Spans::from(spans)
+                    })
+                })
+                .collect()
+        } else {
+            visible_content
+                .into_iter()
+                .zip(gutter)
+                .map(|(mut spans, label)| {
+                    if gutter_width > 0 {
+                        spans.0.insert(0, label);
+                    }
+                    spans
+                })
+                .collect()
+        };
+        let mut paragraph = Paragraph::new(visible_content);
+        if wrap {
+            // With markers on, the lines were wrapped to fit already
+            if !app.options.wrap_markers {
+                paragraph = paragraph.wrap(Wrap { trim: true });
+            }
+        } else {
+            paragraph = paragraph.scroll((0, app.man_page.h_scroll as u16));
+        }
+        f.render_widget(paragraph, inner);
+    } else {
+        // Kept apart so horizontal scrolling leaves the numbers in place
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(gutter_width as u16), Constraint::Min(0)].as_ref())
+            .split(inner);
+        let numbers: Vec<Spans> = gutter.into_iter().map(Spans::from).collect();
+        f.render_widget(Paragraph::new(numbers), columns[0]);
+        let paragraph = Paragraph::new(visible_content).scroll((0, app.man_page.h_scroll as u16));
+        f.render_widget(paragraph, columns[1]);
+    }
+    app.man_page.lines_in_view = lines_in_view;
+
+    if app.show_peek && !app.search.matches.is_empty() {
+        render_peek(f, app, area);
+    }
+}
+
+/// Line with its indentation made of no-break spaces when wrapping, which
+/// trimmed wrapping leaves in place
+fn keep_indent(line: &str, wrap: bool) -> Cow<'_, str> {
+    let text = line.trim_start_matches(' ');
+    if !wrap || text.len() == line.len() {
+        return Cow::Borrowed(line);
+    }
+    Cow::Owned("\u{a0}".repeat(line.len() - text.len()) + text)
+}
+
+/// Splits a line into rows at most width chars wide, breaking after the last
+/// space that fits; rows after the first leave room for a marker_width marker
+fn wrap_spans(spans: &Spans, width: usize, marker_width: usize) -> Vec<Vec<Span<'static>>> {
+    let chars: Vec<(char, Style)> = spans
+        .0
+        .iter()
+        .flat_map(|span| span.content.chars().map(move |c| (c, span.style)))
+        .collect();
+    let mut rows = Vec::new();
+    let mut start = 0;
+    loop {
+        let room = if rows.is_empty() {
+            width
+        } else {
+            width.saturating_sub(marker_width)
+        }
+        .max(1);
+        if chars.len() - start <= room {
+            rows.push(styled_row(&chars[start..]));
+            return rows;
+        }
+        let end = start + room;
+        // A single word wider than the row is broken mid-word
+        let split = chars[start..end]
+            .iter()
+            .rposition(|&(c, _)| c == ' ')
+            .map_or(end, |idx| start + idx + 1);
+        rows.push(styled_row(&chars[start..split]));
+        start = split;
+        // Like trimmed wrapping, continuation rows don't start with spaces
+        while chars.get(start).is_some_and(|&(c, _)| c == ' ') {
+            start += 1;
+        }
+        if start == chars.len() {
+            return rows;
+        }
+    }
+}
+
+/// Joins styled chars back into spans, one per run of equal style
+fn styled_row(chars: &[(char, Style)]) -> Vec<Span<'static>> {
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    for &(c, style) in chars {
+        match spans.last_mut() {
+            Some(span) if span.style == style => span.content.to_mut().push(c),
+            _ => spans.push(Span::styled(c.to_string(), style)),
+        }
+    }
+    spans
+}
+
+/// Gutter number for content line idx
+fn gutter_label(mode: LineNumbers, idx: usize, cursor: usize) -> String {
+    match mode {
+        LineNumbers::Off => String::new(),
+        LineNumbers::Relative if idx != cursor => idx.abs_diff(cursor).to_string(),
+        _ => (idx + 1).to_string(),
+    }
+}
+
+/// Draws a failed load with what went wrong and what to try next
+fn render_error_page<B: tui::backend::Backend>(f: &mut tui::Frame<B>, app: &AppState, area: Rect) {
+    let header = Style::default()
+        .fg(app.options.theme.error)
+        .add_modifier(Modifier::BOLD);
+    let mut lines = vec![
+        Spans::from(Span::styled("Page unavailable", header)),
+        Spans::default(),
+    ];
+    lines.extend(
+        app.man_page
+            .content
+            .iter()
+            .filter(|line| !line.is_empty())
+            .map(|line| Spans::from(Span::raw(line.as_str()))),
+    );
+    lines.push(Spans::default());
+    lines.push(Spans::from(Span::styled(
+        error_hint(app),
+        Style::default().fg(app.options.theme.warning),
+    )));
+
+    let title = content_title(app);
+    let focused = matches!(app.focus, Focus::ManPage);
+    let paragraph = Paragraph::new(lines)
+        .block(pane_block(&title, focused, &app.options.theme))
+        .wrap(Wrap { trim: true });
+    f.render_widget(paragraph, area);
+}
+
+/// Suggests another source or section after a failed load
+fn error_hint(app: &AppState) -> String {
+    match app.page_source {
+        PageSource::Man => {
+            let sections = app
+                .man_page
+                .command
+                .as_deref()
+                .map(|cmd| app.provider.available_sections(cmd))
+                .unwrap_or_default();
+            match &app.man_page.section {
+                Some(section) if sections.len() > 1 => {
+                    format!("No man page in section {section}; press [ or ] to change section")
+                }
+                _ => "Press t to try the tldr page".to_string(),
+            }
+        }
+        PageSource::Tldr => "Press t to try the info page".to_string(),
+        PageSource::Info => "Press t to go back to the man page".to_string(),
+    }
+}
+
+/// Shrinks area by padding columns on the left and right
+fn pad_rect(area: Rect, padding: usize) -> Rect {
+    let padding = padding.min(area.width as usize / 2) as u16;
+    Rect::new(
+        area.x + padding,
+        area.y,
+        area.width - 2 * padding,
+        area.height,
+    )
+}
+
+/// Centers a column of at most max_width text cells (plus borders) in area
+fn column_rect(area: Rect, max_width: usize) -> Rect {
+    let width = (max_width + 2).min(u16::MAX as usize) as u16;
+    if max_width == 0 || width >= area.width {
+        return area;
+    }
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y,
+        width,
+        area.height,
+    )
+}
+
+/// Content pane title, naming the man page section and its alternatives
+fn content_title(app: &AppState) -> String {
+    let (Some(cmd), Some(section), PageSource::Man) = (
+        &app.man_page.command,
+        &app.man_page.section,
+        &app.page_source,
+    ) else {
+        return "Content".to_string();
+    };
+
+    let sections = app.provider.available_sections(cmd);
+    let title = if sections.len() > 1 {
+        format!("{cmd}({section}) [{}]", sections.join(" "))
+    } else {
+        format!("{cmd}({section})")
+    };
+    match &app.man_page.jumped_to {
+        Some(heading) if app.options.ascii => format!("{title} > {heading}"),
+        Some(heading) => format!("{title} › {heading}"),
+        None => title,
+    }
+}
+
+/// Lines shown in the peek popup for the match at line
+fn peek_range(line: usize, content_len: usize) -> Range<usize> {
+    line.saturating_sub(PEEK_CONTEXT)..(line + PEEK_CONTEXT + 1).min(content_len)
+}
+
+/// Draws the current match with its context in a popup near the match
+fn render_peek<B: tui::backend::Backend>(f: &mut tui::Frame<B>, app: &AppState, area: Rect) {
+    let line = app.search.matches[app.search.current_match];
+    let range = peek_range(line, app.man_page.content.len());
+    let height = (range.len() as u16 + 2).min(area.height);
+    let width = area.width.saturating_sub(4);
+
+    // Below the match row when it fits, otherwise above it
+    let row = area.y + 1 + line.saturating_sub(app.man_page.scroll) as u16;
+    let y = if row + 1 + height <= area.bottom() {
+        row + 1
+    } else {
+        row.saturating_sub(height)
+            .clamp(area.y, area.bottom().saturating_sub(height))
+    };
+    let popup = Rect::new(area.x + 2, y, width, height);
+
+    let lines: Vec<Spans> = range
+        .map(|idx| {
+            let text = app.man_page.content[idx].as_str();
+            if idx == line {
+                Spans::from(Span::styled(
+                    text,
+                    Style::default().add_modifier(Modifier::BOLD),
+                ))
+            } else {
+                Spans::from(Span::styled(
+                    text,
+                    Style::default().fg(app.options.theme.peek_fg),
+                ))
+            }
+        })
+        .collect();
+
+    let title = format!(
+        "Match {}/{}",
+        app.search.current_match + 1,
+        app.search.matches.len()
+    );
+    f.render_widget(Clear, popup);
+    f.render_widget(
+        Paragraph::new(lines).block(pane_block(&title, true, &app.options.theme)),
+        popup,
+    );
+}
+
+/// Cuts line to at most `max_width` chars for display, reporting whether it was cut
+fn clip_line(line: &str, max_width: usize) -> (&str, bool) {
+    if max_width == 0 {
+        return (line, false);
+    }
+    match line.char_indices().nth(max_width) {
+        Some((end, _)) => (&line[..end], true),
+        None => (line, false),
+    }
+}
+
+/// Bordered block, highlighted when its pane has focus
+fn pane_block<'a>(title: &'a str, focused: bool, theme: &Theme) -> Block<'a> {
+    let border_style = if focused {
+        Style::default()
+            .fg(theme.border_focused)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(title)
+}
+
+/// Basic syntax highlighting for man pages
+fn syntax_highlight<'a>(line: &'a str, role: LineRole, theme: &Theme) -> Vec<Span<'a>> {
+    let mut spans = Vec::new();
+    let mut words = line.split_whitespace();
+
+    if let Some(first) = words.next() {
+        // Highlight headings
+        if first.ends_with(':') {
+            spans.push(Span::styled(
+                first,
+                Style::default()
+                    .fg(theme.heading)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        // Highlight options
+        else if role == LineRole::Option {
+            spans.push(Span::styled(
+                first,
+                Style::default()
+                    .fg(theme.option)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        } else {
+            push_word(&mut spans, first, theme);
+        }
+
+        for word in words {
+            spans.push(Span::raw(" "));
+
+            if links::is_url(word) {
+                spans.push(Span::styled(
+                    word,
+                    Style::default()
+                        .fg(theme.url)
+                        .add_modifier(Modifier::UNDERLINED),
+                ));
+            } else if word.starts_with('-') {
+                spans.push(Span::styled(word, Style::default().fg(theme.option)));
+            } else if word.starts_with('[') && word.ends_with(']') {
+                spans.push(Span::styled(word, Style::default().fg(theme.optional)));
+            } else if word.starts_with('<') && word.ends_with('>') {
+                spans.push(Span::styled(word, Style::default().fg(theme.argument)));
+            } else {
+                push_word(&mut spans, word, theme);
+            }
+        }
+    } else {
+        spans.push(Span::raw(line));
+    }
+
+    spans
+}
+
+/// Adds a word of prose, coloring a variable or path it starts with
+fn push_word<'a>(spans: &mut Vec<Span<'a>>, word: &'a str, theme: &Theme) {
+    let (len, color) = if let Some(len) = path_len(word) {
+        (len, theme.path)
+    } else if let Some(len) = variable_len(word, false) {
+        (len, theme.variable)
+    } else {
+        spans.push(Span::raw(word));
+        return;
+    };
+    spans.push(Span::styled(&word[..len], Style::default().fg(color)));
+    if len < word.len() {
+        spans.push(Span::raw(&word[len..]));
+    }
+}
+
+/// Length of the variable like `$HOME` or `${HOME}` starting text; parameters
+/// like `$1` and `$@` only count in shell commands, as prose uses `$1` for money
+fn variable_len(text: &str, shell: bool) -> Option<usize> {
+    let rest = text.strip_prefix('$')?;
+    if let Some(braced) = rest.strip_prefix('{') {
+        let end = braced.find('}')?;
+        return (end > 0).then_some(end + 3);
+    }
+    let name = rest
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(rest.len());
+    match rest.chars().next()? {
+        c if c.is_ascii_alphabetic() || c == '_' => Some(1 + name),
+        c if shell && (c.is_ascii_digit() || "@#?*$!".contains(c)) => Some(2),
+        _ => None,
+    }
+}
+
+/// Length of the absolute, `~/` or `$VAR/` path starting text, short of
+/// punctuation ending a sentence; a lone `/` or a `//` comment isn't one
+fn path_len(text: &str) -> Option<usize> {
+    let body = if let Some(body) = text.strip_prefix("~/") {
+        body
+    } else if let Some(var) = variable_len(text, false) {
+        text[var..].strip_prefix('/')?
+    } else {
+        text.strip_prefix('/')
+            .filter(|body| body.starts_with(|c: char| c.is_alphanumeric() || "._".contains(c)))?
+    };
+    let trailing = body.len()
+        - body
+            .trim_end_matches(['.', ',', ';', ':', ')', '\'', '"'])
+            .len();
+    Some(text.len() - trailing)
+}
+
+/// Shell operators, longest first so `>>` wins over `>`
+const SHELL_OPERATORS: [&str; 10] = ["2>&1", "&&", "||", ">>", "2>", "|", ";", ">", "<", "&"];
+
+/// Highlights a tldr example as a shell command line
+fn shell_highlight<'a>(line: &'a str, theme: &Theme) -> Vec<Span<'a>> {
+    let mut spans = Vec::new();
+    let mut rest = line;
+    // The next word names a program: at the start and after a pipe or separator
+    let mut expect_command = true;
+
+    while let Some(c) = rest.chars().next() {
+        let (len, style) = if c.is_whitespace() {
+            (rest.len() - rest.trim_start().len(), Style::default())
+        } else if rest.starts_with("{{") {
+            let len = rest.find("}}").map_or(rest.len(), |end| end + 2);
+            expect_command = false;
+            (len, Style::default().fg(theme.placeholder))
+        } else if c == '\'' || c == '"' {
+            let len = rest[1..].find(c).map_or(rest.len(), |end| end + 2);
+            expect_command = false;
+            (len, Style::default().fg(theme.string))
+        } else if let Some(op) = SHELL_OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            // Redirections are followed by a file, everything else by a command
+            expect_command = !op.contains(['>', '<']);
+            (op.len(), Style::default().fg(theme.operator))
+        } else {
+            let len = rest
+                .find(|c: char| c.is_whitespace() || "'\"|;&<>".contains(c))
+                .unwrap_or(rest.len());
+            let mut len = rest[..len].find("{{").unwrap_or(len).max(c.len_utf8());
+            let path = path_len(&rest[..len]);
+            let style = if c == '$'
+                && let Some(path) = path
+            {
+                len = path;
+                Style::default().fg(theme.path)
+            } else if let Some(var) = variable_len(&rest[..len], true) {
+                len = var;
+                Style::default().fg(theme.variable)
+            } else if expect_command {
+                Style::default()
+                    .fg(theme.command)
+                    .add_modifier(Modifier::BOLD)
+            } else if c == '-' {
+                Style::default().fg(theme.option)
+            } else if let Some(path) = path {
+                len = path;
+                Style::default().fg(theme.path)
+            } else {
+                Style::default()
+            };
+            expect_command = false;
+            (len, style)
+        };
+
+        let (token, tail) = rest.split_at(len);
+        spans.push(Span::styled(token, style));
+        rest = tail;
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tui_tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::provider::{DoneFuture, LineFuture, PageFuture};
+    use crate::trie::Trie;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Provider serving a fixed index and canned pages
+    struct MockProvider {
+        commands: Vec<String>,
+        truncated: bool,
+    }
+
+    impl PageProvider for MockProvider {
+        fn commands(&self) -> &Vec<String> {
+            &self.commands
+        }
+
+        fn complete_prefix(&self, prefix: &str) -> String {
+            let mut trie = Trie::new();
+            for cmd in &self.commands {
+                trie.insert(cmd);
+            }
+            trie.longest_common_prefix(prefix)
+        }
+
+        fn description(&self, command: &str) -> Option<String> {
+            match command {
+                "grep" => Some("print lines that match patterns".to_string()),
+                "ls" => Some("list directory contents".to_string()),
+                _ => Some(String::new()),
+            }
+        }
+
+        fn section(&self, command: &str) -> Option<&str> {
+            match command {
+                "gitk" => Some("10"),
+                "ls" => Some("8"),
+                _ => Some("1"),
+            }
+        }
+
+        fn available_sections(&self, command: &str) -> Vec<String> {
+            match command {
+                "git" => vec!["1".to_string(), "3".to_string(), "7".to_string()],
+                _ => vec!["1".to_string()],
+            }
+        }
+
+        fn man_page_in<'a>(&'a self, command: &'a str, section: &'a str) -> PageFuture<'a> {
+            Box::pin(async move { LoadedPage::new(vec![format!("{command}({section})")]) })
+        }
+
+        fn man_page<'a>(&'a self, command: &'a str) -> PageFuture<'a> {
+            let lines = (0..50).map(|i| format!("{command} man line {i}")).collect();
+            Box::pin(async move { LoadedPage::new(lines) })
+        }
+
+        fn tldr_page<'a>(&'a self, command: &'a str) -> PageFuture<'a> {
+            Box::pin(async move { LoadedPage::new(vec![format!("{command} tldr")]) })
+        }
+
+        fn info_page<'a>(&'a self, command: &'a str) -> PageFuture<'a> {
+            Box::pin(async move { LoadedPage::new(vec![format!("{command} info")]) })
+        }
+
+        fn name_line<'a>(&'a self, _command: &'a str) -> LineFuture<'a> {
+            Box::pin(async { None })
+        }
+
+        fn cached_name_line(&self, command: &str) -> Option<String> {
+            (command == "ls").then(|| "list directory contents, from NAME".to_string())
+        }
+
+        fn clear_caches(&self) -> DoneFuture<'_> {
+            Box::pin(async {})
+        }
+
+        fn tldr_only(&self) -> bool {
+            false
+        }
+
+        fn truncated(&self) -> bool {
+            self.truncated
+        }
+    }
+
+    fn mock_app() -> AppState {
+        mock_app_with(TuiOptions::default())
+    }
+
+    fn mock_app_with(options: TuiOptions) -> AppState {
+        let provider = MockProvider {
+            commands: ["git", "gitk", "grep", "ls"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            truncated: false,
+        };
+        // Each app gets its own directory so tests writing files can run in parallel
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("rtfm-tui-{}-{id}", std::process::id()));
+        let paths = Paths {
+            config_dir: dir.join("config"),
+            cache_dir: dir.join("cache"),
+        };
+        let mut app = AppState::new(Arc::new(provider), paths, options);
+        app.command_list.visible_range = (0, LIST_SIZE);
+        app.man_page.visible_height = 10;
+        app
+    }
+
+    /// Runs a future to completion on a runtime shared by every test
+    fn block_on<F: Future>(future: F) -> F::Output {
+        static RUNTIME: LazyLock<tokio::runtime::Runtime> =
+            LazyLock::new(|| tokio::runtime::Runtime::new().unwrap());
+        RUNTIME.block_on(future)
+    }
+
+    fn press(app: &mut AppState, code: KeyCode) {
+        block_on(handle_key(app, KeyEvent::new(code, KeyModifiers::NONE)));
+    }
+
+    /// Symbols of every cell in the terminal's buffer, row after row
+    fn buffer_text(terminal: &Terminal<tui::backend::TestBackend>) -> String {
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol.as_str())
+            .collect()
+    }
+
+    /// Symbols of the cells in columns of row y
+    fn buffer_row(
+        terminal: &Terminal<tui::backend::TestBackend>,
+        y: u16,
+        columns: Range<u16>,
+    ) -> String {
+        columns
+            .map(|x| terminal.backend().buffer().get(x, y).symbol.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_typing_filters_commands() {
+        let mut app = mock_app();
+        assert_eq!(app.command_list.filtered_commands.len(), 4);
+
+        press(&mut app, KeyCode::Char('g'));
+        press(&mut app, KeyCode::Char('i'));
+        assert_eq!(*app.command_list.filtered_commands, vec!["git", "gitk"]);
+        assert!(app.pending_man_load);
+
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(app.command_list.filtered_commands.len(), 3);
+    }
+
+    #[test]
+    fn test_preview_limit_drops_previous_page() {
+        let mut app = mock_app_with(TuiOptions {
+            max_preview_matches: Some(1),
+            ..TuiOptions::default()
+        });
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Enter);
+        app.search.query = "line".to_string();
+        update_search_matches(&mut app);
+        assert!(!app.search.matches.is_empty());
+
+        press(&mut app, KeyCode::Char('i'));
+        assert_eq!(
+            app.man_page.content[0],
+            "2 matching commands, keep typing or press Enter to load"
+        );
+        assert_eq!(app.man_page.raw_content, app.man_page.content);
+        assert!(app.man_page.command.is_none());
+        assert!(app.search.matches.is_empty());
+    }
+
+    #[test]
+    fn test_empty_filter_drops_previous_page() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Enter);
+        assert!(app.man_page.command.is_some());
+
+        press(&mut app, KeyCode::Char('z'));
+        press(&mut app, KeyCode::Char('z'));
+        assert!(app.command_list.filtered_commands.is_empty());
+        block_on(load_current_page(&mut app));
+        assert_eq!(*app.man_page.content, vec!["No commands found"]);
+        assert_eq!(app.man_page.raw_content, app.man_page.content);
+        assert!(app.man_page.command.is_none());
+    }
+
+    #[test]
+    fn test_unbound_ctrl_keys_are_not_typed() {
+        let mut app = mock_app();
+        let ctrl_a = KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL);
+        block_on(handle_key(&mut app, ctrl_a));
+        assert!(app.command_list.input.is_empty());
+
+        app.focus = Focus::Search;
+        block_on(handle_key(&mut app, ctrl_a));
+        assert!(app.search.query.is_empty());
+    }
+
+    #[test]
+    fn test_tab_completes_filter() {
+        let mut app = mock_app();
+        assert!(!complete_filter(&mut app));
+
+        press(&mut app, KeyCode::Char('g'));
+        press(&mut app, KeyCode::Char('i'));
+        assert!(complete_filter(&mut app));
+        assert_eq!(app.command_list.input, "git");
+        assert_eq!(*app.command_list.filtered_commands, vec!["git", "gitk"]);
+        assert!(!complete_filter(&mut app));
+    }
+
+    #[test]
+    fn test_start_in_content() {
+        let app = mock_app_with(TuiOptions {
+            initial_query: Some("git".to_string()),
+            focus: StartFocus::Content,
+            ..TuiOptions::default()
+        });
+        assert!(matches!(app.focus, Focus::ManPage));
+        assert_eq!(app.command_list.selected_idx, 0);
+        assert!(app.pending_man_load);
+        assert_eq!(app.load_delay, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_initial_query_prefilters() {
+        let app = mock_app_with(TuiOptions {
+            initial_query: Some("gr".to_string()),
+            ..TuiOptions::default()
+        });
+        assert_eq!(app.command_list.input, "gr");
+        assert_eq!(*app.command_list.filtered_commands, vec!["grep"]);
+    }
+
+    #[test]
+    fn test_description_filter_mode() {
+        let mut app = mock_app();
+        app.filter_mode = FilterMode::Description;
+        app.command_list.input = "directory".to_string();
+        filter_commands(&mut app);
+        assert_eq!(*app.command_list.filtered_commands, vec!["ls"]);
+    }
+
+    #[test]
+    fn test_cycle_sort_keeps_selection() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::Down);
+        let ctrl_r = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        block_on(handle_key(&mut app, ctrl_r));
+
+        assert_eq!(app.sort_mode, SortMode::Descending);
+        assert_eq!(
+            *app.command_list.filtered_commands,
+            vec!["ls", "grep", "gitk", "git"]
+        );
+        assert_eq!(app.command_list.selected_idx, 2);
+
+        press(&mut app, KeyCode::Char('g'));
+        assert_eq!(
+            *app.command_list.filtered_commands,
+            vec!["grep", "gitk", "git"]
+        );
+    }
+
+    #[test]
+    fn test_section_sort_uses_manual_order() {
+        let mut app = mock_app();
+        app.sort_mode = SortMode::Section;
+        sort_commands(&mut app);
+        assert_eq!(
+            *app.command_list.filtered_commands,
+            vec!["git", "grep", "ls", "gitk"]
+        );
+    }
+
+    #[test]
+    fn test_marked_pages_load_together() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::Char(' '));
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char(' '));
+        assert_eq!(app.command_list.selected_idx, 3);
+        assert_eq!(list_label(&app, "git"), "✓ git");
+        assert_eq!(list_label(&app, "gitk"), "  gitk");
+
+        press(&mut app, KeyCode::Enter);
+        assert!(app.loading);
+        assert!(app.command_list.marked.is_empty());
+        block_on(show_marked_pages(&mut app));
+        let content = &app.man_page.content;
+        assert_eq!(content[0], "──── git ────");
+        assert_eq!(content[1], "git man line 0");
+        assert!(content.contains(&"──── grep ────".to_string()));
+        assert_eq!(app.man_page.command, None);
+
+        press(&mut app, KeyCode::Enter);
+        assert!(app.pending_batch.is_none());
+        assert_eq!(app.man_page.command.as_deref(), Some("ls"));
+    }
+
+    #[test]
+    fn test_description_source_toggle() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::End);
+        assert_eq!(selected_description(&app), "list directory contents");
+
+        let ctrl_n = KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL);
+        block_on(handle_key(&mut app, ctrl_n));
+        assert_eq!(app.description_source, DescriptionSource::Name);
+        assert_eq!(
+            selected_description(&app),
+            "list directory contents, from NAME"
+        );
+
+        // Commands without a NAME line keep their apropos text
+        app.command_list.selected_idx = 2;
+        assert_eq!(
+            selected_description(&app),
+            "print lines that match patterns"
+        );
+
+        let config = Config::load(&app.paths.config_dir.join(CONFIG_FILE)).unwrap();
+        assert_eq!(config.description_source, Some(DescriptionSource::Name));
+        std::fs::remove_dir_all(app.paths.config_dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_navigation_and_enter_loads_page() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.command_list.selected_idx, 2);
+
+        press(&mut app, KeyCode::End);
+        assert_eq!(app.command_list.selected_idx, 3);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.command_list.selected_idx, 3);
+
+        press(&mut app, KeyCode::Enter);
+        assert!(!app.pending_man_load);
+        assert_eq!(app.man_page.content[0], "ls man line 0");
+    }
+
+    #[test]
+    fn test_search_moves_between_matches() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Enter);
+
+        app.focus = Focus::ManPage;
+        press(&mut app, KeyCode::Char('f'));
+        assert!(matches!(app.focus, Focus::Search));
+
+        for c in "line 4".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.focus, Focus::ManPage));
+        assert_eq!(app.search.matches.len(), 14);
+        assert_eq!(app.man_page.scroll, 0);
+
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.search.current_match, 1);
+        assert_eq!(app.man_page.scroll, 9);
+        assert!(app.notice.is_none());
+
+        press(&mut app, KeyCode::Char('N'));
+        press(&mut app, KeyCode::Char('N'));
+        assert_eq!(app.search.current_match, 13);
+        assert_eq!(app.man_page.scroll, 40);
+        assert_eq!(
+            app.notice.as_deref(),
+            Some("search hit TOP, continuing at BOTTOM")
+        );
+
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.search.current_match, 0);
+        assert_eq!(
+            app.notice.as_deref(),
+            Some("search hit BOTTOM, continuing at TOP")
+        );
+    }
+
+    #[test]
+    fn test_search_in_place_jumps_on_enter() {
+        let mut app = mock_app_with(TuiOptions {
+            jump_while_searching: false,
+            ..TuiOptions::default()
+        });
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Enter);
+        app.focus = Focus::ManPage;
+        app.man_page.scroll = 25;
+        app.man_page.cursor = 25;
+
+        press(&mut app, KeyCode::Char('f'));
+        for c in "line 4".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert!(!app.search.matches.is_empty());
+        assert_eq!(app.man_page.scroll, 25);
+        assert!(app.search.matches[app.search.current_match] >= 25);
+
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(
+            app.man_page.cursor,
+            app.search.matches[app.search.current_match]
+        );
+        assert_ne!(app.man_page.scroll, 25);
+    }
+
+    #[test]
+    fn test_match_position() {
+        let mut app = mock_app();
+        app.focus = Focus::ManPage;
+        app.man_page.visible_height = 10;
+        app.options.scroll_off = 0;
+        app.man_page.content = Arc::new((0..100).map(|i| format!("row {i}")).collect());
+
+        for _ in 0..6 {
+            press(&mut app, KeyCode::Char('{'));
+        }
+        assert_eq!(app.options.match_position, 0);
+        reveal_match(&mut app, 40);
+        assert_eq!(app.man_page.scroll, 40);
+
+        press(&mut app, KeyCode::Char('}'));
+        reveal_match(&mut app, 70);
+        assert_eq!(app.man_page.scroll, 69);
+        reveal_match(&mut app, 98);
+        assert_eq!(app.man_page.scroll, 90);
+    }
+
+    #[test]
+    fn test_search_history_recall() {
+        let mut app = mock_app();
+        for query in ["line", "line", "4"] {
+            start_search(&mut app);
+            for c in query.chars() {
+                press(&mut app, KeyCode::Char(c));
+            }
+            press(&mut app, KeyCode::Enter);
+        }
+        assert_eq!(app.search.history, vec!["line", "4"]);
+
+        start_search(&mut app);
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.search.query, "4");
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.search.query, "line");
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.search.query, "4");
+        press(&mut app, KeyCode::Down);
+        assert!(app.search.query.is_empty());
+    }
+
+    #[test]
+    fn test_paste_into_inputs() {
+        let mut app = mock_app();
+        handle_paste(&mut app, "gi\r\n");
+        assert_eq!(app.command_list.input, "gi");
+        assert_eq!(app.command_list.filtered_commands.len(), 2);
+
+        app.focus = Focus::Search;
+        handle_paste(&mut app, "line\n4");
+        assert_eq!(app.search.query, "line 4");
+
+        app.focus = Focus::Notes;
+        handle_paste(&mut app, "a\r\nb");
+        assert_eq!(app.note_draft, "a\nb");
+    }
+
+    #[test]
+    fn test_description_names_section() {
+        let mut app = mock_app();
+        assert_eq!(description_subject(&app), "Description of git(1)");
+        for c in "zzz".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(description_subject(&app), "Description");
+    }
+
+    #[test]
+    fn test_compact_blank_lines() {
+        let lines: Vec<String> = ["", "A", "", "", "B", "  ", "C", "", ""]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(compact_line_indices(&lines), vec![1, 2, 4, 5, 6]);
+
+        let mut app = mock_app();
+        show_content(&mut app, LoadedPage::new(lines));
+        app.man_page.cursor = 6;
+        app.man_page.scroll = 4;
+        toggle_compact_blank_lines(&mut app);
+        assert_eq!(app.man_page.content.len(), 5);
+        assert_eq!(app.man_page.content[app.man_page.cursor], "C");
+        assert_eq!(app.man_page.scroll, 2);
+
+        toggle_compact_blank_lines(&mut app);
+        assert_eq!(app.man_page.content.len(), 9);
+        assert_eq!(app.man_page.cursor, 6);
+    }
+
+    #[test]
+    fn test_idle_reset() {
+        let mut app = mock_app_with(TuiOptions {
+            idle_reset: Some(Duration::from_secs(60)),
+            ..TuiOptions::default()
+        });
+        assert!(!is_idle(&app));
+
+        app.last_key_time = Some(Instant::now());
+        assert!(!is_idle(&app));
+        app.last_key_time = Instant::now().checked_sub(Duration::from_secs(61));
+        assert!(is_idle(&app));
+        app.focus = Focus::Notes;
+        assert!(!is_idle(&app));
+        app.focus = Focus::ManPage;
+
+        app.options.idle_reset = None;
+        assert!(!is_idle(&app));
+    }
+
+    #[test]
+    fn test_selection_clamped_when_list_shrinks() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::End);
+        assert_eq!(app.command_list.selected_idx, 3);
+        for c in "gitk".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(app.command_list.filtered_commands.len(), 1);
+        assert_eq!(app.command_list.selected_idx, 0);
+
+        // A list shrunk behind the selection's back
+        app.command_list.selected_idx = 3;
+        app.command_list.list_scroll = 3;
+        block_on(load_current_page(&mut app));
+        assert_eq!(app.command_list.selected_idx, 0);
+        assert_eq!(app.command_list.list_scroll, 0);
+        assert_eq!(app.man_page.command.as_deref(), Some("gitk"));
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.command_list.selected_idx, 0);
+    }
+
+    #[test]
+    fn test_list_label_highlights_matches() {
+        let mut app = mock_app();
+        app.command_list.input = "it".to_string();
+        let spans = list_label_spans(&app, "gitk", 0).0;
+        let texts: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(texts, vec!["  g", "it", "k"]);
+        assert_eq!(spans[1].style.fg, Some(app.options.theme.list_match));
+
+        app.options.highlight_matches = false;
+        assert_eq!(list_label_spans(&app, "gitk", 0).0.len(), 1);
+    }
+
+    #[test]
+    fn test_source_toggle_keeps_place() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::Char('g'));
+        press(&mut app, KeyCode::Char('i'));
+        press(&mut app, KeyCode::Down);
+        block_on(load_pending_page(&mut app));
+        assert_eq!(app.man_page.command.as_deref(), Some("gitk"));
+
+        // Following a reference opens a page other than the selected one
+        block_on(open_man_page(
+            &mut app,
+            "git".to_string(),
+            Some("7".to_string()),
+        ));
+        switch_page_source(&mut app);
+        block_on(load_pending_page(&mut app));
+        assert_eq!(app.man_page.content[0], "git tldr");
+
+        switch_page_source(&mut app);
+        switch_page_source(&mut app);
+        block_on(load_pending_page(&mut app));
+        assert_eq!(app.man_page.content[0], "git(7)");
+
+        block_on(clear_caches(&mut app));
+        block_on(load_pending_page(&mut app));
+        assert_eq!(app.man_page.content[0], "git(7)");
+        assert_eq!(app.command_list.input, "gi");
+        assert_eq!(app.command_list.selected_idx, 1);
+    }
+
+    #[test]
+    fn test_section_picker() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.focus, Focus::Sections));
+        assert_eq!(app.sections.sections, vec!["1", "3", "7"]);
+        assert_eq!(app.sections.selected, 0);
+
+        press(&mut app, KeyCode::Char('7'));
+        assert_eq!(app.sections.selected, 2);
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.focus, Focus::CommandList));
+        assert_eq!(app.man_page.content[0], "git(3)");
+        assert_eq!(app.man_page.section.as_deref(), Some("3"));
+
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Esc);
+        assert!(matches!(app.focus, Focus::CommandList));
+        assert_eq!(app.man_page.content[0], "git(3)");
+
+        // Commands in a single section open straight away
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.focus, Focus::CommandList));
+        assert_eq!(app.man_page.command.as_deref(), Some("gitk"));
+    }
+
+    /// Waits for the early exit check of a started terminal to finish
+    fn finish_launch(app: &mut AppState) {
+        while app.launch_check.is_some() {
+            std::thread::sleep(Duration::from_millis(10));
+            check_launch(app);
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_open_terminal() {
+        assert_eq!(
+            launch_line("alacritty -e {cmd}", "it's"),
+            r"alacritty -e 'it'\''s'"
+        );
+
+        let mut app = mock_app_with(TuiOptions {
+            launcher: Some("true {cmd}".to_string()),
+            ..TuiOptions::default()
+        });
+        open_terminal(&mut app);
+        assert_eq!(app.notice.as_deref(), Some("Started git in a new terminal"));
+        finish_launch(&mut app);
+        assert_eq!(app.notice.as_deref(), Some("Started git in a new terminal"));
+
+        app.options.launcher = Some("false {cmd}".to_string());
+        open_terminal(&mut app);
+        finish_launch(&mut app);
+        assert_eq!(
+            app.notice.as_deref(),
+            Some("Failed to run `false 'git'`: exited with exit status: 1")
+        );
+        app.options.launcher = Some("rtfm-no-such-terminal -e {cmd}".to_string());
+        open_terminal(&mut app);
+        finish_launch(&mut app);
+        assert_eq!(
+            app.notice.as_deref(),
+            Some("Failed to run `rtfm-no-such-terminal -e 'git'`: program not found on PATH")
+        );
+        app.options.launcher = Some("true {cmd}".to_string());
+
+        app.focus = Focus::ManPage;
+        open_terminal(&mut app);
+        assert_eq!(app.notice.as_deref(), Some("No command selected"));
+    }
+
+    #[test]
+    fn test_on_enter() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Enter);
+        assert!(app.pending_enter.is_none());
+        assert_eq!(app.man_page.command.as_deref(), Some("git"));
+
+        for action in [OnEnter::Pager, OnEnter::Print, OnEnter::Copy] {
+            let mut app = mock_app_with(TuiOptions {
+                on_enter: action,
+                ..TuiOptions::default()
+            });
+            press(&mut app, KeyCode::Down);
+            press(&mut app, KeyCode::Enter);
+            assert_eq!(app.pending_enter, Some((action, "gitk".to_string())));
+        }
+
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"ls"), "bHM=");
+        assert_eq!(base64(b"git"), "Z2l0");
+        assert_eq!(base64(b"grep"), "Z3JlcA==");
+    }
+
+    #[test]
+    fn test_copy_page() {
+        let mut app = mock_app();
+        block_on(load_current_page(&mut app));
+        app.focus = Focus::ManPage;
+        app.man_page.scroll = 45;
+
+        press(&mut app, KeyCode::Char('Y'));
+        let (text, label) = app.pending_copy.take().unwrap();
+        assert_eq!(label, "50 lines");
+        assert!(text.starts_with("git man line 0\ngit man line 1\n"));
+
+        press(&mut app, KeyCode::Char('y'));
+        let (text, label) = app.pending_copy.take().unwrap();
+        assert_eq!(label, "5 lines");
+        assert!(text.starts_with("git man line 45\n"));
+
+        let lines = [
+            "a\tb".to_string(),
+            PAGE_BREAK.to_string(),
+            "c\x1b".to_string(),
+        ];
+        assert_eq!(plain_text(&lines), "a\tb\n\nc");
+    }
+
+    #[test]
+    fn test_copy_view_counts_wrapped_rows() {
+        let mut app = mock_app_with(TuiOptions {
+            content_padding: 0,
+            ..TuiOptions::default()
+        });
+        app.page_source = PageSource::Tldr;
+        app.focus = Focus::ManPage;
+        let lines = (0..10).map(|i| format!("line {i} wraps onto a second row"));
+        app.man_page.content = Arc::new(lines.collect());
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(80, 20)).unwrap();
+        terminal
+            .draw(|f| render_man_page(f, &mut app, Rect::new(0, 0, 24, 6)))
+            .unwrap();
+
+        press(&mut app, KeyCode::Char('y'));
+        let (text, label) = app.pending_copy.take().unwrap();
+        assert_eq!(label, "2 lines");
+        assert!(text.ends_with("line 1 wraps onto a second row"));
+    }
+
+    #[test]
+    fn test_tldr_only_keeps_page_source() {
+        let mut app = mock_app();
+        app.tldr_only = true;
+        app.page_source = PageSource::Tldr;
+        toggle_page_source(&mut app);
+        assert!(matches!(app.page_source, PageSource::Tldr));
+        assert!(app.notice.as_deref().unwrap().contains("tldr"));
+
+        app.man_page.content = Arc::new(vec!["see gitk(1)".to_string()]);
+        block_on(follow_reference(&mut app));
+        assert!(matches!(app.page_source, PageSource::Tldr));
+        assert_eq!(*app.man_page.content, vec!["gitk tldr"]);
+
+        app.tldr_only = false;
+        toggle_page_source(&mut app);
+        assert!(matches!(app.page_source, PageSource::Info));
+    }
+
+    #[test]
+    fn test_jump_to_options_heading() {
+        let mut app = mock_app();
+        app.options.jump_to = JumpTo::Options;
+        app.man_page.visible_height = 10;
+        app.man_page.command = Some("ls".to_string());
+        app.man_page.section = Some("1".to_string());
+
+        let mut content: Vec<String> = (0..40).map(|i| format!("   line {i}")).collect();
+        content[5] = "   OPTIONS are listed below".to_string();
+        content[20] = "GLOBAL OPTIONS".to_string();
+        show_content(&mut app, LoadedPage::new(content));
+        assert_eq!(app.man_page.scroll, 20);
+        assert_eq!(app.man_page.cursor, 20);
+        assert!(content_title(&app).ends_with("› GLOBAL OPTIONS"));
+
+        show_content(
+            &mut app,
+            LoadedPage::new(vec!["NAME".to_string(), "   ls".to_string()]),
+        );
+        assert_eq!(app.man_page.scroll, 0);
+        assert_eq!(app.man_page.jumped_to, None);
+        assert_eq!(options_heading(&["FLAGS".to_string()]), Some(0));
+        assert_eq!(options_heading(&["Options".to_string()]), None);
+    }
+
+    #[test]
+    fn test_load_error_hints() {
+        let mut app = mock_app();
+        app.man_page.command = Some("git".to_string());
+        app.man_page.section = Some("3".to_string());
+        show_content(
+            &mut app,
+            LoadedPage {
+                lines: Arc::new(vec!["Failed to load man page: git".to_string()]),
+                failed: true,
+            },
+        );
+        assert!(app.man_page.is_error);
+        assert!(error_hint(&app).contains("section 3"));
+
+        app.man_page.command = Some("ls".to_string());
+        assert!(error_hint(&app).contains("tldr"));
+
+        show_content(&mut app, LoadedPage::new(vec!["ls(1)".to_string()]));
+        assert!(!app.man_page.is_error);
+        // Only the provider's flag marks a failure, not the text
+        let text = vec!["Failed to load the config, see FILES".to_string()];
+        show_content(&mut app, LoadedPage::new(text));
+        assert!(!app.man_page.is_error);
+    }
+
+    #[test]
+    fn test_jump_opens_command_and_goes_back() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Enter);
+        app.focus = Focus::ManPage;
+        press(&mut app, KeyCode::Char('g'));
+        assert!(matches!(app.focus, Focus::Jump));
+
+        for c in "gi".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(app.jump.matches, vec!["git", "gitk"]);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.focus, Focus::ManPage));
+        assert_eq!(app.man_page.command.as_deref(), Some("gitk"));
+        assert_eq!(app.command_list.selected_idx, 0);
+
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(app.man_page.command.as_deref(), Some("git"));
+    }
+
+    #[test]
+    fn test_page_break_renders_as_rule() {
+        let mut app = mock_app_with(TuiOptions {
+            content_padding: 0,
+            ..TuiOptions::default()
+        });
+        app.man_page.content = Arc::new(vec!["a".to_string(), PAGE_BREAK.to_string()]);
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(80, 20)).unwrap();
+        terminal
+            .draw(|f| render_man_page(f, &mut app, Rect::new(0, 0, 20, 5)))
+            .unwrap();
+
+        assert_eq!(buffer_row(&terminal, 2, 1..19), "─".repeat(18));
+    }
+
+    #[test]
+    fn test_content_padding() {
+        let mut app = mock_app_with(TuiOptions {
+            content_padding: 2,
+            ..TuiOptions::default()
+        });
+        app.man_page.content = Arc::new(vec!["abc".to_string(), PAGE_BREAK.to_string()]);
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(80, 20)).unwrap();
+        terminal
+            .draw(|f| render_man_page(f, &mut app, Rect::new(0, 0, 20, 5)))
+            .unwrap();
+        assert_eq!(
+            buffer_row(&terminal, 1, 1..19),
+            format!("  abc{}", " ".repeat(13))
+        );
+        assert_eq!(
+            buffer_row(&terminal, 2, 1..19),
+            format!("  {}  ", "─".repeat(14))
+        );
+    }
+
+    #[test]
+    fn test_wrap_markers() {
+        let text = |rows: Vec<Vec<Span>>| -> Vec<String> {
+            rows.iter()
+                .map(|row| row.iter().map(|span| span.content.as_ref()).collect())
+                .collect()
+        };
+        let line = Spans::from(vec![Span::raw("alpha beta "), Span::raw("gamma")]);
+        assert_eq!(text(wrap_spans(&line, 12, 2)), vec!["alpha beta ", "gamma"]);
+        let word = Spans::from("abcdefghij");
+        assert_eq!(text(wrap_spans(&word, 6, 2)), vec!["abcdef", "ghij"]);
+        assert_eq!(text(wrap_spans(&Spans::from(""), 6, 2)), vec![""]);
+
+        let mut app = mock_app_with(TuiOptions {
+            wrap_markers: true,
+            content_padding: 0,
+            ..TuiOptions::default()
+        });
+        app.page_source = PageSource::Tldr;
+        app.man_page.content = Arc::new(vec!["one two three four".to_string()]);
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(80, 20)).unwrap();
+        terminal
+            .draw(|f| render_man_page(f, &mut app, Rect::new(0, 0, 12, 5)))
+            .unwrap();
+        assert_eq!(buffer_row(&terminal, 1, 1..11), "one two   ");
+        assert_eq!(buffer_row(&terminal, 2, 1..11), "↪ three   ");
+    }
+
+    #[test]
+    fn test_examples_shaded() {
+        let mut app = mock_app();
+        let content = ["EXAMPLES", "       List:", "", "           $ ls -l", ""];
+        show_content(
+            &mut app,
+            LoadedPage::new(content.iter().map(|s| s.to_string()).collect()),
+        );
+        assert_eq!(app.man_page.roles[3], LineRole::Example);
+        assert_eq!(app.man_page.roles[0], LineRole::Heading);
+
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(80, 20)).unwrap();
+        terminal
+            .draw(|f| render_man_page(f, &mut app, Rect::new(0, 0, 30, 7)))
+            .unwrap();
+        let bg = |y| terminal.backend().buffer().get(12, y).bg;
+        assert_eq!(bg(4), app.options.theme.example_bg);
+        assert_ne!(bg(2), app.options.theme.example_bg);
+        assert_eq!(keep_indent("  $ ls", true), "\u{a0}\u{a0}$ ls");
+        assert_eq!(keep_indent("  $ ls", false), "  $ ls");
+
+        app.page_source = PageSource::Tldr;
+        show_content(
+            &mut app,
+            LoadedPage::new(content.iter().map(|s| s.to_string()).collect()),
+        );
+        assert!(!app.man_page.roles.contains(&LineRole::Example));
+    }
+
+    #[test]
+    fn test_indexing_splash() {
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(60, 10)).unwrap();
+        terminal
+            .draw(|f| render_indexing(f, &TuiOptions::default(), 0))
+            .unwrap();
+
+        let text = buffer_text(&terminal);
+        assert!(text.contains("Indexing man pages… (Ctrl+C to cancel)"));
+
+        terminal
+            .draw(|f| render_indexing(f, &TuiOptions::default(), 1234))
+            .unwrap();
+        let text = buffer_text(&terminal);
+        assert!(text.contains("Indexing man pages… 1234 entries (Ctrl+C to cancel)"));
+
+        assert!(is_cancel_key(KeyEvent::new(
+            KeyCode::Char('c'),
+            KeyModifiers::CONTROL
+        )));
+        assert!(!is_cancel_key(KeyEvent::new(
+            KeyCode::Char('c'),
+            KeyModifiers::NONE
+        )));
+    }
+
+    #[test]
+    fn test_shell_highlight() {
+        let theme = Theme::default();
+        let line = "      grep -r 'a b' {{path}} | sort > {{out.txt}}";
+        let spans = shell_highlight(line, &theme);
+        let colored: Vec<(&str, Option<tui::style::Color>)> = spans
+            .iter()
+            .filter(|s| !s.content.trim().is_empty())
+            .map(|s| (s.content.as_ref(), s.style.fg))
+            .collect();
+        assert_eq!(
+            colored,
+            [
+                ("grep", Some(theme.command)),
+                ("-r", Some(theme.option)),
+                ("'a b'", Some(theme.string)),
+                ("{{path}}", Some(theme.placeholder)),
+                ("|", Some(theme.operator)),
+                ("sort", Some(theme.command)),
+                (">", Some(theme.operator)),
+                ("{{out.txt}}", Some(theme.placeholder)),
+            ]
+        );
+        let text: String = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, line);
+    }
+
+    #[test]
+    fn test_variables_and_paths() {
+        let theme = Theme::default();
+        let colored = |spans: Vec<Span>| -> Vec<(String, tui::style::Color)> {
+            spans
+                .iter()
+                .filter_map(|s| Some((s.content.to_string(), s.style.fg?)))
+                .collect()
+        };
+
+        let line = "Reads ${XDG_CONFIG_HOME}/rtfm or $HOME/.config, then /etc/rtfm.conf. Costs $1";
+        assert_eq!(
+            colored(syntax_highlight(line, LineRole::Normal, &theme)),
+            [
+                ("${XDG_CONFIG_HOME}/rtfm".to_string(), theme.path),
+                ("$HOME/.config".to_string(), theme.path),
+                ("/etc/rtfm.conf".to_string(), theme.path),
+            ]
+        );
+        let spans = syntax_highlight("~/.profile and a / alone", LineRole::Normal, &theme);
+        assert_eq!(colored(spans), [("~/.profile".to_string(), theme.path)]);
+        let spans = syntax_highlight("Set $EDITOR", LineRole::Normal, &theme);
+        assert_eq!(colored(spans), [("$EDITOR".to_string(), theme.variable)]);
+
+        let spans = shell_highlight("echo $1 $HOME/bin > /dev/null", &theme);
+        assert_eq!(
+            colored(spans),
+            [
+                ("echo".to_string(), theme.command),
+                ("$1".to_string(), theme.variable),
+                ("$HOME/bin".to_string(), theme.path),
+                (">".to_string(), theme.operator),
+                ("/dev/null".to_string(), theme.path),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truncated_index_marks_list_title() {
+        let mut app = mock_app();
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(80, 20)).unwrap();
+        terminal.draw(|f| render_ui(f, &mut app)).unwrap();
+        assert!(!buffer_text(&terminal).contains("Commands (first"));
+
+        app.provider = Arc::new(MockProvider {
+            commands: vec!["git".to_string(), "ls".to_string()],
+            truncated: true,
+        });
+        terminal.draw(|f| render_ui(f, &mut app)).unwrap();
+        assert!(buffer_text(&terminal).contains("Commands (first 2)"));
+    }
+
+    #[test]
+    fn test_hidden_preview_shows_content_while_searching() {
+        let mut app = mock_app_with(TuiOptions {
+            preview: PreviewPosition::None,
+            ..TuiOptions::default()
+        });
+        app.man_page.content = Arc::new(vec!["searched page".to_string()]);
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(80, 20)).unwrap();
+        terminal
+            .draw(|f| render_main_content(f, &mut app, Rect::new(0, 0, 80, 20)))
+            .unwrap();
+        assert!(!buffer_text(&terminal).contains("searched page"));
+
+        app.focus = Focus::Search;
+        terminal
+            .draw(|f| render_main_content(f, &mut app, Rect::new(0, 0, 80, 20)))
+            .unwrap();
+        assert!(buffer_text(&terminal).contains("searched page"));
+    }
+
+    #[test]
+    fn test_small_terminal_shows_message() {
+        let mut app = mock_app();
+        let mut terminal = Terminal::new(tui::backend::TestBackend::new(30, 6)).unwrap();
+        terminal.draw(|f| render_ui(f, &mut app)).unwrap();
+
+        let text = buffer_text(&terminal);
+        assert!(text.contains("Terminal too small"));
+    }
+
+    #[test]
+    fn test_line_numbers_cycle() {
+        let mut app = mock_app();
+        app.focus = Focus::ManPage;
+        press(&mut app, KeyCode::Char('#'));
+        assert_eq!(app.line_numbers, LineNumbers::Absolute);
+        press(&mut app, KeyCode::Char('#'));
+        assert_eq!(app.line_numbers, LineNumbers::Relative);
+        press(&mut app, KeyCode::Char('#'));
+        assert_eq!(app.line_numbers, LineNumbers::Off);
+
+        assert_eq!(gutter_label(LineNumbers::Absolute, 4, 9), "5");
+        assert_eq!(gutter_label(LineNumbers::Relative, 4, 9), "5");
+        assert_eq!(gutter_label(LineNumbers::Relative, 12, 9), "3");
+        assert_eq!(gutter_label(LineNumbers::Relative, 9, 9), "10");
+    }
+
+    #[test]
+    fn test_wrapped_line_count() {
+        assert_eq!(wrapped_line_count("", 10), 0);
+        assert_eq!(wrapped_line_count("copy files", 10), 1);
+        assert_eq!(wrapped_line_count("copy files and dirs", 10), 2);
+        assert_eq!(wrapped_line_count("abcdefghijklmnopqrstuvwxy z", 10), 3);
+    }
+
+    #[test]
+    fn test_toggle_mouse() {
+        let mut app = mock_app();
+        assert!(app.mouse_capture);
+        toggle_mouse(&mut app);
+        assert!(!app.mouse_capture);
+        assert!(app.notice.as_deref().unwrap().contains("off"));
+    }
+
+    #[test]
+    fn test_wrap_is_remembered_per_source() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::Enter);
+        app.focus = Focus::ManPage;
+        assert!(!wrap_enabled(&app));
+
+        press(&mut app, KeyCode::Right);
+        assert_eq!(app.man_page.h_scroll, H_SCROLL_STEP);
+
+        toggle_page_source(&mut app);
+        assert!(wrap_enabled(&app));
+        press(&mut app, KeyCode::Char('w'));
+        assert!(!wrap_enabled(&app));
+        assert_eq!(app.notice.as_deref(), Some("Wrapping off"));
+
+        toggle_page_source(&mut app);
+        toggle_page_source(&mut app);
+        assert!(!wrap_enabled(&app));
+
+        let config = Config::load(&app.paths.config_dir.join(CONFIG_FILE)).unwrap();
+        assert_eq!(config.wrap_tldr, Some(false));
+        std::fs::remove_dir_all(app.paths.config_dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_ascii_borders() {
+        let area = Rect::new(0, 0, 6, 3);
+        let mut buf = Buffer::empty(area);
+        pane_block("ls", false, &Theme::default()).render(area, &mut buf);
+        AsciiBorders.render(area, &mut buf);
+
+        let rows: Vec<String> = (0..3)
+            .map(|y| (0..6).map(|x| buf.get(x, y).symbol.clone()).collect())
+            .collect();
+        assert_eq!(rows, vec!["+ls--+", "|    |", "+----+"]);
+    }
+
+    #[test]
+    fn test_peek_shows_after_jump_until_next_key() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::Enter);
+        app.focus = Focus::ManPage;
+        app.search.query = "line 4".to_string();
+        update_search_matches(&mut app);
+
+        press(&mut app, KeyCode::Char('n'));
+        assert!(!app.show_peek);
+
+        press(&mut app, KeyCode::Char('p'));
+        press(&mut app, KeyCode::Char('n'));
+        assert!(app.show_peek);
+    }
+
+    #[test]
+    fn test_peek_range() {
+        assert_eq!(peek_range(10, 50), 8..13);
+        assert_eq!(peek_range(1, 50), 0..4);
+        assert_eq!(peek_range(49, 50), 47..50);
+    }
+
+    #[test]
+    fn test_list_scroll_keeps_margin() {
+        let mut app = mock_app();
+        app.command_list.filtered_commands = Arc::new((0..20).map(|i| i.to_string()).collect());
+        app.command_list.visible_range = (0, 8);
+
+        app.command_list.selected_idx = 5;
+        update_list_scroll(&mut app);
+        assert_eq!(app.command_list.list_scroll, 1);
+
+        app.command_list.selected_idx = 3;
+        update_list_scroll(&mut app);
+        assert_eq!(app.command_list.list_scroll, 0);
+
+        app.command_list.selected_idx = 19;
+        update_list_scroll(&mut app);
+        assert_eq!(app.command_list.list_scroll, 12);
+    }
+
+    #[test]
+    fn test_grid_navigation() {
+        let mut app = mock_app();
+        app.command_list.filtered_commands = Arc::new((0..20).map(|i| i.to_string()).collect());
+        app.command_list.columns = 4;
+        app.command_list.visible_range = (0, 12);
+
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.command_list.selected_idx, 5);
+        press(&mut app, KeyCode::Left);
+        assert_eq!(app.command_list.selected_idx, 4);
+
+        press(&mut app, KeyCode::End);
+        assert_eq!(app.command_list.list_scroll, 8);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.command_list.selected_idx, 19);
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.command_list.selected_idx, 15);
+    }
+
+    #[test]
+    fn test_tldr_template() {
+        assert_eq!(
+            tldr_template("      tar xf {{archive.tar}}"),
+            Some("tar xf {{archive.tar}}")
+        );
+        assert!(tldr_template("  - Extract an archive:").is_none());
+        assert!(tldr_template("  Archiving utility.").is_none());
+        assert!(tldr_template("").is_none());
+    }
+
+    #[test]
+    fn test_cursor_moves_and_confirms_example() {
+        let mut app = mock_app();
+        app.page_source = PageSource::Tldr;
+        app.man_page.content = Arc::new(vec![
+            "tar".to_string(),
+            "  - List an archive:".to_string(),
+            "      tar tf {{file}}".to_string(),
+        ]);
+        app.man_page.visible_height = 2;
+        app.focus = Focus::ManPage;
+
+        press(&mut app, KeyCode::Enter);
+        assert!(app.pending_run.is_none());
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.man_page.cursor, 2);
+        assert_eq!(app.man_page.scroll, 1);
+
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.focus, Focus::Placeholders));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.pending_run.as_deref(), Some("tar tf file"));
+    }
+
+    #[test]
+    fn test_placeholders_are_filled_in_order() {
+        let mut app = mock_app();
+        app.page_source = PageSource::Tldr;
+        app.man_page.content = Arc::new(vec!["    cp {{src}} {{dest}}".to_string()]);
+        app.focus = Focus::ManPage;
+
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char('a'));
+        press(&mut app, KeyCode::Enter);
+        assert!(app.pending_run.is_none());
+        for c in "b/".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.focus, Focus::ManPage));
+        assert_eq!(app.pending_run.as_deref(), Some("cp a b/"));
+
+        assert_eq!(
+            fill_placeholders("x {{a}} {{b}}", &[String::new()]),
+            "x a b"
+        );
+    }
+
+    #[test]
+    fn test_page_keys_move_cursor_and_view() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Enter);
+        app.focus = Focus::ManPage;
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::PageDown);
+        assert_eq!((app.man_page.scroll, app.man_page.cursor), (10, 11));
+
+        press(&mut app, KeyCode::End);
+        assert_eq!((app.man_page.scroll, app.man_page.cursor), (40, 49));
+
+        press(&mut app, KeyCode::PageDown);
+        assert_eq!((app.man_page.scroll, app.man_page.cursor), (40, 49));
+
+        press(&mut app, KeyCode::PageUp);
+        assert_eq!((app.man_page.scroll, app.man_page.cursor), (30, 39));
+
+        press(&mut app, KeyCode::Home);
+        assert_eq!((app.man_page.scroll, app.man_page.cursor), (0, 0));
+    }
+
+    #[test]
+    fn test_edit_and_save_note() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Enter);
+        app.focus = Focus::ManPage;
+        assert!(app.note.is_none());
+
+        press(&mut app, KeyCode::Char('e'));
+        assert!(matches!(app.focus, Focus::Notes));
+        for c in "a\tb".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char('x'));
+        block_on(handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL),
+        ));
+
+        assert!(matches!(app.focus, Focus::ManPage));
+        assert_eq!(app.note.as_deref(), Some("a\tb\nx"));
+        assert_eq!(app.notice.as_deref(), Some("Saved note for git"));
+
+        std::fs::remove_dir_all(app.paths.config_dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_prompts_ignore_control_chars() {
+        let mut app = mock_app();
+        let ctrl_x = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        app.focus = Focus::Notes;
+        block_on(handle_key(&mut app, ctrl_x));
+        assert!(app.note_draft.is_empty());
+
+        app.focus = Focus::Jump;
+        block_on(handle_key(&mut app, ctrl_x));
+        assert!(app.jump.query.is_empty());
+
+        app.focus = Focus::Placeholders;
+        block_on(handle_key(&mut app, ctrl_x));
+        assert!(app.fill.input.is_empty());
+    }
+
+    #[test]
+    fn test_cycle_section_of_open_page() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Enter);
+        app.focus = Focus::ManPage;
+        assert_eq!(content_title(&app), "git(1) [1 3 7]");
+
+        press(&mut app, KeyCode::Char(']'));
+        assert!(app.pending_man_load);
+        block_on(load_pending_page(&mut app));
+        assert_eq!(app.man_page.content[0], "git(3)");
+        press(&mut app, KeyCode::Char('['));
+        press(&mut app, KeyCode::Char('['));
+        block_on(load_pending_page(&mut app));
+        assert_eq!(app.man_page.content[0], "git(7)");
+        assert_eq!(content_title(&app), "git(7) [1 3 7]");
+
+        press(&mut app, KeyCode::Esc);
+        app.focus = Focus::CommandList;
+        press(&mut app, KeyCode::End);
+        press(&mut app, KeyCode::Enter);
+        app.focus = Focus::ManPage;
+        press(&mut app, KeyCode::Char(']'));
+        assert_eq!(app.notice.as_deref(), Some("No other sections document ls"));
+    }
+
+    #[test]
+    fn test_stale_tldr_hint() {
+        let dir = std::env::temp_dir().join(format!("rtfm-tldr-hint-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("tar.md"), "# tar").unwrap();
+
+        assert!(stale_tldr_hint(&dir, 30).is_none());
+
+        let old = std::time::SystemTime::now() - Duration::from_secs(45 * SECONDS_PER_DAY);
+        std::fs::File::options()
+            .write(true)
+            .open(dir.join("tar.md"))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        let hint = stale_tldr_hint(&dir, 30).unwrap();
+        assert!(hint.contains("are 45 days old"), "{hint}");
+        assert!(stale_tldr_hint(&dir, 60).is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(stale_tldr_hint(&dir, 30).is_none());
+    }
+
+    #[test]
+    fn test_column_rect() {
+        let area = Rect::new(10, 2, 100, 20);
+        assert_eq!(column_rect(area, 0), area);
+        assert_eq!(column_rect(area, 200), area);
+        assert_eq!(column_rect(area, 80), Rect::new(19, 2, 82, 20));
+    }
+
+    #[test]
+    fn test_follow_reference_and_go_back() {
+        let mut app = mock_app();
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Enter);
+        app.focus = Focus::ManPage;
+        app.man_page.content = Arc::new(vec![
+            "SEE ALSO".to_string(),
+            "       tig(1), gitk(1)".to_string(),
+        ]);
+
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(
+            app.notice.as_deref(),
+            Some("No man page reference on the cursor line")
+        );
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.focus, Focus::References));
+        assert_eq!(app.references.references.len(), 2);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.focus, Focus::ManPage));
+        assert_eq!(app.man_page.command.as_deref(), Some("gitk"));
+        assert_eq!(app.man_page.content[0], "gitk(1)");
+        assert_eq!(app.history.len(), 1);
+
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(app.man_page.command.as_deref(), Some("git"));
+        assert_eq!(app.man_page.content[0], "git(1)");
+        assert_eq!(app.man_page.cursor, 1);
+        assert!(app.history.is_empty());
+    }
+
+    #[test]
+    fn test_go_back_restores_page_source() {
+        let mut app = mock_app();
+        app.page_source = PageSource::Tldr;
+        press(&mut app, KeyCode::Enter);
+        app.focus = Focus::ManPage;
+        assert_eq!(app.man_page.content[0], "git tldr");
+
+        press(&mut app, KeyCode::Char('g'));
+        for c in "gitk".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.man_page.command.as_deref(), Some("gitk"));
+
+        app.page_source = PageSource::Man;
+        press(&mut app, KeyCode::Backspace);
+        assert!(matches!(app.page_source, PageSource::Tldr));
+        assert_eq!(app.man_page.content[0], "git tldr");
+    }
+}
@@ -0,0 +1,59 @@
+use crate::man_db::{ManDb, is_fresh};
+use anyhow::{Result, anyhow};
+use std::io::Write;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+
+/// Page of a command to render into the disk cache
+enum Page {
+    Man,
+    Tldr,
+}
+
+/// Renders the man and tldr pages of every command starting with prefix into the
+/// disk cache, skipping pages already cached and fresh. Returns how many were rendered
+pub async fn warm(man_db: Arc<ManDb>, prefix: &str) -> Result<usize> {
+    let commands = if prefix.is_empty() {
+        man_db.get_commands().clone()
+    } else {
+        man_db.commands_starting_with(prefix)
+    };
+    if commands.is_empty() {
+        return Err(anyhow!("No commands start with `{prefix}`"));
+    }
+
+    let stale = |path: Option<std::path::PathBuf>| path.is_some_and(|path| !is_fresh(&path));
+    let mut tasks = JoinSet::new();
+    for command in commands {
+        let mut pages = Vec::new();
+        if !man_db.tldr_only() && stale(man_db.man_cache_path(&command, None)) {
+            pages.push(Page::Man);
+        }
+        if stale(man_db.tldr_cache_path(&command)) {
+            pages.push(Page::Tldr);
+        }
+        for page in pages {
+            let (man_db, command) = (man_db.clone(), command.clone());
+            tasks.spawn(async move {
+                // ManDb caps how many `man` and `tldr` processes run at once, and
+                // scanned pages only go to the disk cache
+                match page {
+                    Page::Man => man_db.scan_man_page(&command).await,
+                    Page::Tldr => man_db.scan_tldr_page(&command).await,
+                };
+            });
+        }
+    }
+
+    let total = tasks.len();
+    let mut done = 0;
+    while tasks.join_next().await.is_some() {
+        done += 1;
+        eprint!("\rRendered {done}/{total} pages");
+        let _ = std::io::stderr().flush();
+    }
+    if total > 0 {
+        eprintln!();
+    }
+    Ok(total)
+}